@@ -0,0 +1,255 @@
+// The ABI encoding: a tuple is a head of 32-byte words, static values inline and dynamic ones as an
+// offset, followed by a tail holding the dynamic values. Offsets count from the start of the
+// tuple they're in.
+//   encode(&[AbiValue::uint(1u8), AbiValue::string("hi")])
+//     -> 0x...01 (the uint) | 0x...40 (offset of the string) | 0x...02 (length) | "hi" padded
+
+use alloy_primitives::{Address, I256, U256};
+
+use crate::abi::{AbiError, AbiType, AbiValue, value::fits_signed};
+
+pub fn encode(values: &[AbiValue]) -> Vec<u8> {
+    let mut out = Vec::new();
+    encode_sequence(values, &mut out);
+    out
+}
+
+// a selector followed by the arguments, what a call sends as calldata
+pub fn encode_with_selector(selector: [u8; 4], values: &[AbiValue]) -> Vec<u8> {
+    let mut out = selector.to_vec();
+    encode_sequence(values, &mut out);
+    out
+}
+
+pub fn decode(types: &[AbiType], data: &[u8]) -> Result<Vec<AbiValue>, AbiError> {
+    decode_sequence(types.iter(), data)
+}
+
+fn is_dynamic(value: &AbiValue) -> bool {
+    match value {
+        AbiValue::Bytes(_) | AbiValue::String(_) | AbiValue::Array(..) => true,
+        AbiValue::FixedArray(values) | AbiValue::Tuple(values) => values.iter().any(is_dynamic),
+        _ => false,
+    }
+}
+
+fn head_size(value: &AbiValue) -> usize {
+    match value {
+        _ if is_dynamic(value) => 32,
+        AbiValue::FixedArray(values) | AbiValue::Tuple(values) => {
+            values.iter().map(head_size).sum()
+        }
+        _ => 32,
+    }
+}
+
+fn word(value: U256) -> [u8; 32] {
+    value.to_be_bytes()
+}
+
+fn encode_sequence(values: &[AbiValue], out: &mut Vec<u8>) {
+    let start = out.len();
+    let mut tail_offset: usize = values.iter().map(head_size).sum();
+    let mut tail = Vec::new();
+    for value in values {
+        if is_dynamic(value) {
+            out.extend_from_slice(&word(U256::from(tail_offset)));
+            let before = tail.len();
+            encode_value(value, &mut tail);
+            tail_offset += tail.len() - before;
+        } else {
+            encode_value(value, out);
+        }
+    }
+    out.extend_from_slice(&tail);
+    debug_assert_eq!(out.len() - start, tail_offset);
+}
+
+fn encode_value(value: &AbiValue, out: &mut Vec<u8>) {
+    match value {
+        AbiValue::Uint(value, _) => out.extend_from_slice(&word(*value)),
+        AbiValue::Int(value, _) => out.extend_from_slice(&value.to_be_bytes::<32>()),
+        AbiValue::Address(address) => out.extend_from_slice(address.into_word().as_slice()),
+        AbiValue::Bool(value) => out.extend_from_slice(&word(U256::from(*value as u8))),
+        AbiValue::FixedBytes(bytes) => {
+            let mut padded = [0u8; 32];
+            padded[..bytes.len()].copy_from_slice(bytes);
+            out.extend_from_slice(&padded);
+        }
+        AbiValue::Bytes(bytes) => encode_bytes(bytes, out),
+        AbiValue::String(value) => encode_bytes(value.as_bytes(), out),
+        AbiValue::Array(_, values) => {
+            out.extend_from_slice(&word(U256::from(values.len())));
+            encode_sequence(values, out);
+        }
+        AbiValue::FixedArray(values) | AbiValue::Tuple(values) => encode_sequence(values, out),
+    }
+}
+
+fn encode_bytes(bytes: &[u8], out: &mut Vec<u8>) {
+    out.extend_from_slice(&word(U256::from(bytes.len())));
+    out.extend_from_slice(bytes);
+    out.resize(out.len() + bytes.len().next_multiple_of(32) - bytes.len(), 0);
+}
+
+fn read_word(data: &[u8], at: usize) -> Result<&[u8; 32], AbiError> {
+    data.get(at..at.checked_add(32).ok_or(AbiError::OutOfBounds)?)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(AbiError::OutOfBounds)
+}
+
+// an offset or a length, anything that doesn't fit the data can't be right
+fn read_usize(data: &[u8], at: usize) -> Result<usize, AbiError> {
+    let value = U256::from_be_bytes(*read_word(data, at)?);
+    usize::try_from(value)
+        .ok()
+        .filter(|value| *value <= data.len())
+        .ok_or(AbiError::OutOfBounds)
+}
+
+fn decode_sequence<'a>(
+    types: impl Iterator<Item = &'a AbiType>,
+    data: &[u8],
+) -> Result<Vec<AbiValue>, AbiError> {
+    let mut values = Vec::new();
+    let mut head = 0;
+    for ty in types {
+        let value = if ty.is_dynamic() {
+            let offset = read_usize(data, head)?;
+            decode_value(ty, &data[offset..])?
+        } else {
+            decode_value(ty, data.get(head..).ok_or(AbiError::OutOfBounds)?)?
+        };
+        values.push(value);
+        head += ty.head_size();
+    }
+    Ok(values)
+}
+
+// `data` starts where the value is encoded
+fn decode_value(ty: &AbiType, data: &[u8]) -> Result<AbiValue, AbiError> {
+    let invalid = || AbiError::InvalidValue(ty.clone());
+    Ok(match ty {
+        AbiType::Uint(bits) => {
+            let value = U256::from_be_bytes(*read_word(data, 0)?);
+            if value.bit_len() > *bits {
+                return Err(invalid());
+            }
+            AbiValue::Uint(value, *bits)
+        }
+        AbiType::Int(bits) => {
+            let value = I256::from_be_bytes(*read_word(data, 0)?);
+            if !fits_signed(value, *bits) {
+                return Err(invalid());
+            }
+            AbiValue::Int(value, *bits)
+        }
+        AbiType::Address => {
+            let word = read_word(data, 0)?;
+            if word[..12].iter().any(|byte| *byte != 0) {
+                return Err(invalid());
+            }
+            AbiValue::Address(Address::from_slice(&word[12..]))
+        }
+        AbiType::Bool => match U256::from_be_bytes(*read_word(data, 0)?) {
+            value if value.is_zero() => AbiValue::Bool(false),
+            value if value == U256::from(1) => AbiValue::Bool(true),
+            _ => return Err(invalid()),
+        },
+        AbiType::FixedBytes(size) => AbiValue::FixedBytes(read_word(data, 0)?[..*size].to_vec()),
+        AbiType::Bytes => AbiValue::Bytes(decode_bytes(data)?.to_vec()),
+        AbiType::String => AbiValue::String(
+            String::from_utf8(decode_bytes(data)?.to_vec()).map_err(|_| invalid())?,
+        ),
+        AbiType::Array(inner) => {
+            let length = read_usize(data, 0)?;
+            let values = decode_sequence(std::iter::repeat_n(&**inner, length), &data[32..])?;
+            AbiValue::Array((**inner).clone(), values)
+        }
+        AbiType::FixedArray(inner, length) => {
+            AbiValue::FixedArray(decode_sequence(std::iter::repeat_n(&**inner, *length), data)?)
+        }
+        AbiType::Tuple(types) => AbiValue::Tuple(decode_sequence(types.iter(), data)?),
+    })
+}
+
+fn decode_bytes(data: &[u8]) -> Result<&[u8], AbiError> {
+    let length = read_usize(data, 0)?;
+    data.get(32..32 + length).ok_or(AbiError::OutOfBounds)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{address, hex};
+
+    use super::*;
+
+    fn words(hex: &str) -> Vec<u8> {
+        hex::decode(hex.split_whitespace().collect::<String>()).unwrap()
+    }
+
+    #[test]
+    fn test_encode_static_and_dynamic() {
+        // the example of the Solidity docs: f(uint256,uint32[],bytes10,bytes)
+        // with (0x123, [0x456, 0x789], "1234567890", "Hello, world!")
+        let values = [
+            AbiValue::uint(U256::from(0x123)),
+            AbiValue::Array(
+                AbiType::Uint(32),
+                vec![AbiValue::Uint(U256::from(0x456), 32), AbiValue::Uint(U256::from(0x789), 32)],
+            ),
+            AbiValue::FixedBytes(b"1234567890".to_vec()),
+            AbiValue::Bytes(b"Hello, world!".to_vec()),
+        ];
+        let expected = words(
+            "0000000000000000000000000000000000000000000000000000000000000123
+             0000000000000000000000000000000000000000000000000000000000000080
+             3132333435363738393000000000000000000000000000000000000000000000
+             00000000000000000000000000000000000000000000000000000000000000e0
+             0000000000000000000000000000000000000000000000000000000000000002
+             0000000000000000000000000000000000000000000000000000000000000456
+             0000000000000000000000000000000000000000000000000000000000000789
+             000000000000000000000000000000000000000000000000000000000000000d
+             48656c6c6f2c20776f726c642100000000000000000000000000000000000000",
+        );
+        assert_eq!(encode(&values), expected);
+
+        let types: Vec<AbiType> =
+            ["uint256", "uint32[]", "bytes10", "bytes"].map(|ty| ty.parse().unwrap()).to_vec();
+        assert_eq!(decode(&types, &expected).unwrap(), values);
+    }
+
+    #[test]
+    fn test_nested_dynamic_round_trip() {
+        let ty: AbiType = "(address,string[],int8)[]".parse().unwrap();
+        let AbiType::Array(inner) = &ty else { unreachable!() };
+        let element = |name: &str, n: i8| {
+            AbiValue::Tuple(vec![
+                address!("0x5B38Da6a701c568545dCfcB03FcB875f56beddC4").into(),
+                AbiValue::Array(AbiType::String, vec![name.into(), "x".into()]),
+                AbiValue::Int(I256::try_from(n).unwrap(), 8),
+            ])
+        };
+        let value = AbiValue::Array((**inner).clone(), vec![element("a", -1), element("bc", 7)]);
+        assert!(value.matches(&ty));
+        let encoded = encode(std::slice::from_ref(&value));
+        assert_eq!(decode(&[ty], &encoded).unwrap(), vec![value]);
+    }
+
+    #[test]
+    fn test_decode_rejects_bad_data() {
+        let one = words("0000000000000000000000000000000000000000000000000000000000000001");
+        let two = words("0000000000000000000000000000000000000000000000000000000000000002");
+        assert!(matches!(decode(&[AbiType::Bool], &two), Err(AbiError::InvalidValue(_))));
+        assert!(matches!(decode(&[AbiType::Uint(1)], &two), Err(AbiError::InvalidValue(_))));
+        let short = decode(&[AbiType::Uint(8), AbiType::Bool], &one);
+        assert!(matches!(short, Err(AbiError::OutOfBounds)));
+        // an offset past the end of the data
+        assert!(matches!(decode(&[AbiType::String], &two), Err(AbiError::OutOfBounds)));
+        let minus_one = [0xff; 32];
+        assert_eq!(
+            decode(&[AbiType::Int(8)], &minus_one).unwrap(),
+            vec![AbiValue::Int(I256::MINUS_ONE, 8)]
+        );
+    }
+}
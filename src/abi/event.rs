@@ -0,0 +1,242 @@
+// Events, what LOG0 to LOG4 emit. The indexed parameters are the topics, the others are encoded
+// together as the data, and topic0 is the keccak256 of the signature:
+//   event Transfer(address indexed from, address indexed to, uint256 value)
+//   -> topics [keccak256("Transfer(address,address,uint256)"), from, to], data abi.encode(value)
+// An `anonymous` event has no topic0, every topic is one of its parameters.
+// An indexed string, bytes, array or struct is only there as the keccak256 of its encoding, it
+// decodes to that hash as a `bytes32`.
+
+use std::fmt;
+
+use alloy_primitives::{B256, keccak256};
+
+use crate::{
+    abi::{
+        AbiError, AbiType, AbiValue, coder,
+        types::{join, parse_signature},
+    },
+    evm::Log,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventParam {
+    pub name: String,
+    pub ty: AbiType,
+    pub indexed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub name: String,
+    pub inputs: Vec<EventParam>,
+    pub anonymous: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedParam {
+    pub name: String,
+    pub indexed: bool,
+    pub value: AbiValue,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedEvent {
+    pub name: String,
+    // in the order of the declaration, indexed or not
+    pub params: Vec<DecodedParam>,
+}
+
+impl DecodedEvent {
+    pub fn param(&self, name: &str) -> Option<&AbiValue> {
+        self.params.iter().find(|param| param.name == name).map(|param| &param.value)
+    }
+}
+
+// e.g. `Transfer(from: 0x5B38..., to: 0xAb84..., value: 100)`
+impl fmt::Display for DecodedEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let params: Vec<_> = self
+            .params
+            .iter()
+            .map(|param| match param.name.as_str() {
+                "" => param.value.to_string(),
+                name => format!("{}: {}", name, param.value),
+            })
+            .collect();
+        write!(f, "{}({})", self.name, params.join(", "))
+    }
+}
+
+// the types whose indexed values are hashed rather than stored
+fn is_hashed(ty: &AbiType) -> bool {
+    matches!(
+        ty,
+        AbiType::Bytes
+            | AbiType::String
+            | AbiType::Array(_)
+            | AbiType::FixedArray(..)
+            | AbiType::Tuple(_)
+    )
+}
+
+impl Event {
+    // `Name(types)`, optionally with `event`, parameter names, `indexed` and `anonymous`
+    pub fn parse(signature: &str) -> Result<Self, AbiError> {
+        let parsed = parse_signature(signature)?;
+        let anonymous = parsed.modifiers.iter().any(|modifier| modifier == "anonymous");
+        if parsed.kind.as_deref().is_some_and(|kind| kind != "event")
+            || !parsed.outputs.is_empty()
+            || parsed.modifiers.len() > anonymous as usize
+        {
+            return Err(AbiError::InvalidSignature {
+                signature: signature.to_string(),
+                position: 0,
+            });
+        }
+        let inputs = parsed
+            .inputs
+            .into_iter()
+            .zip(parsed.indexed)
+            .map(|(param, indexed)| EventParam {
+                name: param.name,
+                ty: param.ty,
+                indexed,
+            })
+            .collect();
+        Ok(Event {
+            name: parsed.name,
+            inputs,
+            anonymous,
+        })
+    }
+
+    pub fn signature(&self) -> String {
+        let types: Vec<_> = self.inputs.iter().map(|param| param.ty.clone()).collect();
+        format!("{}({})", self.name, join(&types))
+    }
+
+    // the topic0 of its logs, unless it is anonymous
+    pub fn topic0(&self) -> B256 {
+        keccak256(self.signature())
+    }
+
+    pub fn decode(&self, topics: &[B256], data: &[u8]) -> Result<DecodedEvent, AbiError> {
+        let mut topics = topics.iter();
+        if !self.anonymous {
+            let expected = self.topic0();
+            let first = topics.next();
+            if first != Some(&expected) {
+                return Err(AbiError::TopicMismatch {
+                    expected,
+                    got: first.copied(),
+                });
+            }
+        }
+        let indexed = self.inputs.iter().filter(|param| param.indexed).count();
+        if topics.len() != indexed {
+            return Err(AbiError::WrongTopicCount {
+                expected: indexed + !self.anonymous as usize,
+                got: topics.len() + !self.anonymous as usize,
+            });
+        }
+
+        let data_types: Vec<_> = self
+            .inputs
+            .iter()
+            .filter(|param| !param.indexed)
+            .map(|param| param.ty.clone())
+            .collect();
+        let mut data_values = coder::decode(&data_types, data)?.into_iter();
+        let mut params = Vec::new();
+        for param in &self.inputs {
+            let value = if !param.indexed {
+                data_values.next().ok_or(AbiError::OutOfBounds)?
+            } else {
+                let topic = topics.next().ok_or(AbiError::OutOfBounds)?;
+                if is_hashed(&param.ty) {
+                    AbiValue::FixedBytes(topic.to_vec())
+                } else {
+                    coder::decode(std::slice::from_ref(&param.ty), topic.as_slice())?
+                        .pop()
+                        .ok_or(AbiError::OutOfBounds)?
+                }
+            };
+            params.push(DecodedParam {
+                name: param.name.clone(),
+                indexed: param.indexed,
+                value,
+            });
+        }
+        Ok(DecodedEvent {
+            name: self.name.clone(),
+            params,
+        })
+    }
+
+    pub fn decode_log(&self, log: &Log) -> Result<DecodedEvent, AbiError> {
+        self.decode(&log_topics(log), &log.data)
+    }
+}
+
+pub(crate) fn log_topics(log: &Log) -> Vec<B256> {
+    log.topics.iter().map(|topic| B256::from(*topic)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Address, U256, hex};
+
+    use super::*;
+
+    fn transfer() -> Event {
+        Event::parse("event Transfer(address indexed from, address indexed to, uint256 value)")
+            .unwrap()
+    }
+
+    fn topic(address: Address) -> U256 {
+        U256::from_be_slice(address.as_slice())
+    }
+
+    #[test]
+    fn test_decode_transfer() {
+        let event = transfer();
+        assert_eq!(
+            event.topic0(),
+            B256::from(hex!("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"))
+        );
+        let (from, to) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let log = Log::new(
+            Address::ZERO,
+            U256::from(100).to_be_bytes::<32>().to_vec(),
+            vec![U256::from_be_bytes(event.topic0().0), topic(from), topic(to)],
+        );
+        let decoded = event.decode_log(&log).unwrap();
+        assert_eq!(decoded.param("from"), Some(&AbiValue::Address(from)));
+        assert_eq!(decoded.param("value"), Some(&AbiValue::uint(100u64)));
+        assert!(decoded.params[1].indexed && !decoded.params[2].indexed);
+        let expected = format!("Transfer(from: {}, to: {}, value: 100)", from, to);
+        assert_eq!(decoded.to_string(), expected);
+
+        // the topic of another event, or a missing topic
+        let approval = Event::parse("Approval(address indexed, address indexed, uint256)").unwrap();
+        assert!(matches!(approval.decode_log(&log), Err(AbiError::TopicMismatch { .. })));
+        let short = Log::new(Address::ZERO, log.data.clone(), log.topics[..2].to_vec());
+        assert!(matches!(
+            event.decode_log(&short),
+            Err(AbiError::WrongTopicCount { expected: 3, got: 2 })
+        ));
+    }
+
+    #[test]
+    fn test_anonymous_event_and_hashed_topics() {
+        let event =
+            Event::parse("event Named(string indexed name, uint8 indexed n) anonymous").unwrap();
+        assert!(event.anonymous);
+        let hash = keccak256("alice");
+        let log = Log::new(Address::ZERO, vec![], vec![U256::from_be_bytes(hash.0), U256::from(7)]);
+        let decoded = event.decode_log(&log).unwrap();
+        assert_eq!(decoded.param("name"), Some(&AbiValue::FixedBytes(hash.to_vec())));
+        assert_eq!(decoded.param("n"), Some(&AbiValue::Uint(U256::from(7), 8)));
+        assert!(Event::parse("event E(uint256) view").is_err());
+    }
+}
@@ -0,0 +1,208 @@
+// Functions and custom errors: a name and parameters, identified by the first four bytes of the
+// keccak256 of their canonical signature, e.g. `transfer(address,uint256)` -> 0xa9059cbb.
+//   let transfer = Function::parse("transfer(address to, uint256 amount) returns (bool)")?;
+//   let calldata = transfer.encode_input(&[to.into(), AbiValue::uint(100u64)])?;
+//   let ok = transfer.decode_output(&evm.output)?;
+
+use alloy_primitives::keccak256;
+
+use crate::abi::{
+    AbiError, AbiType, AbiValue, Param, coder,
+    types::{join, parse_signature},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    pub name: String,
+    pub inputs: Vec<Param>,
+    pub outputs: Vec<Param>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomError {
+    pub name: String,
+    pub inputs: Vec<Param>,
+}
+
+// the first four bytes of the hash of a canonical signature
+pub fn selector(signature: &str) -> [u8; 4] {
+    keccak256(signature)[..4].try_into().unwrap()
+}
+
+fn types(params: &[Param]) -> Vec<AbiType> {
+    params.iter().map(|param| param.ty.clone()).collect()
+}
+
+// the arguments, with their integers sized like the parameters, if every one fits its parameter
+fn check_args(params: &[Param], args: &[AbiValue]) -> Result<Vec<AbiValue>, AbiError> {
+    if params.len() != args.len() {
+        return Err(AbiError::WrongArgumentCount {
+            expected: params.len(),
+            got: args.len(),
+        });
+    }
+    params
+        .iter()
+        .zip(args)
+        .enumerate()
+        .map(|(index, (param, arg))| {
+            if arg.matches(&param.ty) {
+                Ok(arg.clone().cast(&param.ty))
+            } else {
+                Err(AbiError::TypeMismatch {
+                    index,
+                    expected: param.ty.clone(),
+                    value: arg.clone(),
+                })
+            }
+        })
+        .collect()
+}
+
+fn strip_selector(expected: [u8; 4], data: &[u8]) -> Result<&[u8], AbiError> {
+    data.strip_prefix(&expected).ok_or_else(|| AbiError::SelectorMismatch {
+        expected,
+        got: data[..data.len().min(4)].to_vec(),
+    })
+}
+
+impl Function {
+    // `name(types)`, optionally with `function`, parameter names, modifiers and `returns (types)`
+    pub fn parse(signature: &str) -> Result<Self, AbiError> {
+        let parsed = parse_signature(signature)?;
+        if parsed.kind.as_deref().is_some_and(|kind| kind != "function")
+            || parsed.indexed.contains(&true)
+        {
+            return Err(AbiError::InvalidSignature {
+                signature: signature.to_string(),
+                position: 0,
+            });
+        }
+        Ok(Function {
+            name: parsed.name,
+            inputs: parsed.inputs,
+            outputs: parsed.outputs,
+        })
+    }
+
+    pub fn signature(&self) -> String {
+        format!("{}({})", self.name, join(&types(&self.inputs)))
+    }
+
+    pub fn selector(&self) -> [u8; 4] {
+        selector(&self.signature())
+    }
+
+    // the calldata of a call with these arguments
+    pub fn encode_input(&self, args: &[AbiValue]) -> Result<Vec<u8>, AbiError> {
+        Ok(coder::encode_with_selector(self.selector(), &check_args(&self.inputs, args)?))
+    }
+
+    pub fn decode_input(&self, calldata: &[u8]) -> Result<Vec<AbiValue>, AbiError> {
+        coder::decode(&types(&self.inputs), strip_selector(self.selector(), calldata)?)
+    }
+
+    pub fn encode_output(&self, values: &[AbiValue]) -> Result<Vec<u8>, AbiError> {
+        Ok(coder::encode(&check_args(&self.outputs, values)?))
+    }
+
+    pub fn decode_output(&self, data: &[u8]) -> Result<Vec<AbiValue>, AbiError> {
+        coder::decode(&types(&self.outputs), data)
+    }
+}
+
+impl CustomError {
+    // `Name(types)`, optionally with `error` and parameter names
+    pub fn parse(signature: &str) -> Result<Self, AbiError> {
+        let parsed = parse_signature(signature)?;
+        if parsed.kind.as_deref().is_some_and(|kind| kind != "error")
+            || parsed.indexed.contains(&true)
+            || !parsed.outputs.is_empty()
+        {
+            return Err(AbiError::InvalidSignature {
+                signature: signature.to_string(),
+                position: 0,
+            });
+        }
+        Ok(CustomError {
+            name: parsed.name,
+            inputs: parsed.inputs,
+        })
+    }
+
+    pub fn signature(&self) -> String {
+        format!("{}({})", self.name, join(&types(&self.inputs)))
+    }
+
+    pub fn selector(&self) -> [u8; 4] {
+        selector(&self.signature())
+    }
+
+    // the revert data of `revert Name(args)`
+    pub fn encode(&self, args: &[AbiValue]) -> Result<Vec<u8>, AbiError> {
+        Ok(coder::encode_with_selector(self.selector(), &check_args(&self.inputs, args)?))
+    }
+
+    pub fn decode(&self, data: &[u8]) -> Result<Vec<AbiValue>, AbiError> {
+        coder::decode(&types(&self.inputs), strip_selector(self.selector(), data)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{U256, address, hex};
+
+    use super::*;
+
+    #[test]
+    fn test_function_calldata() {
+        let transfer =
+            Function::parse("transfer(address to, uint256 amount) returns (bool)").unwrap();
+        assert_eq!(transfer.signature(), "transfer(address,uint256)");
+        assert_eq!(transfer.selector(), hex!("a9059cbb"));
+
+        let to = address!("0x00000000000000000000000000000000000000aa");
+        let calldata = transfer.encode_input(&[to.into(), AbiValue::uint(100u64)]).unwrap();
+        assert_eq!(calldata.len(), 4 + 64);
+        assert_eq!(
+            transfer.decode_input(&calldata).unwrap(),
+            vec![to.into(), AbiValue::uint(100u64)]
+        );
+        let output = transfer.encode_output(&[true.into()]).unwrap();
+        assert_eq!(transfer.decode_output(&output).unwrap(), vec![true.into()]);
+    }
+
+    #[test]
+    fn test_arguments_are_checked() {
+        let f = Function::parse("f(uint8,string)").unwrap();
+        assert!(matches!(
+            f.encode_input(&[AbiValue::uint(1u64)]),
+            Err(AbiError::WrongArgumentCount { expected: 2, got: 1 })
+        ));
+        // 256 doesn't fit a uint8
+        assert!(matches!(
+            f.encode_input(&[AbiValue::uint(256u64), "x".into()]),
+            Err(AbiError::TypeMismatch { index: 0, .. })
+        ));
+        let calldata = f.encode_input(&[AbiValue::uint(255u64), "x".into()]).unwrap();
+        assert_eq!(
+            f.decode_input(&calldata).unwrap()[0],
+            AbiValue::Uint(U256::from(255), 8)
+        );
+        assert!(matches!(
+            f.decode_input(&[0xde, 0xad]),
+            Err(AbiError::SelectorMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_custom_error() {
+        let error =
+            CustomError::parse("error InsufficientBalance(uint256 available, uint256 required)")
+                .unwrap();
+        assert_eq!(error.signature(), "InsufficientBalance(uint256,uint256)");
+        let data = error.encode(&[AbiValue::uint(1u64), AbiValue::uint(2u64)]).unwrap();
+        assert_eq!(error.decode(&data).unwrap()[1], AbiValue::uint(2u64));
+        assert!(CustomError::parse("function f()").is_err());
+    }
+}
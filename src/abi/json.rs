@@ -0,0 +1,263 @@
+// A contract ABI, read from the JSON solc writes with `--abi` (or the `abi` field of a Hardhat or
+// Foundry artifact), or from a list of human-readable signatures:
+//   [{ "type": "function", "name": "transfer",
+//      "inputs": [{ "name": "to", "type": "address" }, { "name": "amount", "type": "uint256" }],
+//      "outputs": [{ "name": "", "type": "bool" }], "stateMutability": "nonpayable" }]
+// Tuples are `"type": "tuple"` (or `tuple[]`, `tuple[2]`, ...) with their members in `components`.
+// Events mark their parameters `"indexed": true` and may be `"anonymous": true`. Constructors,
+// fallbacks and anything else that isn't a function, event or error are skipped.
+
+use std::{fs, path::Path};
+
+use serde::Deserialize;
+
+use crate::{
+    abi::{
+        AbiError, AbiType, CustomError, DecodedEvent, Event, EventParam, Function, Param, Revert,
+        event::log_topics,
+        types::{join, parse_signature},
+    },
+    evm::Log,
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum JsonAbi {
+    Items(Vec<JsonItem>),
+    Artifact { abi: Vec<JsonItem> },
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonItem {
+    #[serde(rename = "type", default = "function_kind")]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    inputs: Vec<JsonParam>,
+    #[serde(default)]
+    outputs: Vec<JsonParam>,
+    #[serde(default)]
+    anonymous: bool,
+}
+
+fn function_kind() -> String {
+    "function".to_string()
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonParam {
+    #[serde(default)]
+    name: String,
+    #[serde(rename = "type")]
+    ty: String,
+    #[serde(default)]
+    components: Vec<JsonParam>,
+    #[serde(default)]
+    indexed: bool,
+}
+
+impl JsonParam {
+    fn ty(&self) -> Result<AbiType, AbiError> {
+        match self.ty.strip_prefix("tuple") {
+            // the members, then whatever array suffix the tuple has
+            Some(suffix) => {
+                let members =
+                    self.components.iter().map(JsonParam::ty).collect::<Result<Vec<_>, _>>()?;
+                format!("({}){}", join(&members), suffix).parse()
+            }
+            None => self.ty.parse(),
+        }
+    }
+
+    fn param(&self) -> Result<Param, AbiError> {
+        Ok(Param {
+            name: self.name.clone(),
+            ty: self.ty()?,
+        })
+    }
+
+    fn event_param(&self) -> Result<EventParam, AbiError> {
+        Ok(EventParam {
+            name: self.name.clone(),
+            ty: self.ty()?,
+            indexed: self.indexed,
+        })
+    }
+}
+
+fn params(params: &[JsonParam]) -> Result<Vec<Param>, AbiError> {
+    params.iter().map(JsonParam::param).collect()
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Abi {
+    pub functions: Vec<Function>,
+    pub events: Vec<Event>,
+    pub errors: Vec<CustomError>,
+}
+
+impl Abi {
+    pub fn from_json(json: &str) -> Result<Self, AbiError> {
+        let items = match serde_json::from_str(json).map_err(AbiError::Json)? {
+            JsonAbi::Items(items) | JsonAbi::Artifact { abi: items } => items,
+        };
+        let mut abi = Abi::default();
+        for item in items {
+            match item.kind.as_str() {
+                "function" => abi.functions.push(Function {
+                    name: item.name,
+                    inputs: params(&item.inputs)?,
+                    outputs: params(&item.outputs)?,
+                }),
+                "event" => abi.events.push(Event {
+                    name: item.name,
+                    inputs: item
+                        .inputs
+                        .iter()
+                        .map(JsonParam::event_param)
+                        .collect::<Result<_, _>>()?,
+                    anonymous: item.anonymous,
+                }),
+                "error" => abi.errors.push(CustomError {
+                    name: item.name,
+                    inputs: params(&item.inputs)?,
+                }),
+                _ => {}
+            }
+        }
+        Ok(abi)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, AbiError> {
+        Self::from_json(&fs::read_to_string(path).map_err(AbiError::Io)?)
+    }
+
+    // one signature per item, `function ...`, `event ...` or `error ...`, functions may leave out
+    // the keyword
+    pub fn parse<'a>(signatures: impl IntoIterator<Item = &'a str>) -> Result<Self, AbiError> {
+        let mut abi = Abi::default();
+        for signature in signatures {
+            match parse_signature(signature)?.kind.as_deref() {
+                Some("event") => abi.events.push(Event::parse(signature)?),
+                Some("error") => abi.errors.push(CustomError::parse(signature)?),
+                _ => abi.functions.push(Function::parse(signature)?),
+            }
+        }
+        Ok(abi)
+    }
+
+    // by name, or by signature when the name is overloaded
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        if name.contains('(') {
+            return self.functions.iter().find(|function| function.signature() == name);
+        }
+        self.functions.iter().find(|function| function.name == name)
+    }
+
+    // the function some calldata is for
+    pub fn function_by_selector(&self, selector: [u8; 4]) -> Option<&Function> {
+        self.functions.iter().find(|function| function.selector() == selector)
+    }
+
+    pub fn event(&self, name: &str) -> Option<&Event> {
+        self.events.iter().find(|event| event.name == name)
+    }
+
+    // the event whose topic0 the log starts with, or else an anonymous event that decodes it
+    pub fn decode_log(&self, log: &Log) -> Option<DecodedEvent> {
+        let topics = log_topics(log);
+        let by_topic0 = self
+            .events
+            .iter()
+            .filter(|event| !event.anonymous && topics.first() == Some(&event.topic0()));
+        let anonymous = self.events.iter().filter(|event| event.anonymous);
+        by_topic0.chain(anonymous).find_map(|event| event.decode(&topics, &log.data).ok())
+    }
+
+    pub fn error(&self, name: &str) -> Option<&CustomError> {
+        self.errors.iter().find(|error| error.name == name)
+    }
+
+    pub fn decode_revert(&self, data: &[u8]) -> Revert {
+        Revert::decode(data, &self.errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Address, U256};
+
+    use super::*;
+    use crate::abi::AbiValue;
+
+    const JSON: &str = r#"[
+        { "type": "constructor", "inputs": [] },
+        { "type": "function", "name": "transfer", "stateMutability": "nonpayable",
+          "inputs": [{ "name": "to", "type": "address" }, { "name": "amount", "type": "uint256" }],
+          "outputs": [{ "name": "", "type": "bool" }] },
+        { "type": "function", "name": "submit", "stateMutability": "nonpayable",
+          "inputs": [{ "name": "orders", "type": "tuple[]", "components": [
+              { "name": "id", "type": "uint64" },
+              { "name": "tags", "type": "string[]" } ] }],
+          "outputs": [] },
+        { "type": "error", "name": "Unauthorized",
+          "inputs": [{ "name": "who", "type": "address" }] },
+        { "type": "event", "name": "Transfer", "anonymous": false, "inputs": [
+              { "name": "from", "type": "address", "indexed": true },
+              { "name": "to", "type": "address", "indexed": true },
+              { "name": "value", "type": "uint256", "indexed": false } ] }
+    ]"#;
+
+    #[test]
+    fn test_from_json() {
+        let abi = Abi::from_json(JSON).unwrap();
+        assert_eq!(abi.functions.len(), 2);
+        assert_eq!(abi.function("submit").unwrap().signature(), "submit((uint64,string[])[])");
+        let transfer = abi.function("transfer(address,uint256)").unwrap();
+        assert_eq!(abi.function_by_selector(transfer.selector()), Some(transfer));
+        assert_eq!(abi.error("Unauthorized").unwrap().signature(), "Unauthorized(address)");
+        let transfer_event = abi.event("Transfer").unwrap();
+        assert_eq!(transfer_event.signature(), "Transfer(address,address,uint256)");
+        assert!(transfer_event.inputs[0].indexed && !transfer_event.inputs[2].indexed);
+
+        // the same ABI inside an artifact
+        let artifact = format!(r#"{{ "contractName": "Token", "abi": {} }}"#, JSON);
+        assert_eq!(Abi::from_json(&artifact).unwrap(), abi);
+    }
+
+    #[test]
+    fn test_decode_log_picks_the_event() {
+        let abi = Abi::parse([
+            "event Approval(address indexed owner, address indexed spender, uint256 value)",
+            "event Transfer(address indexed from, address indexed to, uint256 value)",
+            "event Ping(uint256 n) anonymous",
+        ])
+        .unwrap();
+        let topic0 = U256::from_be_bytes(abi.events[1].topic0().0);
+        let transfer = Log::new(
+            Address::ZERO,
+            vec![0; 32],
+            vec![topic0, U256::from(1), U256::from(2)],
+        );
+        assert_eq!(abi.decode_log(&transfer).unwrap().name, "Transfer");
+        // no topics, only the anonymous event fits
+        let ping = Log::new(Address::ZERO, vec![0; 32], vec![]);
+        assert_eq!(abi.decode_log(&ping).unwrap().to_string(), "Ping(n: 0)");
+        let unknown = Log::new(Address::ZERO, vec![], vec![topic0]);
+        assert_eq!(abi.decode_log(&unknown), None);
+    }
+
+    #[test]
+    fn test_human_readable_abi_decodes_custom_reverts() {
+        let abi = Abi::parse([
+            "function balanceOf(address) view returns (uint256)",
+            "error Unauthorized(address who)",
+        ])
+        .unwrap();
+        assert_eq!(abi.functions[0].outputs[0].ty, AbiType::Uint(256));
+        let data = abi.errors[0].encode(&[Address::ZERO.into()]).unwrap();
+        assert!(matches!(abi.decode_revert(&data), Revert::Custom { args, .. }
+            if args == vec![AbiValue::Address(Address::ZERO)]));
+    }
+}
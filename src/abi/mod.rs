@@ -0,0 +1,55 @@
+// The Solidity ABI: calldata for a function call, the values in return data and what a revert
+// says.
+//   types -> `AbiType` and the parser for human-readable types and signatures
+//   value -> `AbiValue`, a value of some `AbiType`
+//   coder -> the encoding itself, `encode` / `decode` of a list of values
+//   function -> `Function` and `CustomError`, signatures with their selectors
+//   event -> `Event`, decoding logs into `DecodedEvent`s
+//   json -> `Abi`, the functions, events and errors of a contract from its JSON ABI
+//   revert -> `Revert`, `Error(string)`, `Panic(uint256)` or a custom error
+// e.g. calling `transfer` on a token and reading the result:
+//   let transfer = Function::parse("transfer(address,uint256) returns (bool)")?;
+//   let calldata = transfer.encode_input(&[to.into(), AbiValue::uint(100u64)])?;
+//   let mut evm = EVM::new(sender, code, gas, U256::ZERO, calldata);
+//   evm.run()?;
+//   if evm.revert_flag { println!("{}", abi.decode_revert(&evm.output)) }
+//   else { let [ok] = &transfer.decode_output(&evm.output)?[..] else { .. }; }
+
+pub mod coder;
+pub mod event;
+pub mod function;
+pub mod json;
+pub mod revert;
+pub mod types;
+pub mod value;
+
+pub use coder::{decode, encode, encode_with_selector};
+pub use event::{DecodedEvent, DecodedParam, Event, EventParam};
+pub use function::{CustomError, Function, selector};
+pub use json::Abi;
+pub use revert::{ERROR_SELECTOR, PANIC_SELECTOR, PanicCode, Revert};
+pub use types::{AbiType, Param};
+pub use value::AbiValue;
+
+use alloy_primitives::B256;
+
+#[derive(Debug)]
+pub enum AbiError {
+    // a type or signature that doesn't parse, `position` is the byte it stopped at
+    InvalidSignature { signature: String, position: usize },
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    WrongArgumentCount { expected: usize, got: usize },
+    // the argument at `index` doesn't fit its parameter
+    TypeMismatch { index: usize, expected: AbiType, value: AbiValue },
+    // the data doesn't start with the selector of the function or error decoding it
+    SelectorMismatch { expected: [u8; 4], got: Vec<u8> },
+    // a log whose topic0 isn't the one of the event decoding it
+    TopicMismatch { expected: B256, got: Option<B256> },
+    // a log with more or fewer topics than the event has, topic0 included
+    WrongTopicCount { expected: usize, got: usize },
+    // the data is too short, or an offset or length points past its end
+    OutOfBounds,
+    // the data holds something that isn't a value of the type, e.g. a bool of 2
+    InvalidValue(AbiType),
+}
@@ -0,0 +1,194 @@
+// What the data of a revert means. Solidity reverts with one of
+//   Error(string)   revert("...") and require(.., "...")
+//   Panic(uint256)  failed asserts, overflows, division by zero, out of bounds indexing, ...
+//   a custom error  revert InsufficientBalance(1, 2), found by its selector in the errors given
+// or with no data at all.
+
+use std::fmt;
+
+use alloy_primitives::{U256, hex};
+
+use crate::abi::{AbiType, AbiValue, CustomError, coder};
+
+// the selector of `Error(string)`
+pub const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+// the selector of `Panic(uint256)`
+pub const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanicCode {
+    Generic,
+    AssertionFailed,
+    ArithmeticOverflow,
+    DivisionByZero,
+    InvalidEnumValue,
+    InvalidStorageByteArray,
+    EmptyArrayPop,
+    ArrayOutOfBounds,
+    OutOfMemory,
+    UninitializedFunction,
+    // a code the compiler doesn't use
+    Unknown(U256),
+}
+
+impl PanicCode {
+    pub fn code(&self) -> U256 {
+        U256::from(match self {
+            PanicCode::Generic => 0x00,
+            PanicCode::AssertionFailed => 0x01,
+            PanicCode::ArithmeticOverflow => 0x11,
+            PanicCode::DivisionByZero => 0x12,
+            PanicCode::InvalidEnumValue => 0x21,
+            PanicCode::InvalidStorageByteArray => 0x22,
+            PanicCode::EmptyArrayPop => 0x31,
+            PanicCode::ArrayOutOfBounds => 0x32,
+            PanicCode::OutOfMemory => 0x41,
+            PanicCode::UninitializedFunction => 0x51,
+            PanicCode::Unknown(code) => return *code,
+        })
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            PanicCode::Generic => "generic compiler panic",
+            PanicCode::AssertionFailed => "assertion failed",
+            PanicCode::ArithmeticOverflow => "arithmetic underflow or overflow",
+            PanicCode::DivisionByZero => "division or modulo by zero",
+            PanicCode::InvalidEnumValue => "conversion to an invalid enum value",
+            PanicCode::InvalidStorageByteArray => "incorrectly encoded storage byte array",
+            PanicCode::EmptyArrayPop => "pop on an empty array",
+            PanicCode::ArrayOutOfBounds => "array index out of bounds",
+            PanicCode::OutOfMemory => "too much memory allocated",
+            PanicCode::UninitializedFunction => "call to a zero-initialized function variable",
+            PanicCode::Unknown(_) => "unknown panic code",
+        }
+    }
+}
+
+impl From<U256> for PanicCode {
+    fn from(code: U256) -> Self {
+        let Ok(known) = u8::try_from(code) else {
+            return PanicCode::Unknown(code);
+        };
+        match known {
+            0x00 => PanicCode::Generic,
+            0x01 => PanicCode::AssertionFailed,
+            0x11 => PanicCode::ArithmeticOverflow,
+            0x12 => PanicCode::DivisionByZero,
+            0x21 => PanicCode::InvalidEnumValue,
+            0x22 => PanicCode::InvalidStorageByteArray,
+            0x31 => PanicCode::EmptyArrayPop,
+            0x32 => PanicCode::ArrayOutOfBounds,
+            0x41 => PanicCode::OutOfMemory,
+            0x51 => PanicCode::UninitializedFunction,
+            _ => PanicCode::Unknown(code),
+        }
+    }
+}
+
+impl fmt::Display for PanicCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:#x})", self.description(), self.code())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Revert {
+    // a bare `revert()` or a failed `require` without a message
+    Empty,
+    Error(String),
+    Panic(PanicCode),
+    Custom { error: CustomError, args: Vec<AbiValue> },
+    // a selector none of the errors has, or data that doesn't decode
+    Unknown(Vec<u8>),
+}
+
+impl Revert {
+    // `errors` are the custom errors the reverting contract may use, e.g. `abi.errors`
+    pub fn decode(data: &[u8], errors: &[CustomError]) -> Self {
+        if data.is_empty() {
+            return Revert::Empty;
+        }
+        let unknown = || Revert::Unknown(data.to_vec());
+        let Some(selector) = data.first_chunk::<4>() else {
+            return unknown();
+        };
+        match *selector {
+            ERROR_SELECTOR => match coder::decode(&[AbiType::String], &data[4..]).as_deref() {
+                Ok([AbiValue::String(message)]) => Revert::Error(message.clone()),
+                _ => unknown(),
+            },
+            PANIC_SELECTOR => match coder::decode(&[AbiType::Uint(256)], &data[4..]).as_deref() {
+                Ok([AbiValue::Uint(code, _)]) => Revert::Panic(PanicCode::from(*code)),
+                _ => unknown(),
+            },
+            _ => errors
+                .iter()
+                .filter(|error| error.selector() == *selector)
+                .find_map(|error| {
+                    let args = error.decode(data).ok()?;
+                    Some(Revert::Custom {
+                        error: error.clone(),
+                        args,
+                    })
+                })
+                .unwrap_or_else(unknown),
+        }
+    }
+}
+
+impl fmt::Display for Revert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Revert::Empty => write!(f, "reverted without data"),
+            Revert::Error(message) => write!(f, "{}", message),
+            Revert::Panic(code) => write!(f, "panic: {}", code),
+            Revert::Custom { error, args } => {
+                let args: Vec<_> = args.iter().map(AbiValue::to_string).collect();
+                write!(f, "{}({})", error.name, args.join(", "))
+            }
+            Revert::Unknown(data) => write!(f, "0x{}", hex::encode(data)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::{Function, coder::encode_with_selector};
+
+    #[test]
+    fn test_builtin_reverts() {
+        let error = encode_with_selector(ERROR_SELECTOR, &[AbiValue::string("not owner")]);
+        assert_eq!(Revert::decode(&error, &[]), Revert::Error("not owner".to_string()));
+        // the selectors are the ones of the signatures
+        assert_eq!(Function::parse("Error(string)").unwrap().selector(), ERROR_SELECTOR);
+        assert_eq!(Function::parse("Panic(uint256)").unwrap().selector(), PANIC_SELECTOR);
+
+        let panic = encode_with_selector(PANIC_SELECTOR, &[AbiValue::uint(0x11u64)]);
+        let revert = Revert::decode(&panic, &[]);
+        assert_eq!(revert, Revert::Panic(PanicCode::ArithmeticOverflow));
+        assert_eq!(revert.to_string(), "panic: arithmetic underflow or overflow (0x11)");
+        let odd = encode_with_selector(PANIC_SELECTOR, &[AbiValue::uint(0x99u64)]);
+        assert_eq!(Revert::decode(&odd, &[]), Revert::Panic(PanicCode::Unknown(U256::from(0x99))));
+
+        assert_eq!(Revert::decode(&[], &[]), Revert::Empty);
+        // an Error(string) cut short
+        assert_eq!(Revert::decode(&error[..36], &[]), Revert::Unknown(error[..36].to_vec()));
+    }
+
+    #[test]
+    fn test_custom_errors() {
+        let errors = [
+            CustomError::parse("Unauthorized()").unwrap(),
+            CustomError::parse("InsufficientBalance(uint256 available, uint256 required)").unwrap(),
+        ];
+        let data = errors[1].encode(&[AbiValue::uint(1u64), AbiValue::uint(5u64)]).unwrap();
+        let revert = Revert::decode(&data, &errors);
+        assert!(
+            matches!(&revert, Revert::Custom { error, .. } if error.name == "InsufficientBalance")
+        );
+        assert_eq!(revert.to_string(), "InsufficientBalance(1, 5)");
+        assert_eq!(Revert::decode(&data, &errors[..1]), Revert::Unknown(data.clone()));
+    }
+}
@@ -0,0 +1,344 @@
+// Solidity ABI types and the parser for human-readable types and signatures, e.g.
+//   uint256, bytes32[], (address,uint256)[2], tuple(string,bool)
+//   function transfer(address to, uint256 amount) external returns (bool)
+//   event Transfer(address indexed from, address indexed to, uint256 value)
+// Parameter names and data locations are allowed and kept out of the canonical form, `uint` and
+// `int` are read as `uint256` and `int256`.
+
+use std::{fmt, str::FromStr};
+
+use crate::abi::AbiError;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AbiType {
+    // the size in bits, a multiple of 8 up to 256
+    Uint(usize),
+    Int(usize),
+    Address,
+    Bool,
+    // bytes1 to bytes32
+    FixedBytes(usize),
+    Bytes,
+    String,
+    // T[]
+    Array(Box<AbiType>),
+    // T[k]
+    FixedArray(Box<AbiType>, usize),
+    Tuple(Vec<AbiType>),
+}
+
+impl AbiType {
+    // dynamic values are encoded in the tail, with an offset in the head
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            AbiType::Bytes | AbiType::String | AbiType::Array(_) => true,
+            AbiType::FixedArray(inner, _) => inner.is_dynamic(),
+            AbiType::Tuple(types) => types.iter().any(AbiType::is_dynamic),
+            _ => false,
+        }
+    }
+
+    // the bytes the type takes in the head of a tuple
+    pub fn head_size(&self) -> usize {
+        match self {
+            _ if self.is_dynamic() => 32,
+            AbiType::FixedArray(inner, length) => inner.head_size() * length,
+            AbiType::Tuple(types) => types.iter().map(AbiType::head_size).sum(),
+            _ => 32,
+        }
+    }
+}
+
+impl FromStr for AbiType {
+    type Err = AbiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(s);
+        let ty = parser.ty()?;
+        parser.end()?;
+        Ok(ty)
+    }
+}
+
+impl fmt::Display for AbiType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AbiType::Uint(bits) => write!(f, "uint{}", bits),
+            AbiType::Int(bits) => write!(f, "int{}", bits),
+            AbiType::Address => write!(f, "address"),
+            AbiType::Bool => write!(f, "bool"),
+            AbiType::FixedBytes(size) => write!(f, "bytes{}", size),
+            AbiType::Bytes => write!(f, "bytes"),
+            AbiType::String => write!(f, "string"),
+            AbiType::Array(inner) => write!(f, "{}[]", inner),
+            AbiType::FixedArray(inner, length) => write!(f, "{}[{}]", inner, length),
+            AbiType::Tuple(types) => write!(f, "({})", join(types)),
+        }
+    }
+}
+
+// comma separated, the way types appear in a signature
+pub(crate) fn join(types: &[AbiType]) -> String {
+    types.iter().map(AbiType::to_string).collect::<Vec<_>>().join(",")
+}
+
+// a parameter of a function or error, `name` is empty when the signature has none
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    pub name: String,
+    pub ty: AbiType,
+}
+
+// what a human-readable signature is made of
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Signature {
+    pub(crate) kind: Option<String>,
+    pub(crate) name: String,
+    pub(crate) inputs: Vec<Param>,
+    // whether each input is marked `indexed`, which only events may do
+    pub(crate) indexed: Vec<bool>,
+    pub(crate) outputs: Vec<Param>,
+    // visibility, mutability and the like, which don't change the encoding
+    pub(crate) modifiers: Vec<String>,
+}
+
+pub(crate) fn parse_signature(s: &str) -> Result<Signature, AbiError> {
+    let mut parser = Parser::new(s);
+    let mut name = parser.word();
+    let mut kind = None;
+    if matches!(name.as_str(), "function" | "error" | "event") {
+        kind = Some(name);
+        name = parser.word();
+    }
+    if name.is_empty() {
+        return Err(parser.error());
+    }
+    let (inputs, indexed) = parser.params()?.into_iter().unzip();
+    let mut outputs = Vec::new();
+    let mut modifiers = Vec::new();
+    loop {
+        let word = parser.word();
+        match word.as_str() {
+            "" => break,
+            "returns" => {
+                outputs = parser.params()?.into_iter().map(|(param, _)| param).collect();
+            }
+            _ => modifiers.push(word),
+        }
+    }
+    parser.end()?;
+    Ok(Signature {
+        kind,
+        name,
+        inputs,
+        indexed,
+        outputs,
+        modifiers,
+    })
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, pos: 0 }
+    }
+
+    fn error(&self) -> AbiError {
+        AbiError::InvalidSignature {
+            signature: self.input.to_string(),
+            position: self.pos,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let found = self.peek() == Some(byte);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), AbiError> {
+        if self.eat(byte) { Ok(()) } else { Err(self.error()) }
+    }
+
+    fn end(&mut self) -> Result<(), AbiError> {
+        if self.peek().is_none() { Ok(()) } else { Err(self.error()) }
+    }
+
+    // an identifier, or nothing if there is none at the current position
+    fn word(&mut self) -> String {
+        self.skip_whitespace();
+        let rest = &self.input[self.pos..];
+        let length = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '$'))
+            .unwrap_or(rest.len());
+        self.pos += length;
+        rest[..length].to_string()
+    }
+
+    // `(type name, ...)`, each with whether it is `indexed`
+    fn params(&mut self) -> Result<Vec<(Param, bool)>, AbiError> {
+        self.expect(b'(')?;
+        let mut params = Vec::new();
+        if self.eat(b')') {
+            return Ok(params);
+        }
+        loop {
+            params.push(self.param()?);
+            if self.eat(b')') {
+                return Ok(params);
+            }
+            self.expect(b',')?;
+        }
+    }
+
+    fn param(&mut self) -> Result<(Param, bool), AbiError> {
+        let ty = self.ty()?;
+        let mut name = String::new();
+        let mut indexed = false;
+        loop {
+            let word = self.word();
+            match word.as_str() {
+                "" => break,
+                "memory" | "calldata" | "storage" => {}
+                "indexed" if !indexed && name.is_empty() => indexed = true,
+                _ if name.is_empty() => name = word,
+                _ => return Err(self.error()),
+            }
+        }
+        Ok((Param { name, ty }, indexed))
+    }
+
+    fn ty(&mut self) -> Result<AbiType, AbiError> {
+        let start = self.pos;
+        let mut ty = if self.peek() == Some(b'(') {
+            self.tuple()?
+        } else {
+            let word = self.word();
+            if word == "tuple" && self.peek() == Some(b'(') {
+                self.tuple()?
+            } else {
+                elementary(&word).ok_or_else(|| {
+                    self.pos = start;
+                    self.error()
+                })?
+            }
+        };
+        while self.eat(b'[') {
+            let length = self.word();
+            ty = if length.is_empty() {
+                AbiType::Array(Box::new(ty))
+            } else {
+                let length = length.parse().map_err(|_| self.error())?;
+                AbiType::FixedArray(Box::new(ty), length)
+            };
+            self.expect(b']')?;
+        }
+        Ok(ty)
+    }
+
+    fn tuple(&mut self) -> Result<AbiType, AbiError> {
+        let params = self.params()?;
+        Ok(AbiType::Tuple(params.into_iter().map(|(param, _)| param.ty).collect()))
+    }
+}
+
+fn elementary(word: &str) -> Option<AbiType> {
+    let sized = |digits: &str, max: usize, step: usize| -> Option<usize> {
+        let size: usize = digits.parse().ok()?;
+        let valid = size > 0 && size <= max && size.is_multiple_of(step);
+        (valid && !digits.starts_with('0')).then_some(size)
+    };
+    Some(match word {
+        "address" => AbiType::Address,
+        "bool" => AbiType::Bool,
+        "bytes" => AbiType::Bytes,
+        "string" => AbiType::String,
+        "uint" => AbiType::Uint(256),
+        "int" => AbiType::Int(256),
+        _ => {
+            if let Some(bits) = word.strip_prefix("uint") {
+                AbiType::Uint(sized(bits, 256, 8)?)
+            } else if let Some(bits) = word.strip_prefix("int") {
+                AbiType::Int(sized(bits, 256, 8)?)
+            } else if let Some(size) = word.strip_prefix("bytes") {
+                AbiType::FixedBytes(sized(size, 32, 1)?)
+            } else {
+                return None;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_types() {
+        let ty: AbiType = "(uint, address[], bytes32)[2]".parse().unwrap();
+        assert_eq!(
+            ty,
+            AbiType::FixedArray(
+                Box::new(AbiType::Tuple(vec![
+                    AbiType::Uint(256),
+                    AbiType::Array(Box::new(AbiType::Address)),
+                    AbiType::FixedBytes(32),
+                ])),
+                2
+            )
+        );
+        assert_eq!(ty.to_string(), "(uint256,address[],bytes32)[2]");
+        assert!(ty.is_dynamic());
+        assert_eq!("tuple(int8,bool)".parse::<AbiType>().unwrap().head_size(), 64);
+        let invalid_types = [
+            "uint7", "uint264", "bytes33", "bytes0", "uint08", "foo", "uint256[", "()x",
+        ];
+        for invalid in invalid_types {
+            assert!(invalid.parse::<AbiType>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_parse_signature() {
+        let signature = parse_signature(
+            "function transfer(address to, uint256 amount) external returns (bool)",
+        )
+        .unwrap();
+        assert_eq!(signature.kind.as_deref(), Some("function"));
+        assert_eq!(signature.name, "transfer");
+        assert_eq!(signature.inputs[1].name, "amount");
+        assert_eq!(signature.inputs[1].ty, AbiType::Uint(256));
+        assert_eq!(signature.outputs[0].ty, AbiType::Bool);
+        assert_eq!(signature.modifiers, vec!["external".to_string()]);
+        assert_eq!(signature.indexed, vec![false, false]);
+
+        let event = parse_signature("event Transfer(address indexed from, address indexed, uint)")
+            .unwrap();
+        assert_eq!(event.indexed, vec![true, true, false]);
+        assert_eq!(event.inputs[1].name, "");
+
+        let bare = parse_signature("f((uint a, string memory b)[] calldata xs)").unwrap();
+        assert_eq!(bare.inputs[0].ty.to_string(), "(uint256,string)[]");
+        assert_eq!(bare.inputs[0].name, "xs");
+        assert!(matches!(
+            parse_signature("f(uint256"),
+            Err(AbiError::InvalidSignature { position: 9, .. })
+        ));
+    }
+}
@@ -0,0 +1,194 @@
+// Values of ABI types, what gets encoded into calldata and comes out of decoded return data.
+
+use std::fmt;
+
+use alloy_primitives::{Address, I256, U256, hex, ruint::UintTryFrom};
+
+use crate::abi::AbiType;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AbiValue {
+    // the value and the size of its type in bits
+    Uint(U256, usize),
+    Int(I256, usize),
+    Address(Address),
+    Bool(bool),
+    // the bytes of a bytes1 to bytes32, their count is the size of the type
+    FixedBytes(Vec<u8>),
+    Bytes(Vec<u8>),
+    String(String),
+    // the element type is kept so an empty array still knows what it holds
+    Array(AbiType, Vec<AbiValue>),
+    FixedArray(Vec<AbiValue>),
+    Tuple(Vec<AbiValue>),
+}
+
+impl AbiValue {
+    // a uint256, e.g. `AbiValue::uint(100u64)`, panics if the value doesn't fit
+    pub fn uint<T>(value: T) -> Self
+    where
+        U256: UintTryFrom<T>,
+    {
+        AbiValue::Uint(U256::from(value), 256)
+    }
+
+    pub fn int(value: impl Into<I256>) -> Self {
+        AbiValue::Int(value.into(), 256)
+    }
+
+    pub fn string(value: impl Into<String>) -> Self {
+        AbiValue::String(value.into())
+    }
+
+    // None for an empty fixed array, whose element type can't be told
+    pub fn ty(&self) -> Option<AbiType> {
+        Some(match self {
+            AbiValue::Uint(_, bits) => AbiType::Uint(*bits),
+            AbiValue::Int(_, bits) => AbiType::Int(*bits),
+            AbiValue::Address(_) => AbiType::Address,
+            AbiValue::Bool(_) => AbiType::Bool,
+            AbiValue::FixedBytes(bytes) => AbiType::FixedBytes(bytes.len()),
+            AbiValue::Bytes(_) => AbiType::Bytes,
+            AbiValue::String(_) => AbiType::String,
+            AbiValue::Array(inner, _) => AbiType::Array(Box::new(inner.clone())),
+            AbiValue::FixedArray(values) => {
+                AbiType::FixedArray(Box::new(values.first()?.ty()?), values.len())
+            }
+            AbiValue::Tuple(values) => {
+                AbiType::Tuple(values.iter().map(AbiValue::ty).collect::<Option<_>>()?)
+            }
+        })
+    }
+
+    // whether the value can be encoded as `ty`, integers must fit in its size
+    pub fn matches(&self, ty: &AbiType) -> bool {
+        match (self, ty) {
+            (AbiValue::Uint(value, _), AbiType::Uint(bits)) => value.bit_len() <= *bits,
+            (AbiValue::Int(value, _), AbiType::Int(bits)) => fits_signed(*value, *bits),
+            (AbiValue::Address(_), AbiType::Address)
+            | (AbiValue::Bool(_), AbiType::Bool)
+            | (AbiValue::Bytes(_), AbiType::Bytes)
+            | (AbiValue::String(_), AbiType::String) => true,
+            (AbiValue::FixedBytes(bytes), AbiType::FixedBytes(size)) => bytes.len() == *size,
+            (AbiValue::Array(_, values), AbiType::Array(inner)) => {
+                values.iter().all(|value| value.matches(inner))
+            }
+            (AbiValue::FixedArray(values), AbiType::FixedArray(inner, length)) => {
+                values.len() == *length && values.iter().all(|value| value.matches(inner))
+            }
+            (AbiValue::Tuple(values), AbiType::Tuple(types)) => {
+                values.len() == types.len()
+                    && values.iter().zip(types).all(|(value, ty)| value.matches(ty))
+            }
+            _ => false,
+        }
+    }
+
+    // converts integers to the size of `ty`, so values built with `uint()` encode as `uint8` too
+    pub(crate) fn cast(self, ty: &AbiType) -> AbiValue {
+        match (self, ty) {
+            (AbiValue::Uint(value, _), AbiType::Uint(bits)) => AbiValue::Uint(value, *bits),
+            (AbiValue::Int(value, _), AbiType::Int(bits)) => AbiValue::Int(value, *bits),
+            (AbiValue::Array(_, values), AbiType::Array(inner)) => AbiValue::Array(
+                (**inner).clone(),
+                values.into_iter().map(|value| value.cast(inner)).collect(),
+            ),
+            (AbiValue::FixedArray(values), AbiType::FixedArray(inner, _)) => {
+                AbiValue::FixedArray(values.into_iter().map(|value| value.cast(inner)).collect())
+            }
+            (AbiValue::Tuple(values), AbiType::Tuple(types)) => AbiValue::Tuple(
+                values.into_iter().zip(types).map(|(value, ty)| value.cast(ty)).collect(),
+            ),
+            (value, _) => value,
+        }
+    }
+
+    pub fn as_uint(&self) -> Option<U256> {
+        match self {
+            AbiValue::Uint(value, _) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_address(&self) -> Option<Address> {
+        match self {
+            AbiValue::Address(address) => Some(*address),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            AbiValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            AbiValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) fn fits_signed(value: I256, bits: usize) -> bool {
+    if bits >= 256 {
+        return true;
+    }
+    let shift = 256 - bits;
+    (value << shift).asr(shift) == value
+}
+
+impl From<U256> for AbiValue {
+    fn from(value: U256) -> Self {
+        AbiValue::uint(value)
+    }
+}
+
+impl From<I256> for AbiValue {
+    fn from(value: I256) -> Self {
+        AbiValue::int(value)
+    }
+}
+
+impl From<Address> for AbiValue {
+    fn from(address: Address) -> Self {
+        AbiValue::Address(address)
+    }
+}
+
+impl From<bool> for AbiValue {
+    fn from(value: bool) -> Self {
+        AbiValue::Bool(value)
+    }
+}
+
+impl From<&str> for AbiValue {
+    fn from(value: &str) -> Self {
+        AbiValue::string(value)
+    }
+}
+
+// the way the values would be written in Solidity, e.g. `(0x5B38..., 100, "hi", [1, 2])`
+impl fmt::Display for AbiValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |values: &[AbiValue]| {
+            values.iter().map(AbiValue::to_string).collect::<Vec<_>>().join(", ")
+        };
+        match self {
+            AbiValue::Uint(value, _) => write!(f, "{}", value),
+            AbiValue::Int(value, _) => write!(f, "{}", value),
+            AbiValue::Address(address) => write!(f, "{}", address),
+            AbiValue::Bool(value) => write!(f, "{}", value),
+            AbiValue::FixedBytes(bytes) | AbiValue::Bytes(bytes) => {
+                write!(f, "0x{}", hex::encode(bytes))
+            }
+            AbiValue::String(value) => write!(f, "{:?}", value),
+            AbiValue::Array(_, values) | AbiValue::FixedArray(values) => {
+                write!(f, "[{}]", list(values))
+            }
+            AbiValue::Tuple(values) => write!(f, "({})", list(values)),
+        }
+    }
+}
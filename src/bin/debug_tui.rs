@@ -4,18 +4,60 @@ use crossterm::{
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
-use evm::{evm::EVM, helpers::get_supported_opcode_name};
+use evm::{
+    abi::Abi,
+    disassembler::disassemble_program_with,
+    evm::EVM,
+    sourcemap::{Artifact, DebugInfo},
+};
 use ratatui::{
     Terminal,
     backend::{Backend, CrosstermBackend},
     layout::{Constraint, Direction, Layout,Position},
     style::{Color, Modifier, Style},
-    text::{Span},
+    text::{Line, Span},
     widgets::{Block, Borders,Clear, List, ListItem, Paragraph, ListState},
 };
-use std::{io, time::Duration};
+use std::{env, io, time::Duration};
+
+use anyhow::{Context, bail};
+
+const USAGE: &str =
+    "usage: debug_tui [--artifact <combined json> [--contract <name>]] [--abi <abi json>]";
 
 fn main() -> Result<(), anyhow::Error> {
+    // a solc artifact skips the program selection and shows the Solidity source while stepping,
+    // an ABI (or the one in the artifact) decodes the logs
+    let mut artifact_path = None;
+    let mut contract = None;
+    let mut abi_path = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--artifact" => artifact_path = Some(args.next().context(USAGE)?),
+            "--contract" => contract = Some(args.next().context(USAGE)?),
+            "--abi" => abi_path = Some(args.next().context(USAGE)?),
+            _ => bail!(USAGE),
+        }
+    }
+    let artifact = match artifact_path {
+        Some(path) => Some(
+            Artifact::load(&path, contract.as_deref())
+                .map_err(|err| anyhow::anyhow!("{:?}", err))
+                .with_context(|| format!("could not load {path}"))?,
+        ),
+        None => None,
+    };
+    let (preset_program, debug_info, mut abi) = match artifact {
+        Some(artifact) => (Some(artifact.code), Some(artifact.debug_info), artifact.abi),
+        None => (None, None, Abi::default()),
+    };
+    if let Some(path) = abi_path {
+        abi = Abi::load(&path)
+            .map_err(|err| anyhow::anyhow!("{:?}", err))
+            .with_context(|| format!("could not load {path}"))?;
+    }
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
@@ -23,14 +65,14 @@ fn main() -> Result<(), anyhow::Error> {
     let mut terminal = Terminal::new(backend)?;
 
     // RUN SETUP WIZARD
-    let setup_res = run_setup_wizard(&mut terminal);
+    let setup_res = run_setup_wizard(&mut terminal, preset_program);
 
     // Run the UI Loop
     if let Ok(Some((program, desired_gas))) = setup_res {
         let mut evm = EVM::new(Address::ZERO, program, desired_gas, U256::ZERO, vec![]);
         
         // Run the Main Debugger Loop
-        let res = run_app(&mut terminal, &mut evm, desired_gas);
+        let res = run_app(&mut terminal, &mut evm, desired_gas, debug_info.as_ref(), &abi);
         if let Err(err) = res {
             println!("Runtime Error: {:?}", err);
         }
@@ -62,7 +104,11 @@ struct SampleProgram {
     code: Vec<u8>,
 }
 
-fn run_setup_wizard<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<Option<(Vec<u8>, u64)>> {
+// with a preset program only the gas limit is asked for
+fn run_setup_wizard<B: Backend>(
+    terminal: &mut Terminal<B>,
+    preset_program: Option<Vec<u8>>,
+) -> io::Result<Option<(Vec<u8>, u64)>> {
     let mut stage = SetupStage::ModeSelect;
     
     // UI States
@@ -73,9 +119,14 @@ fn run_setup_wizard<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<Option
     // Input Buffers
     let mut input_buffer = String::new();
     let mut selected_program: Vec<u8> = Vec::new();
+    if let Some(program) = preset_program {
+        selected_program = program;
+        stage = SetupStage::GasInput;
+        input_buffer = "25000".to_string(); // Default gas
+    }
 
     // Data: Samples
-    let samples = vec![
+    let samples = [
         SampleProgram { 
             name: "Grand Tour (Math, Mem, Storage)", 
             code: vec![
@@ -113,7 +164,7 @@ fn run_setup_wizard<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<Option
                         .alignment(ratatui::layout::Alignment::Center);
                     
                     // Simple manual list rendering
-                    let modes = vec![" Use Sample Program ", " Enter Bytecode Manually "];
+                    let modes = [" Use Sample Program ", " Enter Bytecode Manually "];
                     let items: Vec<ListItem> = modes.iter().enumerate().map(|(i, m)| {
                         let style = if i == mode_index { 
                             Style::default().fg(Color::Black).bg(Color::Cyan) 
@@ -200,8 +251,8 @@ fn run_setup_wizard<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<Option
             match stage {
                 SetupStage::ModeSelect => {
                     match key.code {
-                        KeyCode::Up => if mode_index > 0 { mode_index -= 1 },
-                        KeyCode::Down => if mode_index < 1 { mode_index += 1 },
+                        KeyCode::Up => mode_index = mode_index.saturating_sub(1),
+                        KeyCode::Down if mode_index < 1 => mode_index += 1,
                         KeyCode::Enter => {
                             if mode_index == 0 { stage = SetupStage::SampleSelect; }
                             else { 
@@ -233,27 +284,25 @@ fn run_setup_wizard<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<Option
                 },
                 SetupStage::ManualInput => {
                     match key.code {
-                        KeyCode::Char(c) => {
-                            if c.is_digit(16) || c == 'x' || c == 'X' { input_buffer.push(c); } // Only allow hex
+                        KeyCode::Char(c) if c.is_ascii_hexdigit() || c == 'x' || c == 'X' => {
+                            input_buffer.push(c); // Only allow hex
                         },
                         KeyCode::Backspace => { input_buffer.pop(); },
-                        KeyCode::Enter => {
-                            if !input_buffer.is_empty() {
-                                // remove unnecessary user parsed elements
-                                let clean_input = input_buffer
-                                .trim()
-                                .replace(" ", "")
-                                .replace("0x", "")
-                                .replace("0X", "");
-                                // Parse Hex
-                                if let Ok(bytes) = hex::decode(&clean_input) {
-                                    selected_program = bytes;
-                                    stage = SetupStage::GasInput;
-                                    input_buffer = "25000".to_string(); // Reset buffer for gas
-                                } else {
-                                    // Should ideally show error, but simple retry for now
-                                    input_buffer.clear(); 
-                                }
+                        KeyCode::Enter if !input_buffer.is_empty() => {
+                            // remove unnecessary user parsed elements
+                            let clean_input = input_buffer
+                            .trim()
+                            .replace(" ", "")
+                            .replace("0x", "")
+                            .replace("0X", "");
+                            // Parse Hex
+                            if let Ok(bytes) = hex::decode(&clean_input) {
+                                selected_program = bytes;
+                                stage = SetupStage::GasInput;
+                                input_buffer = "25000".to_string(); // Reset buffer for gas
+                            } else {
+                                // Should ideally show error, but simple retry for now
+                                input_buffer.clear(); 
                             }
                         },
                         _ => {}
@@ -261,8 +310,8 @@ fn run_setup_wizard<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<Option
                 },
                 SetupStage::GasInput => {
                     match key.code {
-                        KeyCode::Char(c) => {
-                            if c.is_digit(10) { input_buffer.push(c); } // Only allow numbers
+                        KeyCode::Char(c) if c.is_ascii_digit() => {
+                            input_buffer.push(c); // Only allow numbers
                         },
                         KeyCode::Backspace => { input_buffer.pop(); },
                         KeyCode::Enter => {
@@ -279,7 +328,13 @@ fn run_setup_wizard<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<Option
 
 
 // MAIN APPLICATION LOGIC -> EVM SCREEN
-fn run_app<B: Backend>(terminal: &mut Terminal<B>, evm: &mut EVM, initial_gas: u64) -> io::Result<()> {
+fn run_app<B: Backend>(
+    terminal: &mut Terminal<B>,
+    evm: &mut EVM,
+    initial_gas: u64,
+    debug_info: Option<&DebugInfo>,
+    abi: &Abi,
+) -> io::Result<()> {
     // Track if an error happened
     let mut stop_reason: Option<String> = None;
     
@@ -288,10 +343,14 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, evm: &mut EVM, initial_gas: u
 
     // Track last executed command
     let mut last_action_text: String = "Ready".to_string();
+
+    // the program never changes while it runs, so it is disassembled once
+    // with the EVM's own table, custom instructions show their names
+    let listing = disassemble_program_with(&evm.program, evm.opcode_table());
     loop {
-        // AUTO-SCROLL LOGIC: Always select the current PC
+        // AUTO-SCROLL LOGIC: Always select the instruction at the current PC
         // This forces the UI to scroll down as the PC increases
-        code_state.select(Some(evm.pc));
+        code_state.select(listing.iter().position(|instruction| instruction.pc == evm.pc));
 
         terminal.draw(|f| {
             let main_chunks = Layout::default()
@@ -304,18 +363,25 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, evm: &mut EVM, initial_gas: u
                 .constraints([Constraint::Percentage(29), Constraint::Percentage(42), Constraint::Percentage(29)].as_ref())
                 .split(main_chunks[0]);
 
+            // with debug info the Solidity source sits between the stack and the status
+            let bottom_constraints = if debug_info.is_some() {
+                vec![Constraint::Percentage(29), Constraint::Percentage(42), Constraint::Percentage(29)]
+            } else {
+                vec![Constraint::Percentage(50), Constraint::Percentage(50)]
+            };
             let bottom_row = Layout::default()
                 .direction(Direction::Horizontal)
-                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+                .constraints(bottom_constraints)
                 .split(main_chunks[1]);
+            let status_area = bottom_row[bottom_row.len() - 1];
 
             // --- WIDGET 1: BYTECODE (Stateful!) ---
-            let code_items: Vec<ListItem> = evm.program.iter().enumerate().map(|(i, &byte)| {
-                // HEX Index + HEX Value 
-                // Example: 0000: 0x60
-                let content = format!("{:04x}: {:#04x}", i, byte);
+            let code_items: Vec<ListItem> = listing.iter().map(|instruction| {
+                // HEX Index + Mnemonic + Immediate
+                // Example: 0000: PUSH1 0x2a
+                let content = instruction.to_string();
 
-                let style = if i == evm.pc { 
+                let style = if instruction.pc == evm.pc { 
                     Style::default().fg(Color::Yellow).bg(Color::DarkGray).add_modifier(Modifier::BOLD) 
                 } else { 
                     Style::default().fg(Color::Gray) 
@@ -347,14 +413,56 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, evm: &mut EVM, initial_gas: u
                 .collect();
             f.render_widget(List::new(memory_items).block(Block::default().borders(Borders::ALL).title(" Memory (16b) ")), top_row[1]);
 
-             let storage_items: Vec<ListItem> = evm.storage.storage.iter().map(|(key, value)| {
+             let storage_items: Vec<ListItem> = evm.host.db.storage_of(evm.address).into_iter().flatten().map(|(key, value)| {
                 ListItem::new(format!("S[{:#x}]: {:#x}", key, value))
             }).collect();
-            f.render_widget(List::new(storage_items).block(Block::default().borders(Borders::ALL).title(" Storage ")), top_row[2]);
+            // storage above the logs emitted so far
+            let state_column = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+                .split(top_row[2]);
+            f.render_widget(List::new(storage_items).block(Block::default().borders(Borders::ALL).title(" Storage ")), state_column[0]);
+
+            // events the ABI knows are shown decoded, anything else as raw topics and data
+            let log_items: Vec<ListItem> = evm.host.logs.iter().enumerate().map(|(i, log)| {
+                let text = match abi.decode_log(log) {
+                    Some(event) => event.to_string(),
+                    None => {
+                        let topics: Vec<String> = log.topics.iter().map(|topic| format!("{:#x}", topic)).collect();
+                        format!("LOG{} [{}] 0x{}", log.topics.len(), topics.join(", "), hex::encode(&log.data))
+                    }
+                };
+                ListItem::new(format!("[{}] {}", i, text))
+            }).collect();
+            f.render_widget(List::new(log_items).block(Block::default().borders(Borders::ALL).title(" Logs ")), state_column[1]);
 
-            let stack_items: Vec<ListItem> = evm.stack.items.iter().enumerate().rev().map(|(i, val)| ListItem::new(format!("[{}] {:#x}", i, val))).collect();
+            let stack_items: Vec<ListItem> = evm.stack.items().iter().enumerate().rev().map(|(i, val)| ListItem::new(format!("[{}] {:#x}", i, val))).collect();
             f.render_widget(List::new(stack_items).block(Block::default().borders(Borders::ALL).title(" Stack ")), bottom_row[0]);
 
+            if let Some(debug_info) = debug_info {
+                let location = debug_info.location(evm.pc);
+                let title = match &location {
+                    Some(location) => format!(" Source: {} ", location),
+                    None => " Source: (no mapping) ".to_string(),
+                };
+                let mut source_lines = Vec::new();
+                if let Some(location) = &location {
+                    // a few lines of context around the current one, the current one highlighted
+                    let context = (bottom_row[1].height as usize).saturating_sub(2) / 2;
+                    let first = location.line.saturating_sub(context).max(1);
+                    for number in first..=location.line + context {
+                        let Some(text) = location.file.line(number) else { break };
+                        let style = if number == location.line {
+                            Style::default().fg(Color::Yellow).bg(Color::DarkGray).add_modifier(Modifier::BOLD)
+                        } else {
+                            Style::default().fg(Color::Gray)
+                        };
+                        source_lines.push(Line::from(Span::styled(format!("{:>4} {}", number, text), style)));
+                    }
+                }
+                f.render_widget(Paragraph::new(source_lines).block(Block::default().borders(Borders::ALL).title(title)), bottom_row[1]);
+            }
+
             let status_block = Block::default().borders(Borders::ALL).title(" Status ");
             
            if let Some(msg) = &stop_reason {
@@ -370,7 +478,7 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, evm: &mut EVM, initial_gas: u
                     .style(Style::default().fg(color).add_modifier(Modifier::BOLD)) 
                     .block(status_block.border_style(Style::default().fg(color))); 
                 
-                f.render_widget(paragraph, bottom_row[1]);
+                f.render_widget(paragraph, status_area);
 
                 // Render Popup
                 let area = centered_rect(60, 30, f.area());
@@ -390,35 +498,38 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, evm: &mut EVM, initial_gas: u
                     "PC: {:#04x} ({})\nGas Used: {}\nGas Left: {}\n\nLast Op: {}\n\n[ Controls ]\n'n' : Next Step\n'q' : Quit", 
                     evm.pc, evm.pc, gas_used, evm.gas, last_action_text
                 );
-                f.render_widget(Paragraph::new(status_text).block(status_block), bottom_row[1]);
+                f.render_widget(Paragraph::new(status_text).block(status_block), status_area);
             }
         })?;
 
-        if crossterm::event::poll(Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Char('q') => return Ok(()),
-                    KeyCode::Char('n') => {
-                        if stop_reason.is_none() {
-                            // PEEK
-                            if evm.pc < evm.program.len() {
-                                let op = evm.program[evm.pc];
-                                let op_name = get_supported_opcode_name(op);
-                                last_action_text = format!("Executed {} ({:#02x})", op_name, op);
-                            } else {
-                                last_action_text = "End of Code".to_string();
-                            }
+        if crossterm::event::poll(Duration::from_millis(100))?
+            && let Event::Key(key) = event::read()?
+        {
+            match key.code {
+                KeyCode::Char('q') => return Ok(()),
+                KeyCode::Char('n') if stop_reason.is_none() => {
+                    // an EOF program starts at its first code section, not at byte 0
+                    if let Err(e) = evm.load_container() {
+                        stop_reason = Some(format!("{:?}", e));
+                        continue;
+                    }
+                    // PEEK
+                    if evm.pc < evm.program.len() {
+                        let op = evm.program[evm.pc];
+                        let op_name = evm.opcode_table().name(op);
+                        last_action_text = format!("Executed {} ({:#02x})", op_name, op);
+                    } else {
+                        last_action_text = "End of Code".to_string();
+                    }
 
-                             match evm.step() {
-                                Ok(cont) => {
-                                    if !cont { stop_reason = Some("Program Finished (STOP)".to_string()); }
-                                }
-                                Err(e) => { stop_reason = Some(format!("{:?}", e)); }
-                            }
+                    match evm.step() {
+                        Ok(cont) => {
+                            if !cont { stop_reason = Some("Program Finished (STOP)".to_string()); }
                         }
+                        Err(e) => { stop_reason = Some(format!("{:?}", e)); }
                     }
-                    _ => {}
                 }
+                _ => {}
             }
         }
     }
@@ -0,0 +1,80 @@
+// Runs Ethereum state tests and prints what passed, by fork and by directory:
+//   cargo run --release --bin statetest -- <directory | file> [--fork <name>]... [--filter <text>]
+//       [--jobs <threads>] [--verbose]
+// e.g. the Cancun tests of ethereum/tests with `add` in their name or path:
+//   cargo run --release --bin statetest -- tests/GeneralStateTests --fork Cancun --filter add
+// Every failure gets a line, `--verbose` also lists the skipped ones. The exit code is 1 if
+// anything failed or a file couldn't be read.
+
+use std::{collections::BTreeMap, env, path::PathBuf, process, thread};
+
+use anyhow::{Context, bail};
+use evm::statetest::{Filter, Status, Tally, runner};
+
+const USAGE: &str = "usage: statetest <directory | file> [--fork <name>]... [--filter <text>] \
+                     [--jobs <threads>] [--verbose]";
+
+fn print_tallies(title: &str, tallies: &BTreeMap<String, Tally>) {
+    println!("\n{title}");
+    let width = tallies.keys().map(String::len).max().unwrap_or(0);
+    for (key, tally) in tallies {
+        println!("  {key:<width$}  {tally}");
+    }
+}
+
+fn main() -> Result<(), anyhow::Error> {
+    let mut root = None;
+    let mut filter = Filter::default();
+    let mut jobs = thread::available_parallelism().map_or(1, usize::from);
+    let mut verbose = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--fork" => filter.forks.push(args.next().context(USAGE)?),
+            "--filter" => filter.name = Some(args.next().context(USAGE)?),
+            "--jobs" => {
+                let count = args.next().context(USAGE)?;
+                jobs = count.parse().with_context(|| format!("invalid job count {count}"))?;
+            }
+            "--verbose" => verbose = true,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return Ok(());
+            }
+            _ if root.is_none() => root = Some(PathBuf::from(arg)),
+            _ => bail!(USAGE),
+        }
+    }
+    let root = root.context(USAGE)?;
+
+    let report = runner::run(&root, &filter, jobs)
+        .map_err(|err| anyhow::anyhow!("{:?}", err))
+        .with_context(|| format!("could not read {}", root.display()))?;
+    for (path, err) in &report.errors {
+        eprintln!("error: {}: {:?}", path.display(), err);
+    }
+    for result in &report.results {
+        let label = match &result.status {
+            Status::Failed(why) => format!("FAIL  {why}"),
+            Status::Skipped(why) if verbose => format!("SKIP  {why}"),
+            _ => continue,
+        };
+        println!(
+            "{} {} {}[{}]: {}",
+            result.path.display(),
+            result.name,
+            result.fork,
+            result.index,
+            label
+        );
+    }
+
+    print_tallies("by fork", &report.by_fork());
+    print_tallies("by directory", &report.by_directory());
+    let total = report.total();
+    println!("\ntotal  {total}");
+    if total.failed > 0 || !report.errors.is_empty() {
+        process::exit(1);
+    }
+    Ok(())
+}
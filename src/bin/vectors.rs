@@ -0,0 +1,62 @@
+// Runs the cases of an evm-from-scratch `evm.json` and prints the ones that don't match:
+//   cargo run --bin vectors -- <evm.json> [--filter <text>] [--verbose]
+// `--filter` keeps the cases whose name contains the text, e.g. `--filter PUSH`, `--verbose`
+// adds the hint and the assembly of every failing case. The exit code is 1 if any case failed.
+
+use std::{env, process};
+
+use anyhow::{Context, bail};
+use evm::vectors;
+
+const USAGE: &str = "usage: vectors <evm.json> [--filter <text>] [--verbose]";
+
+fn main() -> Result<(), anyhow::Error> {
+    let mut path = None;
+    let mut filter = None;
+    let mut verbose = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--filter" => filter = Some(args.next().context(USAGE)?),
+            "--verbose" => verbose = true,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return Ok(());
+            }
+            _ if path.is_none() => path = Some(arg),
+            _ => bail!(USAGE),
+        }
+    }
+    let path = path.context(USAGE)?;
+    let mut cases = vectors::load(&path)
+        .map_err(|err| anyhow::anyhow!("{:?}", err))
+        .with_context(|| format!("could not load {path}"))?;
+    if let Some(filter) = &filter {
+        cases.retain(|case| case.name.contains(filter.as_str()));
+    }
+
+    let results = vectors::run_all(&cases);
+    for (case, result) in cases.iter().zip(&results) {
+        if result.passed() {
+            continue;
+        }
+        println!("FAIL {}", result.name);
+        for mismatch in &result.mismatches {
+            println!("  {mismatch}");
+        }
+        if verbose {
+            if !case.hint.is_empty() {
+                println!("  hint: {}", case.hint);
+            }
+            for line in case.code.asm.iter().flat_map(|asm| asm.lines()) {
+                println!("  | {line}");
+            }
+        }
+    }
+    let passed = results.iter().filter(|result| result.passed()).count();
+    println!("\n{} of {} cases passed", passed, results.len());
+    if passed < results.len() {
+        process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,251 @@
+// Caches whatever is read from the database underneath and keeps every write in memory.
+// Nothing reaches the database until `commit`, so dropping the cache undoes a run.
+
+use std::collections::HashMap;
+
+use alloy_primitives::{Address, B256, KECCAK256_EMPTY, U256};
+
+use crate::db::{AccountInfo, Database, DatabaseCommit, EmptyDB};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DbAccount {
+    pub info: AccountInfo,
+    // the slots read or written so far
+    pub storage: HashMap<U256, U256>,
+    // false while the database has no such account and nothing was written to it
+    pub exists: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CacheDB<D> {
+    pub accounts: HashMap<Address, DbAccount>,
+    // code by hash, account infos in `accounts` leave their `code` empty
+    pub contracts: HashMap<B256, Vec<u8>>,
+    pub block_hashes: HashMap<u64, B256>,
+    pub db: D,
+}
+
+// everything lives in the cache, there is nothing underneath
+pub type InMemoryDB = CacheDB<EmptyDB>;
+
+impl<D: Database> CacheDB<D> {
+    pub fn new(db: D) -> Self {
+        Self {
+            accounts: HashMap::new(),
+            contracts: HashMap::new(),
+            block_hashes: HashMap::new(),
+            db,
+        }
+    }
+
+    // the cached account, read from the database the first time it's asked for
+    pub fn account_mut(&mut self, address: Address) -> &mut DbAccount {
+        let Self {
+            accounts,
+            contracts,
+            db,
+            ..
+        } = self;
+        accounts.entry(address).or_insert_with(|| match db.basic(address) {
+            Some(mut info) => {
+                if let Some(code) = info.code.take() {
+                    contracts.insert(info.code_hash, code);
+                }
+                DbAccount {
+                    info,
+                    storage: HashMap::new(),
+                    exists: true,
+                }
+            }
+            None => DbAccount::default(),
+        })
+    }
+
+    // replaces the account's balance, nonce and code, its storage stays as it is
+    pub fn insert_account(&mut self, address: Address, mut info: AccountInfo) {
+        if let Some(code) = info.code.take() {
+            self.contracts.insert(info.code_hash, code);
+        }
+        let account = self.account_mut(address);
+        account.info = info;
+        account.exists = true;
+    }
+
+    pub fn insert_storage(&mut self, address: Address, slot: U256, value: U256) {
+        let account = self.account_mut(address);
+        account.storage.insert(slot, value);
+        account.exists = true;
+    }
+
+    // the slots of `address` seen so far, without going to the database
+    pub fn storage_of(&self, address: Address) -> Option<&HashMap<U256, U256>> {
+        self.accounts.get(&address).map(|account| &account.storage)
+    }
+
+    // hands every account touched so far to the database and empties the cache
+    pub fn commit(&mut self)
+    where
+        D: DatabaseCommit,
+    {
+        let mut changes = std::mem::take(&mut self.accounts);
+        changes.retain(|_, account| account.exists);
+        for account in changes.values_mut() {
+            account.info.code = self.contracts.get(&account.info.code_hash).cloned();
+        }
+        self.db.apply(changes);
+    }
+}
+
+impl<D: Database> Database for CacheDB<D> {
+    fn basic(&mut self, address: Address) -> Option<AccountInfo> {
+        let account = self.account_mut(address);
+        if !account.exists {
+            return None;
+        }
+        let mut info = account.info.clone();
+        info.code = Some(self.code_by_hash(info.code_hash));
+        Some(info)
+    }
+
+    fn code_by_hash(&mut self, code_hash: B256) -> Vec<u8> {
+        if code_hash == KECCAK256_EMPTY {
+            return Vec::new();
+        }
+        if let Some(code) = self.contracts.get(&code_hash) {
+            return code.clone();
+        }
+        let code = self.db.code_by_hash(code_hash);
+        self.contracts.insert(code_hash, code.clone());
+        code
+    }
+
+    fn storage(&mut self, address: Address, slot: U256) -> U256 {
+        let account = self.account_mut(address);
+        if let Some(value) = account.storage.get(&slot) {
+            return *value;
+        }
+        // an account the database doesn't have can't have storage there either
+        let value = if account.exists {
+            self.db.storage(address, slot)
+        } else {
+            U256::ZERO
+        };
+        self.account_mut(address).storage.insert(slot, value);
+        value
+    }
+
+    fn block_hash(&mut self, number: u64) -> B256 {
+        if let Some(hash) = self.block_hashes.get(&number) {
+            return *hash;
+        }
+        let hash = self.db.block_hash(number);
+        self.block_hashes.insert(number, hash);
+        hash
+    }
+}
+
+// lets caches stack, committing the top one only fills the one below
+impl<D> DatabaseCommit for CacheDB<D> {
+    fn apply(&mut self, changes: HashMap<Address, DbAccount>) {
+        for (address, mut change) in changes {
+            if !change.exists {
+                continue;
+            }
+            if let Some(code) = change.info.code.take() {
+                self.contracts.insert(change.info.code_hash, code);
+            }
+            let account = self.accounts.entry(address).or_default();
+            account.info = change.info;
+            account.exists = true;
+            account.storage.extend(change.storage);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // counts how often the database underneath is asked
+    #[derive(Default)]
+    struct CountingDB {
+        inner: InMemoryDB,
+        reads: usize,
+    }
+
+    impl Database for CountingDB {
+        fn basic(&mut self, address: Address) -> Option<AccountInfo> {
+            self.reads += 1;
+            self.inner.basic(address)
+        }
+
+        fn code_by_hash(&mut self, code_hash: B256) -> Vec<u8> {
+            self.reads += 1;
+            self.inner.code_by_hash(code_hash)
+        }
+
+        fn storage(&mut self, address: Address, slot: U256) -> U256 {
+            self.reads += 1;
+            self.inner.storage(address, slot)
+        }
+
+        fn block_hash(&mut self, number: u64) -> B256 {
+            self.reads += 1;
+            self.inner.block_hash(number)
+        }
+    }
+
+    #[test]
+    fn test_reads_are_cached() {
+        let address = Address::repeat_byte(0x11);
+        let mut inner = InMemoryDB::default();
+        inner.insert_account(address, AccountInfo::new(U256::from(5), 1, vec![0x00]));
+        inner.insert_storage(address, U256::from(1), U256::from(7));
+        let mut cache = CacheDB::new(CountingDB { inner, reads: 0 });
+
+        for _ in 0..2 {
+            assert_eq!(cache.basic(address).unwrap().balance, U256::from(5));
+            assert_eq!(cache.storage(address, U256::from(1)), U256::from(7));
+        }
+        // the code came along with the account
+        assert_eq!(cache.db.reads, 2);
+    }
+
+    #[test]
+    fn test_missing_accounts() {
+        let mut cache = InMemoryDB::default();
+        let address = Address::repeat_byte(0x11);
+        assert_eq!(cache.basic(address), None);
+        assert_eq!(cache.storage(address, U256::from(1)), U256::ZERO);
+
+        cache.insert_storage(address, U256::from(1), U256::from(2));
+        let info = cache.basic(address).unwrap();
+        assert!(info.is_empty());
+        assert_eq!(info.code, Some(vec![]));
+    }
+
+    #[test]
+    fn test_writes_stay_in_the_cache_until_commit() {
+        let address = Address::repeat_byte(0x11);
+        let mut inner = InMemoryDB::default();
+        inner.insert_storage(address, U256::from(1), U256::from(7));
+        inner.insert_storage(address, U256::from(2), U256::from(8));
+        let mut cache = CacheDB::new(inner);
+
+        cache.insert_account(address, AccountInfo::new(U256::from(9), 0, vec![0x60, 0x00]));
+        cache.insert_storage(address, U256::from(1), U256::from(70));
+        assert_eq!(cache.db.storage(address, U256::from(1)), U256::from(7));
+        assert_eq!(cache.db.basic(address).unwrap().balance, U256::ZERO);
+
+        cache.commit();
+        assert!(cache.accounts.is_empty());
+        let info = cache.db.basic(address).unwrap();
+        assert_eq!(info.balance, U256::from(9));
+        assert_eq!(info.code, Some(vec![0x60, 0x00]));
+        assert_eq!(cache.db.storage(address, U256::from(1)), U256::from(70));
+        // untouched slots are left alone
+        assert_eq!(cache.db.storage(address, U256::from(2)), U256::from(8));
+        // and reads go through to the committed state
+        assert_eq!(cache.storage(address, U256::from(1)), U256::from(70));
+    }
+}
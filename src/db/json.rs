@@ -0,0 +1,200 @@
+// A database kept in a JSON file, in the same shape as the `pre` / `alloc` sections of the
+// Ethereum test fixtures:
+//   { "0x1000...": { "balance": "0x0de0b6b3a7640000", "nonce": "0x01", "code": "0x6000",
+//                    "storage": { "0x00": "0x01" } } }
+// The whole file is read on `open`, `save` writes the current state back. It holds no block hashes.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use alloy_primitives::{Address, B256, Bytes, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::db::{AccountInfo, Database, DatabaseCommit, DbAccount, InMemoryDB};
+
+#[derive(Debug)]
+pub enum JsonDbError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+// one account of the file, also what the `pre` section of a state test holds
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct JsonAccount {
+    #[serde(default)]
+    balance: U256,
+    #[serde(default)]
+    nonce: U256,
+    #[serde(default)]
+    code: Bytes,
+    #[serde(default)]
+    storage: BTreeMap<U256, U256>,
+}
+
+impl JsonAccount {
+    // puts the account and its storage into `state`
+    pub(crate) fn insert(&self, address: Address, state: &mut InMemoryDB) {
+        let nonce = self.nonce.saturating_to();
+        state.insert_account(address, AccountInfo::new(self.balance, nonce, self.code.to_vec()));
+        for (slot, value) in &self.storage {
+            state.insert_storage(address, *slot, *value);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonFileDB {
+    path: PathBuf,
+    state: InMemoryDB,
+}
+
+impl JsonFileDB {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, JsonDbError> {
+        let path = path.as_ref().to_path_buf();
+        let json = fs::read_to_string(&path).map_err(JsonDbError::Io)?;
+        let mut db = Self::from_json(&json)?;
+        db.path = path;
+        Ok(db)
+    }
+
+    // a database that isn't tied to a file yet, `save_as` gives it one
+    pub fn from_json(json: &str) -> Result<Self, JsonDbError> {
+        let accounts: BTreeMap<Address, JsonAccount> =
+            serde_json::from_str(json).map_err(JsonDbError::Json)?;
+        let mut state = InMemoryDB::default();
+        for (address, account) in accounts {
+            account.insert(address, &mut state);
+        }
+        Ok(Self {
+            path: PathBuf::new(),
+            state,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn to_json(&self) -> String {
+        let accounts: BTreeMap<Address, JsonAccount> = self
+            .state
+            .accounts
+            .iter()
+            .filter(|(_, account)| account.exists)
+            .map(|(address, account)| {
+                let code = self
+                    .state
+                    .contracts
+                    .get(&account.info.code_hash)
+                    .cloned()
+                    .unwrap_or_default();
+                // cleared slots are dropped, like on chain
+                let storage = account
+                    .storage
+                    .iter()
+                    .filter(|(_, value)| **value != U256::ZERO)
+                    .map(|(slot, value)| (*slot, *value))
+                    .collect();
+                let json = JsonAccount {
+                    balance: account.info.balance,
+                    nonce: U256::from(account.info.nonce),
+                    code: code.into(),
+                    storage,
+                };
+                (*address, json)
+            })
+            .collect();
+        // plain maps of strings always serialize
+        serde_json::to_string_pretty(&accounts).expect("state serializes to JSON")
+    }
+
+    // writes the state back to the file it was opened from
+    pub fn save(&self) -> Result<(), JsonDbError> {
+        fs::write(&self.path, self.to_json()).map_err(JsonDbError::Io)
+    }
+
+    pub fn save_as(&mut self, path: impl AsRef<Path>) -> Result<(), JsonDbError> {
+        self.path = path.as_ref().to_path_buf();
+        self.save()
+    }
+}
+
+impl Database for JsonFileDB {
+    fn basic(&mut self, address: Address) -> Option<AccountInfo> {
+        self.state.basic(address)
+    }
+
+    fn code_by_hash(&mut self, code_hash: B256) -> Vec<u8> {
+        self.state.code_by_hash(code_hash)
+    }
+
+    fn storage(&mut self, address: Address, slot: U256) -> U256 {
+        self.state.storage(address, slot)
+    }
+
+    fn block_hash(&mut self, _number: u64) -> B256 {
+        B256::ZERO
+    }
+}
+
+// committed changes only reach the file on `save`
+impl DatabaseCommit for JsonFileDB {
+    fn apply(&mut self, changes: HashMap<Address, DbAccount>) {
+        self.state.apply(changes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::address;
+
+    use super::*;
+    use crate::db::CacheDB;
+
+    const STATE: &str = r#"{
+        "0x1000000000000000000000000000000000000000": {
+            "balance": "0x0a",
+            "nonce": "0x01",
+            "code": "0x600100",
+            "storage": { "0x01": "0x02" }
+        },
+        "0x2000000000000000000000000000000000000000": { "balance": "0x05" }
+    }"#;
+
+    #[test]
+    fn test_reads_the_fixture_format() {
+        let mut db = JsonFileDB::from_json(STATE).unwrap();
+        let contract = address!("1000000000000000000000000000000000000000");
+        let info = db.basic(contract).unwrap();
+        assert_eq!(info.balance, U256::from(10));
+        assert_eq!(info.nonce, 1);
+        assert_eq!(info.code, Some(vec![0x60, 0x01, 0x00]));
+        assert_eq!(db.storage(contract, U256::from(1)), U256::from(2));
+
+        let wallet = address!("2000000000000000000000000000000000000000");
+        assert!(db.basic(wallet).unwrap().code.unwrap().is_empty());
+        assert_eq!(db.basic(Address::ZERO), None);
+    }
+
+    #[test]
+    fn test_commit_and_save_round_trip() {
+        let path = std::env::temp_dir().join(format!("rsevm-json-db-{}.json", std::process::id()));
+        fs::write(&path, STATE).unwrap();
+
+        let contract = address!("1000000000000000000000000000000000000000");
+        let mut cache = CacheDB::new(JsonFileDB::open(&path).unwrap());
+        cache.insert_storage(contract, U256::from(1), U256::ZERO);
+        cache.insert_storage(contract, U256::from(3), U256::from(4));
+        cache.commit();
+        cache.db.save().unwrap();
+
+        let mut reopened = JsonFileDB::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(reopened.storage(contract, U256::from(1)), U256::ZERO);
+        assert_eq!(reopened.storage(contract, U256::from(3)), U256::from(4));
+        assert_eq!(reopened.basic(contract).unwrap().balance, U256::from(10));
+    }
+}
@@ -0,0 +1,90 @@
+// Where the world state comes from.
+// A `Database` only answers questions about accounts, code, storage and old blocks, it never
+// changes. Writes made while running land in a `CacheDB` on top of it, which can hand them back
+// to anything that implements `DatabaseCommit`:
+//   Database (any backend) <- CacheDB (reads cached, writes kept) <- DatabaseHost <- EVM
+
+use std::collections::HashMap;
+
+use alloy_primitives::{Address, B256, KECCAK256_EMPTY, U256, keccak256};
+
+pub mod cache;
+pub mod json;
+
+pub use cache::{CacheDB, DbAccount, InMemoryDB};
+pub use json::{JsonDbError, JsonFileDB};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountInfo {
+    pub balance: U256,
+    pub nonce: u64,
+    pub code_hash: B256,
+    // the code itself, if the backend has it at hand, otherwise it's looked up by `code_hash`
+    pub code: Option<Vec<u8>>,
+}
+
+impl Default for AccountInfo {
+    fn default() -> Self {
+        Self {
+            balance: U256::ZERO,
+            nonce: 0,
+            code_hash: KECCAK256_EMPTY,
+            code: None,
+        }
+    }
+}
+
+impl AccountInfo {
+    pub fn new(balance: U256, nonce: u64, code: Vec<u8>) -> Self {
+        Self {
+            balance,
+            nonce,
+            code_hash: keccak256(&code),
+            code: Some(code),
+        }
+    }
+
+    // no balance, no nonce and no code (EIP-161)
+    pub fn is_empty(&self) -> bool {
+        self.balance == U256::ZERO && self.nonce == 0 && self.code_hash == KECCAK256_EMPTY
+    }
+}
+
+// Read-only access to the state, the methods take `&mut self` so a backend may cache what it reads.
+// A backend that can fail has to deal with it on its own side, e.g. by loading upfront.
+pub trait Database {
+    // None if there is no account at `address`
+    fn basic(&mut self, address: Address) -> Option<AccountInfo>;
+    fn code_by_hash(&mut self, code_hash: B256) -> Vec<u8>;
+    // zero for slots that were never written
+    fn storage(&mut self, address: Address, slot: U256) -> U256;
+    fn block_hash(&mut self, number: u64) -> B256;
+}
+
+// a backend that can take the writes of a `CacheDB` back
+pub trait DatabaseCommit {
+    // the final state of every account in `changes`, storage slots that aren't listed keep their value
+    fn apply(&mut self, changes: HashMap<Address, DbAccount>);
+}
+
+// no accounts, no blocks, every slot is zero
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EmptyDB;
+
+impl Database for EmptyDB {
+    fn basic(&mut self, _address: Address) -> Option<AccountInfo> {
+        None
+    }
+
+    fn code_by_hash(&mut self, _code_hash: B256) -> Vec<u8> {
+        Vec::new()
+    }
+
+    fn storage(&mut self, _address: Address, _slot: U256) -> U256 {
+        U256::ZERO
+    }
+
+    fn block_hash(&mut self, _number: u64) -> B256 {
+        B256::ZERO
+    }
+}
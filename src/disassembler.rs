@@ -0,0 +1,165 @@
+// Turns bytecode back into readable instructions, using the opcode table for names and immediates.
+// Legacy code is read from start to end. An EOF container is split into its code sections,
+// keeping every pc absolute so it lines up with `EVM::pc`.
+// The `_with` variants take an `OpcodeTable`, so custom instructions show up under their own names.
+
+use std::fmt::{self, Display};
+
+use alloy_primitives::hex;
+
+use crate::{
+    eof::{Container, is_eof},
+    opcodes::info::OpcodeTable,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    pub pc: usize,
+    pub opcode: u8,
+    pub mnemonic: String,
+    // may be shorter than the opcode expects if the code ends early
+    pub immediate: Vec<u8>,
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04x}: {}", self.pc, self.mnemonic)?;
+        if !self.immediate.is_empty() {
+            write!(f, " 0x{}", hex::encode(&self.immediate))?;
+        }
+        Ok(())
+    }
+}
+
+// disassembles legacy code, pcs start at 0
+pub fn disassemble(code: &[u8]) -> Vec<Instruction> {
+    disassemble_with(code, &OpcodeTable::default())
+}
+
+pub fn disassemble_with(code: &[u8], table: &OpcodeTable) -> Vec<Instruction> {
+    disassemble_code(table, code, 0, false)
+}
+
+// disassembles whatever the EVM would run: the code sections of a valid EOF container,
+// or the whole program as legacy code
+pub fn disassemble_program(program: &[u8]) -> Vec<Instruction> {
+    disassemble_program_with(program, &OpcodeTable::default())
+}
+
+pub fn disassemble_program_with(program: &[u8], table: &OpcodeTable) -> Vec<Instruction> {
+    if is_eof(program)
+        && let Ok(container) = Container::parse(program)
+    {
+        return (0..container.code_sections.len())
+            .flat_map(|section| {
+                let code = container.code(section);
+                disassemble_code(table, code, container.code_offset(section), true)
+            })
+            .collect();
+    }
+    disassemble_with(program, table)
+}
+
+// one instruction per line
+pub fn format_instructions(instructions: &[Instruction]) -> String {
+    instructions
+        .iter()
+        .map(|instruction| instruction.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn disassemble_code(
+    table: &OpcodeTable,
+    code: &[u8],
+    start_pc: usize,
+    eof: bool,
+) -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+    let mut pc = 0;
+    while pc < code.len() {
+        let opcode = code[pc];
+        // an instruction only has immediates in the kind of code it is defined in
+        let defined = table.get(opcode).is_some_and(|info| {
+            if eof {
+                info.is_valid_in_eof()
+            } else {
+                info.is_valid_in_legacy()
+            }
+        });
+        let size = if defined {
+            // a jump table cut short by the end of the code keeps what is left
+            table.immediate_size(code, pc).unwrap_or(code.len() - pc - 1)
+        } else {
+            0
+        };
+        let end = (pc + 1 + size).min(code.len());
+        instructions.push(Instruction {
+            pc: start_pc + pc,
+            opcode,
+            mnemonic: table.name(opcode),
+            immediate: code[pc + 1..end].to_vec(),
+        });
+        pc += 1 + size;
+    }
+    instructions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fork::Fork, opcodes::info::OpcodeInfo};
+
+    #[test]
+    fn test_disassemble_legacy() {
+        let code = [0x60, 0x2A, 0x60, 0x00, 0x52, 0xFD];
+        let listing = format_instructions(&disassemble(&code));
+        assert_eq!(
+            listing,
+            "0000: PUSH1 0x2a\n0002: PUSH1 0x00\n0004: MSTORE\n0005: REVERT"
+        );
+    }
+
+    #[test]
+    fn test_disassemble_truncated_push() {
+        let instructions = disassemble(&[0x61, 0xFF]);
+        assert_eq!(instructions.len(), 1);
+        assert_eq!(instructions[0].immediate, vec![0xFF]);
+        assert_eq!(instructions[0].to_string(), "0000: PUSH2 0xff");
+    }
+
+    #[test]
+    fn test_eof_opcodes_have_no_immediates_in_legacy_code() {
+        let instructions = disassemble(&[0xE0, 0x00, 0x00]);
+        assert_eq!(instructions.len(), 3);
+    }
+
+    #[test]
+    fn test_disassemble_eof_container() {
+        // one section: PUSH1 0 RJUMPI +0 STOP
+        let mut program = vec![
+            0xEF, 0x00, 0x01, 0x01, 0x00, 0x04, 0x02, 0x00, 0x01, 0x00, 0x06, 0xFF, 0x00, 0x00,
+            0x00, 0x00, 0x80, 0x00, 0x01,
+        ];
+        let code_start = program.len();
+        program.extend_from_slice(&[0x60, 0x00, 0xE1, 0x00, 0x00, 0x00]);
+
+        let instructions = disassemble_program(&program);
+        let mnemonics: Vec<String> = instructions.iter().map(|i| i.mnemonic.clone()).collect();
+        assert_eq!(mnemonics, ["PUSH1", "RJUMPI", "STOP"]);
+        assert_eq!(instructions[0].pc, code_start);
+        assert_eq!(instructions[1].immediate, vec![0x00, 0x00]);
+    }
+
+    #[test]
+    fn test_disassemble_with_custom_opcodes() {
+        let mut table = OpcodeTable::default();
+        table.insert(0x0C, OpcodeInfo::new("MYPUSH", 0, 1, 3, Fork::LATEST).immediate(1));
+        let code = [0x0C, 0x2A, 0x00];
+        assert_eq!(
+            format_instructions(&disassemble_with(&code, &table)),
+            "0000: MYPUSH 0x2a\n0002: STOP"
+        );
+        assert_eq!(disassemble(&code)[0].mnemonic, "UNKNOWN(0x0c)");
+    }
+}
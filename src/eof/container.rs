@@ -0,0 +1,337 @@
+use std::ops::Range;
+
+use crate::eof::{EOF_MAGIC, EOF_VERSION, EofError};
+
+// section kinds as they appear in the header
+pub const KIND_TYPES: u8 = 0x01;
+pub const KIND_CODE: u8 = 0x02;
+pub const KIND_CONTAINER: u8 = 0x03;
+pub const KIND_DATA: u8 = 0xFF;
+pub const TERMINATOR: u8 = 0x00;
+
+// limits from EIP-3540
+pub const MAX_CODE_SECTIONS: usize = 1024;
+pub const MAX_CONTAINER_SECTIONS: usize = 256;
+// outputs value used by a code section that never returns to its caller (e.g. the entry point)
+pub const NON_RETURNING: u8 = 0x80;
+pub const MAX_STACK_INCREASE: u16 = 0x03FF;
+const TYPE_ENTRY_SIZE: usize = 4;
+
+// an entry of the types section, one per code section
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TypeSection {
+    pub inputs: u8,
+    pub outputs: u8,
+    pub max_stack_increase: u16,
+}
+
+impl TypeSection {
+    pub fn is_non_returning(&self) -> bool {
+        self.outputs == NON_RETURNING
+    }
+    // the highest the stack can get while this section runs, counted from the caller's frame
+    pub fn max_stack_height(&self) -> usize {
+        self.inputs as usize + self.max_stack_increase as usize
+    }
+}
+
+// A parsed EOF container.
+// Section positions are kept as byte ranges into `raw` so that the interpreter can keep using
+// absolute program counters: PUSH immediates and relative jumps work exactly as in legacy code.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Container {
+    pub version: u8,
+    pub types: Vec<TypeSection>,
+    pub code_sections: Vec<Range<usize>>,
+    pub container_sections: Vec<Range<usize>>,
+    pub data_section: Range<usize>,
+    pub raw: Vec<u8>,
+}
+
+// cursor over the header bytes
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn u8(&mut self) -> Result<u8, EofError> {
+        let byte = *self.bytes.get(self.pos).ok_or(EofError::TruncatedHeader)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+    fn u16(&mut self) -> Result<usize, EofError> {
+        Ok(((self.u8()? as usize) << 8) | self.u8()? as usize)
+    }
+    fn u32(&mut self) -> Result<usize, EofError> {
+        Ok((self.u16()? << 16) | self.u16()?)
+    }
+    fn expect_kind(&mut self, kind: u8) -> Result<(), EofError> {
+        let found = self.bytes.get(self.pos).copied();
+        if found != Some(kind) {
+            return Err(EofError::MissingSection {
+                expected: kind,
+                found,
+            });
+        }
+        self.pos += 1;
+        Ok(())
+    }
+}
+
+impl Container {
+    // parses the header and splits the body into its sections
+    // this only checks the layout, use `eof::validate` to check the code itself
+    pub fn parse(bytes: &[u8]) -> Result<Self, EofError> {
+        if !bytes.starts_with(&EOF_MAGIC) {
+            return Err(EofError::MissingMagic);
+        }
+        let mut reader = Reader { bytes, pos: 2 };
+        let version = reader.u8()?;
+        if version != EOF_VERSION {
+            return Err(EofError::UnsupportedVersion(version));
+        }
+
+        // types section header
+        reader.expect_kind(KIND_TYPES)?;
+        let types_size = reader.u16()?;
+        if types_size < TYPE_ENTRY_SIZE || types_size % TYPE_ENTRY_SIZE != 0 {
+            return Err(EofError::InvalidSectionSize {
+                kind: KIND_TYPES,
+                size: types_size,
+            });
+        }
+
+        // code sections header
+        reader.expect_kind(KIND_CODE)?;
+        let num_code_sections = reader.u16()?;
+        if num_code_sections == 0 {
+            return Err(EofError::InvalidSectionSize {
+                kind: KIND_CODE,
+                size: 0,
+            });
+        }
+        if num_code_sections > MAX_CODE_SECTIONS {
+            return Err(EofError::TooManySections {
+                kind: KIND_CODE,
+                count: num_code_sections,
+            });
+        }
+        // every code section needs exactly one entry in the types section
+        if types_size / TYPE_ENTRY_SIZE != num_code_sections {
+            return Err(EofError::InvalidSectionSize {
+                kind: KIND_TYPES,
+                size: types_size,
+            });
+        }
+        let mut code_sizes = Vec::with_capacity(num_code_sections);
+        for _ in 0..num_code_sections {
+            let size = reader.u16()?;
+            if size == 0 {
+                return Err(EofError::InvalidSectionSize {
+                    kind: KIND_CODE,
+                    size,
+                });
+            }
+            code_sizes.push(size);
+        }
+
+        // optional container sections header
+        let mut container_sizes = Vec::new();
+        if bytes.get(reader.pos) == Some(&KIND_CONTAINER) {
+            reader.pos += 1;
+            let num_container_sections = reader.u16()?;
+            if num_container_sections == 0 {
+                return Err(EofError::InvalidSectionSize {
+                    kind: KIND_CONTAINER,
+                    size: 0,
+                });
+            }
+            if num_container_sections > MAX_CONTAINER_SECTIONS {
+                return Err(EofError::TooManySections {
+                    kind: KIND_CONTAINER,
+                    count: num_container_sections,
+                });
+            }
+            for _ in 0..num_container_sections {
+                let size = reader.u32()?;
+                if size == 0 {
+                    return Err(EofError::InvalidSectionSize {
+                        kind: KIND_CONTAINER,
+                        size,
+                    });
+                }
+                container_sizes.push(size);
+            }
+        }
+
+        // data section header, the data section may be empty
+        reader.expect_kind(KIND_DATA)?;
+        let data_size = reader.u16()?;
+        if reader.u8().map_err(|_| EofError::MissingTerminator)? != TERMINATOR {
+            return Err(EofError::MissingTerminator);
+        }
+
+        // the body must be exactly as large as the header says
+        let header_size = reader.pos;
+        let body_size = types_size
+            + code_sizes.iter().sum::<usize>()
+            + container_sizes.iter().sum::<usize>()
+            + data_size;
+        if bytes.len() != header_size + body_size {
+            return Err(EofError::BodySizeMismatch {
+                expected: header_size + body_size,
+                found: bytes.len(),
+            });
+        }
+
+        // types section body
+        let mut types = Vec::with_capacity(num_code_sections);
+        for (section, entry) in bytes[header_size..header_size + types_size]
+            .chunks(TYPE_ENTRY_SIZE)
+            .enumerate()
+        {
+            let type_section = TypeSection {
+                inputs: entry[0],
+                outputs: entry[1],
+                max_stack_increase: u16::from_be_bytes([entry[2], entry[3]]),
+            };
+            Self::check_type(section, &type_section)?;
+            types.push(type_section);
+        }
+
+        // lay out the remaining sections one after the other
+        let mut offset = header_size + types_size;
+        let mut code_sections = Vec::with_capacity(num_code_sections);
+        for size in code_sizes {
+            code_sections.push(offset..offset + size);
+            offset += size;
+        }
+        let mut container_sections = Vec::with_capacity(container_sizes.len());
+        for size in container_sizes {
+            container_sections.push(offset..offset + size);
+            offset += size;
+        }
+        let data_section = offset..offset + data_size;
+
+        Ok(Self {
+            version,
+            types,
+            code_sections,
+            container_sections,
+            data_section,
+            raw: bytes.to_vec(),
+        })
+    }
+
+    fn check_type(section: usize, type_section: &TypeSection) -> Result<(), EofError> {
+        let invalid = |reason: &str| EofError::InvalidTypeSection {
+            section,
+            reason: reason.to_string(),
+        };
+        if type_section.inputs > 0x7F {
+            return Err(invalid("too many inputs"));
+        }
+        if type_section.outputs > NON_RETURNING {
+            return Err(invalid("too many outputs"));
+        }
+        if type_section.max_stack_increase > MAX_STACK_INCREASE {
+            return Err(invalid("max stack increase above 1023"));
+        }
+        // the entry point takes nothing and never returns
+        if section == 0 && (type_section.inputs != 0 || !type_section.is_non_returning()) {
+            return Err(invalid("first code section must have 0 inputs and be non-returning"));
+        }
+        Ok(())
+    }
+
+    pub fn code(&self, section: usize) -> &[u8] {
+        &self.raw[self.code_sections[section].clone()]
+    }
+
+    // absolute position of the first byte of a code section, used as the program counter on entry
+    pub fn code_offset(&self, section: usize) -> usize {
+        self.code_sections[section].start
+    }
+
+    pub fn container(&self, index: usize) -> &[u8] {
+        &self.raw[self.container_sections[index].clone()]
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.raw[self.data_section.clone()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 1 code section (STOP), no data
+    const MINIMAL: [u8; 19] = [
+        0xEF, 0x00, 0x01, // magic + version
+        0x01, 0x00, 0x04, // types: 4 bytes
+        0x02, 0x00, 0x01, 0x00, 0x01, // code: 1 section of 1 byte
+        0xFF, 0x00, 0x00, // data: 0 bytes
+        0x00, // terminator
+        0x00, 0x80, 0x00, 0x00, // types: 0 inputs, non-returning, 0 max stack increase
+    ];
+
+    fn minimal_with_code() -> Vec<u8> {
+        let mut bytes = MINIMAL.to_vec();
+        bytes.push(0x00); // STOP
+        bytes
+    }
+
+    #[test]
+    fn test_parse_minimal_container() {
+        let container = Container::parse(&minimal_with_code()).unwrap();
+        assert_eq!(container.types.len(), 1);
+        assert!(container.types[0].is_non_returning());
+        assert_eq!(container.code(0), &[0x00]);
+        assert_eq!(container.code_offset(0), 19);
+        assert!(container.data().is_empty());
+    }
+
+    #[test]
+    fn test_parse_data_section() {
+        let mut bytes = minimal_with_code();
+        bytes[13] = 0x02; // 2 bytes of data
+        bytes.extend_from_slice(&[0xAA, 0xBB]);
+        let container = Container::parse(&bytes).unwrap();
+        assert_eq!(container.data(), &[0xAA, 0xBB]);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_version() {
+        let mut bytes = minimal_with_code();
+        bytes[2] = 0x02;
+        assert_eq!(
+            Container::parse(&bytes),
+            Err(EofError::UnsupportedVersion(0x02))
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_body_size_mismatch() {
+        let mut bytes = minimal_with_code();
+        bytes.push(0x00);
+        assert_eq!(
+            Container::parse(&bytes),
+            Err(EofError::BodySizeMismatch {
+                expected: 20,
+                found: 21
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_returning_entry_point() {
+        let mut bytes = minimal_with_code();
+        bytes[16] = 0x00; // outputs = 0
+        assert!(matches!(
+            Container::parse(&bytes),
+            Err(EofError::InvalidTypeSection { section: 0, .. })
+        ));
+    }
+}
@@ -0,0 +1,73 @@
+// EVM Object Format (EOF)
+// An EOF container is bytecode wrapped in a small header that splits it into typed sections:
+//   header -> types -> code sections -> sub containers -> data
+// Unlike legacy bytecode, a container is validated once at deploy time (EIP-3670, EIP-4200, EIP-5450),
+// so the interpreter never has to check jump destinations or stack heights of known instructions at runtime.
+
+pub mod container;
+pub mod validation;
+
+pub use container::{Container, TypeSection};
+pub use validation::validate;
+
+pub const EOF_MAGIC: [u8; 2] = [0xEF, 0x00];
+pub const EOF_VERSION: u8 = 0x01;
+
+// returns true if the bytecode should be executed as an EOF container
+pub fn is_eof(code: &[u8]) -> bool {
+    code.starts_with(&EOF_MAGIC)
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum EofError {
+    // header and body layout
+    MissingMagic,
+    UnsupportedVersion(u8),
+    MissingSection { expected: u8, found: Option<u8> },
+    MissingTerminator,
+    TruncatedHeader,
+    InvalidSectionSize { kind: u8, size: usize },
+    TooManySections { kind: u8, count: usize },
+    BodySizeMismatch { expected: usize, found: usize },
+    InvalidTypeSection { section: usize, reason: String },
+    // code validation
+    UndefinedInstruction { section: usize, pc: usize, opcode: u8 },
+    TruncatedImmediate { section: usize, pc: usize },
+    InvalidJumpTarget { section: usize, pc: usize, target: isize },
+    InvalidSectionIndex { section: usize, pc: usize, target: usize },
+    InvalidDataOffset { section: usize, pc: usize, offset: usize },
+    UnreachableCode { section: usize, pc: usize },
+    UnreachableSection { section: usize },
+    // stack validation
+    StackUnderflow { section: usize, pc: usize },
+    StackOverflow { section: usize, pc: usize },
+    StackHeightMismatch { section: usize, pc: usize },
+    MaxStackHeightMismatch { section: usize, expected: usize, found: usize },
+    NoTerminatingInstruction { section: usize },
+    InvalidReturn { section: usize, pc: usize, reason: String },
+}
+
+// builds a container from (inputs, outputs, max_stack_increase) types, code sections and data,
+// for the tests here and the integration tests, it does no checking of its own
+#[doc(hidden)]
+pub fn test_container(types: &[(u8, u8, u16)], code: &[&[u8]], data: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![0xEF, 0x00, EOF_VERSION, 0x01];
+    bytes.extend_from_slice(&((types.len() * 4) as u16).to_be_bytes());
+    bytes.push(0x02);
+    bytes.extend_from_slice(&(code.len() as u16).to_be_bytes());
+    for section in code {
+        bytes.extend_from_slice(&(section.len() as u16).to_be_bytes());
+    }
+    bytes.push(0xFF);
+    bytes.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    bytes.push(0x00);
+    for (inputs, outputs, max) in types {
+        bytes.extend_from_slice(&[*inputs, *outputs]);
+        bytes.extend_from_slice(&max.to_be_bytes());
+    }
+    for section in code {
+        bytes.extend_from_slice(section);
+    }
+    bytes.extend_from_slice(data);
+    bytes
+}
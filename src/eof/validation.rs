@@ -0,0 +1,405 @@
+// Deploy-time validation of an EOF container
+// - EIP-3670: every instruction is defined and no immediate is truncated
+// - EIP-4200: relative jumps land inside the section and on an instruction, never on an immediate
+// - EIP-4750: CALLF / JUMPF target existing sections, RETF only appears in returning sections
+// - EIP-5450: the stack height at every instruction is known ahead of time and can never underflow
+
+use std::collections::VecDeque;
+
+use crate::{
+    eof::{Container, EofError},
+    opcodes::{
+        info::{OpcodeInfo, immediate_size, opcode_info},
+        opcodes::{CALLF, DATALOADN, DUPN, EXCHANGE, JUMPF, RETF, RJUMP, RJUMPI, RJUMPV, SWAPN},
+    },
+    stack::MAXIMUM_STACK_SIZE,
+};
+
+// an instruction allowed inside a container
+// Legacy instructions that observe or depend on code layout (JUMP, JUMPI, PC, CODESIZE, CODECOPY,
+// EXTCODE*, GAS, CALL*, CREATE*, SELFDESTRUCT) are undefined in EOF.
+// CALLF, JUMPF, RETF, DUPN, SWAPN and EXCHANGE depend on their immediate and are handled separately.
+fn instruction(opcode: u8) -> Option<&'static OpcodeInfo> {
+    opcode_info(opcode).filter(|info| info.is_valid_in_eof())
+}
+
+fn read_u16(code: &[u8], pos: usize) -> usize {
+    ((code[pos] as usize) << 8) | code[pos + 1] as usize
+}
+
+fn read_i16(code: &[u8], pos: usize) -> isize {
+    i16::from_be_bytes([code[pos], code[pos + 1]]) as isize
+}
+
+// targets of a relative jump, relative to the end of the instruction
+fn jump_targets(code: &[u8], pc: usize, next: usize) -> Vec<isize> {
+    match code[pc] {
+        RJUMP | RJUMPI => vec![next as isize + read_i16(code, pc + 1)],
+        RJUMPV => {
+            let count = code[pc + 1] as usize + 1;
+            (0..count)
+                .map(|i| next as isize + read_i16(code, pc + 2 + i * 2))
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+// validates every code section and, recursively, every sub container
+pub fn validate(container: &Container) -> Result<(), EofError> {
+    let mut section_targets = Vec::with_capacity(container.code_sections.len());
+    for section in 0..container.code_sections.len() {
+        validate_instructions(container, section)?;
+        section_targets.push(validate_stack(container, section)?);
+    }
+
+    // every code section must be reachable from the entry point through CALLF / JUMPF
+    let mut reached = vec![false; container.code_sections.len()];
+    let mut queue = VecDeque::from([0usize]);
+    reached[0] = true;
+    while let Some(section) = queue.pop_front() {
+        for &target in &section_targets[section] {
+            if !reached[target] {
+                reached[target] = true;
+                queue.push_back(target);
+            }
+        }
+    }
+    if let Some(section) = reached.iter().position(|r| !r) {
+        return Err(EofError::UnreachableSection { section });
+    }
+
+    for index in 0..container.container_sections.len() {
+        validate(&Container::parse(container.container(index))?)?;
+    }
+    Ok(())
+}
+
+// EIP-3670 and EIP-4200: opcodes, immediates and static jump destinations
+fn validate_instructions(container: &Container, section: usize) -> Result<(), EofError> {
+    let code = container.code(section);
+    // true where an instruction starts, used to reject jumps into immediates
+    let mut boundaries = vec![false; code.len()];
+    let mut jumps = Vec::new();
+
+    let mut pc = 0;
+    while pc < code.len() {
+        let opcode = code[pc];
+        instruction(opcode).ok_or(EofError::UndefinedInstruction {
+            section,
+            pc,
+            opcode,
+        })?;
+        let size = immediate_size(code, pc).ok_or(EofError::TruncatedImmediate { section, pc })?;
+        let next = pc + 1 + size;
+        if next > code.len() {
+            return Err(EofError::TruncatedImmediate { section, pc });
+        }
+        boundaries[pc] = true;
+
+        match opcode {
+            RJUMP | RJUMPI | RJUMPV => jumps.push((pc, jump_targets(code, pc, next))),
+            CALLF | JUMPF => {
+                let target = read_u16(code, pc + 1);
+                if target >= container.code_sections.len() {
+                    return Err(EofError::InvalidSectionIndex { section, pc, target });
+                }
+            }
+            DATALOADN => {
+                let offset = read_u16(code, pc + 1);
+                if offset + 32 > container.data().len() {
+                    return Err(EofError::InvalidDataOffset { section, pc, offset });
+                }
+            }
+            _ => {}
+        }
+        pc = next;
+    }
+
+    for (pc, targets) in jumps {
+        for target in targets {
+            if target < 0 || target as usize >= code.len() || !boundaries[target as usize] {
+                return Err(EofError::InvalidJumpTarget { section, pc, target });
+            }
+        }
+    }
+    Ok(())
+}
+
+// EIP-5450: walks the section once, tracking the (min, max) stack height reaching every instruction.
+// Forward jumps widen the range of their target, backward jumps must match the recorded range exactly.
+// Returns the sections this one calls or jumps to.
+fn validate_stack(container: &Container, section: usize) -> Result<Vec<usize>, EofError> {
+    let code = container.code(section);
+    let type_section = container.types[section];
+    let inputs = type_section.inputs as usize;
+
+    let mut heights: Vec<Option<(usize, usize)>> = vec![None; code.len()];
+    heights[0] = Some((inputs, inputs));
+    let mut max_height = inputs;
+    let mut returns = false;
+    let mut targets = Vec::new();
+
+    let mut pc = 0;
+    while pc < code.len() {
+        let opcode = code[pc];
+        let (min, max) = heights[pc].ok_or(EofError::UnreachableCode { section, pc })?;
+        let info = instruction(opcode).ok_or(EofError::UndefinedInstruction {
+            section,
+            pc,
+            opcode,
+        })?;
+        let (mut pops, mut pushes) = (info.inputs as usize, info.outputs as usize);
+        // validate_instructions already rejected truncated jump tables
+        let next = pc + 1 + immediate_size(code, pc).unwrap_or(info.immediate_size as usize);
+
+        match opcode {
+            CALLF | JUMPF => {
+                let target = read_u16(code, pc + 1);
+                let target_type = container.types[target];
+                targets.push(target);
+                // the callee must fit on the stack on top of what is already there
+                if max + target_type.max_stack_increase as usize > MAXIMUM_STACK_SIZE {
+                    return Err(EofError::StackOverflow { section, pc });
+                }
+                pops = target_type.inputs as usize;
+                if opcode == CALLF {
+                    if target_type.is_non_returning() {
+                        return Err(EofError::InvalidReturn {
+                            section,
+                            pc,
+                            reason: "CALLF to a non-returning section".to_string(),
+                        });
+                    }
+                    pushes = target_type.outputs as usize;
+                } else if !target_type.is_non_returning() {
+                    // a tail call to a returning section returns to our caller on our behalf
+                    if type_section.is_non_returning() || target_type.outputs > type_section.outputs {
+                        return Err(EofError::InvalidReturn {
+                            section,
+                            pc,
+                            reason: "JUMPF to a section returning more than this one".to_string(),
+                        });
+                    }
+                    let expected = type_section.outputs as usize + pops - target_type.outputs as usize;
+                    if min != max || min != expected {
+                        return Err(EofError::StackHeightMismatch { section, pc });
+                    }
+                    returns = true;
+                }
+            }
+            RETF => {
+                if type_section.is_non_returning() {
+                    return Err(EofError::InvalidReturn {
+                        section,
+                        pc,
+                        reason: "RETF in a non-returning section".to_string(),
+                    });
+                }
+                if min != max || min != type_section.outputs as usize {
+                    return Err(EofError::StackHeightMismatch { section, pc });
+                }
+                returns = true;
+            }
+            DUPN => {
+                let n = code[pc + 1] as usize + 1;
+                (pops, pushes) = (n, n + 1);
+            }
+            SWAPN => {
+                let n = code[pc + 1] as usize + 1;
+                (pops, pushes) = (n + 1, n + 1);
+            }
+            EXCHANGE => {
+                let n = (code[pc + 1] >> 4) as usize + 1;
+                let m = (code[pc + 1] & 0x0F) as usize + 1;
+                (pops, pushes) = (n + m + 1, n + m + 1);
+            }
+            _ => {}
+        }
+
+        if min < pops {
+            return Err(EofError::StackUnderflow { section, pc });
+        }
+        let (next_min, next_max) = (min - pops + pushes, max - pops + pushes);
+        if next_max > MAXIMUM_STACK_SIZE {
+            return Err(EofError::StackOverflow { section, pc });
+        }
+        max_height = max_height.max(next_max);
+
+        let mut successors = Vec::new();
+        if !info.terminating && opcode != RJUMP {
+            if next >= code.len() {
+                return Err(EofError::NoTerminatingInstruction { section });
+            }
+            successors.push(next);
+        }
+        successors.extend(jump_targets(code, pc, next).into_iter().map(|t| t as usize));
+
+        for successor in successors {
+            if successor > pc {
+                heights[successor] = Some(match heights[successor] {
+                    Some((lo, hi)) => (lo.min(next_min), hi.max(next_max)),
+                    None => (next_min, next_max),
+                });
+            } else if heights[successor] != Some((next_min, next_max)) {
+                return Err(EofError::StackHeightMismatch { section, pc });
+            }
+        }
+        pc = next;
+    }
+
+    if max_height != type_section.max_stack_height() {
+        return Err(EofError::MaxStackHeightMismatch {
+            section,
+            expected: type_section.max_stack_height(),
+            found: max_height,
+        });
+    }
+    if !type_section.is_non_returning() && !returns {
+        return Err(EofError::InvalidReturn {
+            section,
+            pc: code.len(),
+            reason: "returning section never returns".to_string(),
+        });
+    }
+    Ok(targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eof::test_container;
+
+    fn build(types: &[(u8, u8, u16)], code: &[&[u8]], data: &[u8]) -> Container {
+        Container::parse(&test_container(types, code, data)).unwrap()
+    }
+
+    #[test]
+    fn test_valid_push_and_stop() {
+        let container = build(&[(0, 0x80, 1)], &[&[0x60, 0x01, 0x50, 0x00]], &[]);
+        assert_eq!(validate(&container), Ok(()));
+    }
+
+    #[test]
+    fn test_rejects_legacy_jump() {
+        let container = build(&[(0, 0x80, 1)], &[&[0x60, 0x00, 0x56]], &[]);
+        assert_eq!(
+            validate(&container),
+            Err(EofError::UndefinedInstruction {
+                section: 0,
+                pc: 2,
+                opcode: 0x56
+            })
+        );
+    }
+
+    #[test]
+    fn test_rejects_truncated_push() {
+        let container = build(&[(0, 0x80, 1)], &[&[0x61, 0x00]], &[]);
+        assert_eq!(
+            validate(&container),
+            Err(EofError::TruncatedImmediate { section: 0, pc: 0 })
+        );
+    }
+
+    #[test]
+    fn test_rejects_jump_into_immediate() {
+        // RJUMP +1 lands on the immediate of the PUSH1 that follows
+        let container = build(&[(0, 0x80, 1)], &[&[0xE0, 0x00, 0x01, 0x60, 0x00, 0x00]], &[]);
+        assert_eq!(
+            validate(&container),
+            Err(EofError::InvalidJumpTarget {
+                section: 0,
+                pc: 0,
+                target: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_rejects_stack_underflow() {
+        let container = build(&[(0, 0x80, 0)], &[&[0x01, 0x00]], &[]);
+        assert_eq!(
+            validate(&container),
+            Err(EofError::StackUnderflow { section: 0, pc: 0 })
+        );
+    }
+
+    #[test]
+    fn test_rejects_wrong_max_stack_height() {
+        let container = build(&[(0, 0x80, 2)], &[&[0x60, 0x01, 0x50, 0x00]], &[]);
+        assert_eq!(
+            validate(&container),
+            Err(EofError::MaxStackHeightMismatch {
+                section: 0,
+                expected: 2,
+                found: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_rejects_falling_off_the_end() {
+        let container = build(&[(0, 0x80, 1)], &[&[0x60, 0x01]], &[]);
+        assert_eq!(
+            validate(&container),
+            Err(EofError::NoTerminatingInstruction { section: 0 })
+        );
+    }
+
+    #[test]
+    fn test_rejects_unbalanced_loop() {
+        // JUMPDEST PUSH1 1 RJUMP -6: every iteration grows the stack
+        let container = build(&[(0, 0x80, 1)], &[&[0x5B, 0x60, 0x01, 0xE0, 0xFF, 0xFA]], &[]);
+        assert_eq!(
+            validate(&container),
+            Err(EofError::StackHeightMismatch { section: 0, pc: 3 })
+        );
+    }
+
+    #[test]
+    fn test_valid_callf_and_retf() {
+        // section 0: PUSH1 2 CALLF 1 STOP, section 1 doubles its input: DUP1 ADD RETF
+        let container = build(
+            &[(0, 0x80, 1), (1, 1, 1)],
+            &[&[0x60, 0x02, 0xE3, 0x00, 0x01, 0x00], &[0x80, 0x01, 0xE4]],
+            &[],
+        );
+        assert_eq!(validate(&container), Ok(()));
+    }
+
+    #[test]
+    fn test_rejects_retf_with_wrong_height() {
+        let container = build(
+            &[(0, 0x80, 1), (0, 1, 0)],
+            &[&[0xE3, 0x00, 0x01, 0x00], &[0xE4]],
+            &[],
+        );
+        assert_eq!(
+            validate(&container),
+            Err(EofError::StackHeightMismatch { section: 1, pc: 0 })
+        );
+    }
+
+    #[test]
+    fn test_rejects_unreachable_section() {
+        let container = build(&[(0, 0x80, 0), (0, 0x80, 0)], &[&[0x00], &[0x00]], &[]);
+        assert_eq!(
+            validate(&container),
+            Err(EofError::UnreachableSection { section: 1 })
+        );
+    }
+
+    #[test]
+    fn test_rejects_dataloadn_out_of_bounds() {
+        let container = build(&[(0, 0x80, 1)], &[&[0xD1, 0x00, 0x01, 0x00]], &[0u8; 32]);
+        assert_eq!(
+            validate(&container),
+            Err(EofError::InvalidDataOffset {
+                section: 0,
+                pc: 0,
+                offset: 1
+            })
+        );
+    }
+}
@@ -1,32 +1,48 @@
 use std::{collections::HashMap, fmt::Debug};
 
-use alloy_primitives::{Address, U256};
+use alloy_primitives::{Address, U256, hex};
 
 use crate::{
-    memory::Memory,
+    eof::{self, Container, EofError},
+    gas::GasSchedule,
+    host::{Env, Host, InMemoryHost},
+    inspector::{AnyInspector, Inspector},
+    memory::{Memory, UNMETERED_LIMIT},
     opcodes::{
         bit::{byte, sar, shl, shr},
+        block::{
+            base_fee, blockhash, chain_id, coinbase, gas_limit, number, prevrandao, self_balance,
+            timestamp,
+        },
         comparisons::{eq, gt, is_zero, lt, sgt, slt},
-        contract::revert,
+        contract::{revert, selfdestruct, vm_return},
         dup::dup,
+        eof::{
+            callf, datacopy, dataload, dataloadn, datasize, dupn, exchange, extcall, jumpf, retf,
+            rjump, rjumpi, rjumpv, swapn,
+        },
         environment::{
-            address, balance, call_data_copy, call_data_load, call_data_size, call_value,
-            code_copy, code_size, ext_code_copy, ext_code_hash, gas_price, return_data_copy,
-            return_data_size,
+            address, balance, call_data_copy, call_data_load, call_data_size, call_value, caller,
+            code_copy, code_size, ext_code_copy, ext_code_hash, ext_code_size, gas_price, origin,
+            return_data_copy, return_data_size,
         },
+        info::{CodeKind, OpcodeInfo, OpcodeTable, opcode_info},
         jump::{jump, jump_dest, jumpi, pc},
         log::log,
         logic::{and, not, or, xor},
-        math::{add, add_mod, div, mul, mul_mod, sdiv, signextend, smod, sub, vm_mod},
+        math::{add, add_mod, div, exp, mul, mul_mod, sdiv, signextend, smod, sub, vm_mod},
         memory::{mload, mstore, mstore8},
         misc::sha3,
         opcodes::{
-            ADD, ADDMOD, ADDRESS, AND, BALANCE, BYTE, CALLDATACOPY, CALLDATALOAD, CALLDATASIZE,
-            CALLVALUE, CODECOPY, CODESIZE, DIV, DUP1, DUP16, EQ, EXTCODECOPY, EXTCODEHASH,
-            GASPRICE, GT, ISZERO, JUMP, JUMPDEST, JUMPI, LOG0, LOG4, LT, MLOAD, MOD, MSTORE,
-            MSTORE8, MUL, MULMOD, NOT, OR, ORIGIN, PC, POP, PUSH1, PUSH32, RETURNDATACOPY,
-            RETURNDATASIZE, REVERT, SAR, SDIV, SGT, SHA3, SHL, SHR, SIGNEXTEND, SLOAD, SLT, SMOD,
-            SSTORE, STOP, SUB, SWAP1, SWAP16, TLOAD, TSTORE, XOR,
+            ADD, ADDMOD, ADDRESS, AND, BALANCE, BASEFEE, BLOCKHASH, BYTE, CALLDATACOPY,
+            CALLDATALOAD, CALLDATASIZE, CALLER, CALLF, CALLVALUE, CHAINID, CODECOPY, CODESIZE,
+            COINBASE, DATACOPY, DATALOAD, DATALOADN, DATASIZE, DIV, DUP1, DUP16, DUPN, EQ,
+            EXCHANGE, EXP, EXTCALL, EXTCODECOPY, EXTCODEHASH, EXTCODESIZE, GASLIMIT, GASPRICE, GT,
+            ISZERO, JUMP, JUMPDEST, JUMPF, JUMPI, LOG0, LOG4, LT, MLOAD, MOD, MSTORE, MSTORE8, MUL,
+            MULMOD, NOT, NUMBER, OR, ORIGIN, PC, POP, PREVRANDAO, PUSH1, PUSH32, RETF, RETURN,
+            RETURNDATACOPY, RETURNDATASIZE, REVERT, RJUMP, RJUMPI, RJUMPV, SAR, SDIV, SELFBALANCE,
+            SELFDESTRUCT, SGT, SHA3, SHL, SHR, SIGNEXTEND, SLOAD, SLT, SMOD, SSTORE, STOP, SUB,
+            SWAP1, SWAP16, SWAPN, TIMESTAMP, TLOAD, TSTORE, XOR,
         },
         pop::pop,
         push::push,
@@ -35,8 +51,8 @@ use crate::{
         swap::swap,
         transient::{tload, tstore},
     },
+    precompile::{Precompile, Precompiles},
     stack::Stack,
-    storage::Storage,
 };
 
 #[derive(Debug, PartialEq)]
@@ -49,6 +65,11 @@ pub enum EvmError {
         size: usize,
         max: usize,
     },
+    // memory would grow past the hard cap set with `Memory::with_limit`
+    MemoryLimitExceeded {
+        required: usize,
+        limit: usize,
+    },
     ReturnDataOutOfBounds {
         offset: usize,
         size: usize,
@@ -61,24 +82,66 @@ pub enum EvmError {
     UnknownOpcode {
         opcode: String,
     },
+    // `register_opcode` was given a byte that already has an instruction
+    OpcodeAlreadyDefined {
+        opcode: String,
+    },
+    // the program starts with 0xEF00 but is not a valid EOF container
+    InvalidEof(EofError),
+    // too many nested CALLF frames
+    ReturnStackOverflow,
+    // an address argument has some of its 12 high bytes set
+    AddressOutOfRange,
+    // SSTORE, TSTORE, LOG or a value transfer inside a read-only (STATICCALL) frame
+    StateChangeDuringStaticCall,
+    // a call into an account with code, there are no frames to run it in yet
+    UnsupportedCall {
+        target: Address,
+    },
 }
+#[derive(Clone, PartialEq)]
 pub struct Log {
+    // the account that emitted it
+    pub address: Address,
     pub topics: Vec<U256>,
     pub data: Vec<u8>,
 }
 impl Log {
-    pub fn new(data: Vec<u8>, topics: Vec<U256>) -> Self {
-        Self { topics, data }
+    pub fn new(address: Address, data: Vec<u8>, topics: Vec<U256>) -> Self {
+        Self {
+            address,
+            topics,
+            data,
+        }
     }
 }
 
+// where RETF resumes execution after a CALLF
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReturnFrame {
+    pub code_section: usize,
+    pub pc: usize,
+}
+
+// topics as 32-byte words and data as one hex string, `abi::Abi::decode_log` gives the event
 impl Debug for Log {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Log: Topics: {:?} | Data: {:?}", self.topics, self.data)
+        let topics: Vec<_> = self.topics.iter().map(|topic| format!("{:#066x}", topic)).collect();
+        write!(
+            f,
+            "Log: Address: {} | Topics: [{}] | Data: 0x{}",
+            self.address,
+            topics.join(", "),
+            hex::encode(&self.data)
+        )
     }
 }
 
-pub struct EVM {
+// a custom instruction, written like the handlers in `opcodes`: it reads its arguments, charges
+// whatever comes on top of its base gas, and moves `pc` past itself and its immediates
+pub type OpcodeHandler<H> = fn(&mut EVM<H>) -> Result<(), EvmError>;
+
+pub struct EVM<H: Host = InMemoryHost> {
     // The evm is dumb, it cannot different between hex values that are opcodes and those that are just values
     // Its train of execution is directed by the program counter. 
     // e.g given program -> [0x60, 0x69,0x60, 0x01, 0x55]. program[0] = 0x60 => PUSH1 
@@ -90,68 +153,241 @@ pub struct EVM {
     pub value: U256,
     pub calldata: Vec<u8>,
     pub gas: u64,
+    // everything charged so far, kept in unmetered runs too
+    pub gas_used: u64,
+    // false for exploration and static analysis: nothing runs out of gas, `gas` stays put
+    // memory is then capped at `memory::UNMETERED_LIMIT` if it has no limit of its own, see
+    // `memory_limit`
+    pub metered: bool,
+    pub gas_schedule: GasSchedule,
     pub refund: u64, // refunds can not pay for transactions themselves, they like vouchers given on transaction execution
     pub sender: Address,
+    // the account whose code is running, storage and logs belong to it
+    pub address: Address,
+    // read-only frame, e.g. a STATICCALL or an eth_call simulation: any state change is an error
+    pub is_static: bool,
     // sub components
     pub program: Vec<u8>,
     pub stack: Stack,
     pub memory: Memory,
-    pub storage: Storage,
-    pub transient_storage: HashMap<U256, U256>,
+    // accounts, storage, logs and the block, everything outside this frame
+    pub host: H,
+    // native contracts EXTCALL runs instead of code, keyed by address
+    pub(crate) precompiles: Precompiles,
+    // EOF: set when the program is an EOF container, pc then indexes into the whole container
+    pub container: Option<Container>,
+    pub code_section: usize,
+    pub return_stack: Vec<ReturnFrame>,
     // flags
     pub stop_flag: bool,
     pub revert_flag: bool,
     // output
+    // return data of the last sub-call, every call or create overwrites it (RETURNDATASIZE, RETURNDATACOPY)
     pub return_data: Vec<u8>,
-    pub logs: Vec<Log>,
+    // what this frame hands back to its caller through RETURN or REVERT
+    pub output: Vec<u8>,
+    // the standard instructions plus the ones added with `register_opcode`
+    opcodes: OpcodeTable,
+    custom_opcodes: HashMap<u8, OpcodeHandler<H>>,
+    inspector: Option<Box<dyn AnyInspector<H>>>,
 }
 
 impl EVM {
+    // runs against a fresh in-memory world, with `sender` as both the running account and tx.origin
     pub fn new(
         sender: Address,
         program: Vec<u8>,
         gas: u64,
         value: U256,
         calldata: Vec<u8>,
+    ) -> Self {
+        let host = InMemoryHost::new(Env {
+            origin: sender,
+            ..Env::default()
+        });
+        Self::with_host(host, sender, program, gas, value, calldata)
+    }
+}
+
+impl<H: Host> EVM<H> {
+    pub fn with_host(
+        host: H,
+        sender: Address,
+        program: Vec<u8>,
+        gas: u64,
+        value: U256,
+        calldata: Vec<u8>,
     ) -> Self {
         Self {
             pc: 0, 
             value,
             sender,
+            address: sender,
+            is_static: false,
             calldata,
             program,
             gas,
+            gas_used: 0,
+            metered: true,
+            gas_schedule: GasSchedule::default(),
             refund: 0,
             stop_flag: false,
             revert_flag: false,
             stack: Stack::new(),
             memory: Memory::new(),
-            storage: Storage::new(),
-            transient_storage: HashMap::new(),
+            host,
+            precompiles: Precompiles::default(),
+            container: None,
+            code_section: 0,
+            return_stack: Vec::new(),
             return_data: Vec::new(),
-            logs: Vec::new(),
+            output: Vec::new(),
+            opcodes: OpcodeTable::default(),
+            custom_opcodes: HashMap::new(),
+            inspector: None,
         }
     }
 
+    // Adds an instruction on an unassigned byte, e.g. an appchain's own opcode.
+    // The interpreter checks the stack against `info` and charges `info.base_gas` before calling
+    // `handler`. Custom instructions only run in legacy code, EOF validation knows the standard set.
+    pub fn register_opcode(
+        &mut self,
+        opcode: u8,
+        mut info: OpcodeInfo,
+        handler: OpcodeHandler<H>,
+    ) -> Result<(), EvmError> {
+        if self.opcodes.get(opcode).is_some() {
+            return Err(EvmError::OpcodeAlreadyDefined {
+                opcode: format!("0x{:02x}", opcode),
+            });
+        }
+        info.code_kind = CodeKind::Legacy;
+        self.opcodes.insert(opcode, info);
+        self.custom_opcodes.insert(opcode, handler);
+        Ok(())
+    }
+
+    // Installs a native contract at `address`, e.g. an oracle mock or a system contract, and
+    // returns the one it replaces. EXTCALL runs it with the gas and value of the call.
+    pub fn register_precompile(
+        &mut self,
+        address: Address,
+        precompile: impl Precompile + 'static,
+    ) -> Option<Box<dyn Precompile>> {
+        self.precompiles.insert(address, precompile)
+    }
+
+    // `register_precompile` while building the EVM
+    pub fn with_precompile(
+        mut self,
+        address: Address,
+        precompile: impl Precompile + 'static,
+    ) -> Self {
+        self.register_precompile(address, precompile);
+        self
+    }
+
+    pub fn remove_precompile(&mut self, address: Address) -> Option<Box<dyn Precompile>> {
+        self.precompiles.remove(address)
+    }
+
+    pub fn precompiles(&self) -> &Precompiles {
+        &self.precompiles
+    }
+
+    // replaces the inspector, if any, it is called from the next instruction on
+    pub fn set_inspector(&mut self, inspector: impl Inspector<H> + 'static) {
+        self.inspector = Some(Box::new(inspector));
+    }
+
+    // the attached inspector, if it is a `T`
+    pub fn inspector<T: Inspector<H> + 'static>(&self) -> Option<&T> {
+        self.inspector.as_ref()?.as_any().downcast_ref()
+    }
+
+    pub fn inspector_mut<T: Inspector<H> + 'static>(&mut self) -> Option<&mut T> {
+        self.inspector.as_mut()?.as_any_mut().downcast_mut()
+    }
+
+    // detaches the inspector if it is a `T`, anything else stays attached
+    pub fn take_inspector<T: Inspector<H> + 'static>(&mut self) -> Option<T> {
+        self.inspector::<T>()?;
+        let inspector = self.inspector.take()?.into_any();
+        inspector.downcast().ok().map(|inspector| *inspector)
+    }
+
+    // calls `hook` with the inspector and a read-only view of the EVM, if one is attached
+    // the inspector is moved out for the call, so it can't see itself through the EVM
+    pub(crate) fn inspect(&mut self, hook: impl FnOnce(&mut dyn Inspector<H>, &Self)) {
+        if let Some(mut inspector) = self.inspector.take() {
+            hook(inspector.as_inspector(), self);
+            self.inspector = Some(inspector);
+        }
+    }
+
+    // what the disassembler and the TUI should name the program's instructions with
+    pub fn opcode_table(&self) -> &OpcodeTable {
+        &self.opcodes
+    }
+
+    // the memory cap that applies, `memory::UNMETERED_LIMIT` stands in for a missing one while
+    // the run is unmetered
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.memory.limit.or((!self.metered).then_some(UNMETERED_LIMIT))
+    }
+
+    // what growing memory to [offset, offset + size) costs, under `memory_limit`
+    pub fn memory_expansion_cost(&self, offset: U256, size: U256) -> Result<u64, EvmError> {
+        self.memory
+            .expansion_cost_within(offset, size, &self.gas_schedule, self.memory_limit())
+    }
+
     pub fn gas_dec(&mut self, amount: u64) -> Result<(), EvmError> {
+        if !self.metered {
+            self.gas_used = self.gas_used.saturating_add(amount);
+            return Ok(());
+        }
         if amount > self.gas {
             return Err(EvmError::OutOfGas);
         }
         self.gas -= amount;
+        self.gas_used += amount;
+        Ok(())
+    }
+    // fails if the current frame is not allowed to modify state
+    pub fn ensure_not_static(&self) -> Result<(), EvmError> {
+        if self.is_static {
+            return Err(EvmError::StateChangeDuringStaticCall);
+        }
         Ok(())
     }
     pub fn peek(&self) -> u8 {
         self.program[self.pc]
     }
+    // Restarts the frame for another run of the program, as a new transaction: the flags, gas
+    // accounting and what the host only keeps for one transaction are cleared. Accounts and
+    // storage stay in the host, and `gas` is left as it is, set it again for a fresh budget.
     pub fn reset(&mut self) {
         self.pc = 0;
         self.stack = Stack::new();
-        self.memory = Memory::new();
-        self.storage = Storage::new();
-        self.transient_storage = HashMap::new()
+        self.memory = Memory {
+            limit: self.memory.limit,
+            ..Memory::new()
+        };
+        self.container = None;
+        self.code_section = 0;
+        self.return_stack = Vec::new();
+        self.return_data = Vec::new();
+        self.output = Vec::new();
+        self.stop_flag = false;
+        self.revert_flag = false;
+        self.gas_used = 0;
+        self.refund = 0;
+        self.host.clear_transaction();
     }
     pub fn should_execute_next_opcode(&self) -> bool {
-        if self.pc > (self.program.len() - 1) {
+        if self.pc >= self.program.len() {
             // means pc has reached the max program length
             return false;
         }
@@ -160,117 +396,108 @@ impl EVM {
         }
         true
     }
+
+    // parses and validates the program once if it is an EOF container (EIP-3540)
+    // execution then starts at the first code section instead of the first byte
+    pub fn load_container(&mut self) -> Result<(), EvmError> {
+        if self.container.is_some() || !eof::is_eof(&self.program) {
+            return Ok(());
+        }
+        let container = Container::parse(&self.program).map_err(EvmError::InvalidEof)?;
+        eof::validate(&container).map_err(EvmError::InvalidEof)?;
+        self.pc = container.code_offset(0);
+        self.code_section = 0;
+        self.container = Some(container);
+        Ok(())
+    }
+
     pub fn run(&mut self) -> Result<(), EvmError> {
+        self.load_container()?;
         while self.should_execute_next_opcode() {
             let opcode = self.program[self.pc];
-            match opcode {
-                // STOP
-                STOP => stop(self)?,
-                // MATH
-                ADD => add(self)?,
-                SUB => sub(self)?,
-                MUL => mul(self)?,
-                SMOD => smod(self)?,
-                DIV => div(self)?,
-                SDIV => sdiv(self)?,
-                MOD => vm_mod(self)?,
-                ADDMOD => add_mod(self)?,
-                MULMOD => mul_mod(self)?,
-                SIGNEXTEND => signextend(self)?,
-                // BIT
-                BYTE => byte(self)?,
-                SHL => shl(self)?,
-                SHR => shr(self)?,
-                SAR => sar(self)?,
-                // LOGIC
-                LT => lt(self)?,
-                SLT => slt(self)?,
-                GT => gt(self)?,
-                SGT => sgt(self)?,
-                EQ => eq(self)?,
-                ISZERO => is_zero(self)?,
-                // DUP
-                DUP1..=DUP16 => {
-                    // calculate n dynamically
-                    let n = (opcode - DUP1 + 1) as usize; // 1 is added because DUP is 1-indexed
-                    dup(self, n)?;
-                }
-                // ENVIRONMENT
-                ADDRESS => address(self)?,
-                BALANCE => balance(self)?,
-                ORIGIN => balance(self)?,
-                CALLVALUE => call_value(self)?,
-                CALLDATALOAD => call_data_load(self)?,
-                CALLDATASIZE => call_data_size(self)?,
-                CALLDATACOPY => call_data_copy(self)?,
-                CODESIZE => code_size(self)?,
-                CODECOPY => code_copy(self)?,
-                GASPRICE => gas_price(self)?,
-                EXTCODECOPY => ext_code_copy(self)?,
-                EXTCODEHASH => ext_code_hash(self)?,
-                RETURNDATACOPY => return_data_copy(self)?,
-                RETURNDATASIZE => return_data_size(self)?,
-                // JUMP
-                JUMP => jump(self)?,
-                JUMPI => jumpi(self)?,
-                JUMPDEST => jump_dest(self)?,
-                PC => pc(self)?,
-                // LOG
-                LOG0..=LOG4 => {
-                    // calculate n dynamically
-                    let n = (opcode - LOG0) as usize; // there's no need to add 1, since LOG is 0-indexed
-                    log(self, n)?;
-                }
-                // LOGIC
-                AND => and(self)?,
-                OR => or(self)?,
-                XOR => xor(self)?,
-                NOT => not(self)?,
-                // MEMORY
-                MLOAD => mload(self)?,
-                MSTORE => mstore(self)?,
-                MSTORE8 => mstore8(self)?,
-                // MISC
-                SHA3 => sha3(self)?,
-                // POP
-                POP => pop(self)?,
-                // PUSH
-                PUSH1..=PUSH32 => {
-                    // calculate n dynamically
-                    let n = (opcode - PUSH1 + 1) as usize; // 1 is added because PUSH is 1-indexed
-                    push(self, n)?;
-                }
-                // STORAGE
-                SLOAD => sload(self)?,
-                SSTORE => s_store(self)?,
-                // SWAP
-                SWAP1..=SWAP16 => {
-                    // calculate n dynamically
-                    // 0x90 - 0x90 + 1 = SWAP 1
-                    // 0X91 - 0X90 + 1 = SWAP 2
-                    let n = (opcode - SWAP1 + 1) as usize; // 1 is added because SWAP is 1-indexed
-                    swap(self, n)?;
-                }
-                // TRANSIENT
-                TLOAD => tload(self)?,
-                TSTORE => tstore(self)?,
-                REVERT => revert(self)?,
-                _ => {
-                    return Err(EvmError::UnknownOpcode {
-                        opcode: format!("0x{:02x}", opcode),
-                    });
-                }
-            }
+            self.execute(opcode)?;
         }
         Ok(())
     }
 
     // for terminal UI
     pub fn step(&mut self) -> Result<bool, EvmError> {
+        self.load_container()?;
         if !self.should_execute_next_opcode() {
             return Ok(false);
         }
         let opcode = self.program[self.pc];
+        self.execute(opcode)?;
+        Ok(true)
+    }
+
+    fn execute(&mut self, opcode: u8) -> Result<(), EvmError> {
+        self.inspect(|inspector, vm| inspector.step(vm));
+        let result = self.execute_instruction(opcode);
+        self.inspect(|inspector, vm| inspector.step_end(vm, &result));
+        result
+    }
+
+    fn execute_instruction(&mut self, opcode: u8) -> Result<(), EvmError> {
+        // the one stack height check of the instruction, handlers pop and push without checking again
+        // an opcode that isn't defined for this kind of code is left to the dispatcher to reject,
+        // one without a handler is rejected here, whatever the stack holds
+        let is_eof = self.container.is_some();
+        if let Some(info) = self.opcodes.get(opcode)
+            && (if is_eof { info.is_valid_in_eof() } else { info.is_valid_in_legacy() })
+        {
+            if !info.implemented {
+                return Err(EvmError::UnknownOpcode {
+                    opcode: format!("0x{:02x}", opcode),
+                });
+            }
+            self.stack.check(info.inputs as usize, info.outputs as usize)?;
+        }
+        if !is_eof && let Some(handler) = self.custom_opcodes.get(&opcode).copied() {
+            let base_gas = self.opcodes.get(opcode).map_or(0, |info| info.base_gas);
+            self.gas_dec(base_gas)?;
+            return handler(self);
+        }
+        if is_eof {
+            self.execute_eof(opcode)
+        } else {
+            self.execute_legacy(opcode)
+        }
+    }
+
+    // EOF only adds instructions on top of the legacy set, and drops the ones that inspect code or gas
+    fn execute_eof(&mut self, opcode: u8) -> Result<(), EvmError> {
+        // rejected by validation, but never execute them even if a container slipped through
+        if !opcode_info(opcode).is_some_and(|info| info.is_valid_in_eof()) {
+            return Err(EvmError::UnknownOpcode {
+                opcode: format!("0x{:02x}", opcode),
+            });
+        }
+        match opcode {
+            // DATA
+            DATALOAD => dataload(self)?,
+            DATALOADN => dataloadn(self)?,
+            DATASIZE => datasize(self)?,
+            DATACOPY => datacopy(self)?,
+            // CONTROL FLOW
+            RJUMP => rjump(self)?,
+            RJUMPI => rjumpi(self)?,
+            RJUMPV => rjumpv(self)?,
+            CALLF => callf(self)?,
+            RETF => retf(self)?,
+            JUMPF => jumpf(self)?,
+            // STACK
+            DUPN => dupn(self)?,
+            SWAPN => swapn(self)?,
+            EXCHANGE => exchange(self)?,
+            // SYSTEM
+            EXTCALL => extcall(self)?,
+            _ => self.execute_legacy(opcode)?,
+        }
+        Ok(())
+    }
+
+    fn execute_legacy(&mut self, opcode: u8) -> Result<(), EvmError> {
         match opcode {
             // STOP
             STOP => stop(self)?,
@@ -284,6 +511,7 @@ impl EVM {
             MOD => vm_mod(self)?,
             ADDMOD => add_mod(self)?,
             MULMOD => mul_mod(self)?,
+            EXP => exp(self)?,
             SIGNEXTEND => signextend(self)?,
             // BIT
             BYTE => byte(self)?,
@@ -306,7 +534,8 @@ impl EVM {
             // ENVIRONMENT
             ADDRESS => address(self)?,
             BALANCE => balance(self)?,
-            ORIGIN => balance(self)?,
+            ORIGIN => origin(self)?,
+            CALLER => caller(self)?,
             CALLVALUE => call_value(self)?,
             CALLDATALOAD => call_data_load(self)?,
             CALLDATASIZE => call_data_size(self)?,
@@ -314,10 +543,21 @@ impl EVM {
             CODESIZE => code_size(self)?,
             CODECOPY => code_copy(self)?,
             GASPRICE => gas_price(self)?,
+            EXTCODESIZE => ext_code_size(self)?,
             EXTCODECOPY => ext_code_copy(self)?,
             EXTCODEHASH => ext_code_hash(self)?,
             RETURNDATACOPY => return_data_copy(self)?,
             RETURNDATASIZE => return_data_size(self)?,
+            // BLOCK
+            BLOCKHASH => blockhash(self)?,
+            COINBASE => coinbase(self)?,
+            TIMESTAMP => timestamp(self)?,
+            NUMBER => number(self)?,
+            PREVRANDAO => prevrandao(self)?,
+            GASLIMIT => gas_limit(self)?,
+            CHAINID => chain_id(self)?,
+            SELFBALANCE => self_balance(self)?,
+            BASEFEE => base_fee(self)?,
             // JUMP
             JUMP => jump(self)?,
             JUMPI => jumpi(self)?,
@@ -362,13 +602,15 @@ impl EVM {
             // TRANSIENT
             TLOAD => tload(self)?,
             TSTORE => tstore(self)?,
+            RETURN => vm_return(self)?,
             REVERT => revert(self)?,
+            SELFDESTRUCT => selfdestruct(self)?,
             _ => {
                 return Err(EvmError::UnknownOpcode {
                     opcode: format!("0x{:02x}", opcode),
                 });
             }
         }
-        Ok(true)
+        Ok(())
     }
 }
@@ -0,0 +1,63 @@
+// Ethereum hard forks that changed the instruction set or what it costs, oldest first
+// Forks that only touched the protocol around the EVM (e.g. the difficulty bomb) are left out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Fork {
+    Frontier,
+    Homestead,
+    Byzantium,
+    Constantinople,
+    Istanbul,
+    Berlin,
+    London,
+    // the Merge, London's rules but DIFFICULTY (0x44) is PREVRANDAO (EIP-4399)
+    Paris,
+    Shanghai,
+    Cancun,
+    Prague,
+    // EOF (EIP-7692)
+    #[default]
+    Osaka,
+}
+
+impl Fork {
+    pub const LATEST: Fork = Fork::Osaka;
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Fork::Frontier => "Frontier",
+            Fork::Homestead => "Homestead",
+            Fork::Byzantium => "Byzantium",
+            Fork::Constantinople => "Constantinople",
+            Fork::Istanbul => "Istanbul",
+            Fork::Berlin => "Berlin",
+            Fork::London => "London",
+            Fork::Paris => "Paris",
+            Fork::Shanghai => "Shanghai",
+            Fork::Cancun => "Cancun",
+            Fork::Prague => "Prague",
+            Fork::Osaka => "Osaka",
+        }
+    }
+
+    // also takes the names the Ethereum test fixtures use for forks folded into another one here
+    // None for Tangerine Whistle and Spurious Dragon (`EIP150`, `EIP158`), they have no fork here
+    // and their rules differ from their neighbours', the state test runner skips their fixtures
+    pub fn from_name(name: &str) -> Option<Fork> {
+        let fork = match name {
+            "Frontier" => Fork::Frontier,
+            "Homestead" => Fork::Homestead,
+            "Byzantium" => Fork::Byzantium,
+            "Constantinople" | "ConstantinopleFix" | "Petersburg" => Fork::Constantinople,
+            "Istanbul" => Fork::Istanbul,
+            "Berlin" => Fork::Berlin,
+            "London" => Fork::London,
+            "Paris" | "Merge" => Fork::Paris,
+            "Shanghai" => Fork::Shanghai,
+            "Cancun" => Fork::Cancun,
+            "Prague" => Fork::Prague,
+            "Osaka" => Fork::Osaka,
+            _ => return None,
+        };
+        Some(fork)
+    }
+}
@@ -0,0 +1,166 @@
+// What every instruction costs, one field per price so a chain with its own pricing (an L2,
+// an appchain) can start from a fork's schedule and change what differs:
+//   let mut schedule = GasSchedule::for_fork(Fork::Cancun);
+//   schedule.cold_sload = 800;
+//   evm.gas_schedule = schedule;
+// Simplifications, shared with the handlers:
+//   - before Berlin an account access costs what EXTCODESIZE did, BALANCE and EXTCODEHASH were
+//     cheaper on some of those forks
+//   - SSTORE is `sstore_set`, `sstore_reset` or `sstore_noop`, plus `sstore_cold_slot` for a cold
+//     slot, without EIP-2200's original values: every store is priced like the first one to its
+//     slot in the transaction
+
+use std::num::NonZeroU64;
+
+use crate::fork::Fork;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasSchedule {
+    // the flat tiers of the yellow paper
+    pub jumpdest: u64,
+    pub base: u64,
+    pub very_low: u64,
+    pub low: u64,
+    pub mid: u64,
+    pub high: u64,
+    pub blockhash: u64,
+    // EOF instructions outside the tiers
+    pub rjumpi: u64,
+    pub dataload: u64,
+
+    // per byte or per word
+    pub exp: u64,
+    pub exp_byte: u64,
+    pub keccak256: u64,
+    pub keccak256_word: u64,
+    pub copy_word: u64,
+    pub memory_word: u64,
+    // memory also costs words² / memory_quad_divisor, never zero so the division is always defined
+    pub memory_quad_divisor: NonZeroU64,
+    pub log: u64,
+    pub log_topic: u64,
+    pub log_data_byte: u64,
+
+    // state access, warm and cold are the same before Berlin (EIP-2929)
+    pub warm_account_access: u64,
+    pub cold_account_access: u64,
+    pub warm_sload: u64,
+    pub cold_sload: u64,
+    pub sstore_set: u64,
+    pub sstore_reset: u64,
+    // a store that leaves the slot as it was
+    pub sstore_noop: u64,
+    // on top of the store, zero before Berlin where slots are neither warm nor cold
+    pub sstore_cold_slot: u64,
+    pub sstore_clears_refund: u64,
+    pub transient: u64,
+
+    // calls and self-destruction
+    pub call_value: u64,
+    pub new_account: u64,
+    pub selfdestruct: u64,
+    pub selfdestruct_cold_beneficiary: u64,
+}
+
+impl Default for GasSchedule {
+    fn default() -> Self {
+        Self::for_fork(Fork::LATEST)
+    }
+}
+
+impl GasSchedule {
+    pub fn for_fork(fork: Fork) -> Self {
+        // Frontier, then every fork that repriced something on top of it
+        let mut schedule = Self {
+            jumpdest: 1,
+            base: 2,
+            very_low: 3,
+            low: 5,
+            mid: 8,
+            high: 10,
+            blockhash: 20,
+            rjumpi: 4,
+            dataload: 4,
+            exp: 10,
+            exp_byte: 10,
+            keccak256: 30,
+            keccak256_word: 6,
+            copy_word: 3,
+            memory_word: 3,
+            memory_quad_divisor: NonZeroU64::new(512).unwrap(),
+            log: 375,
+            log_topic: 375,
+            log_data_byte: 8,
+            warm_account_access: 20,
+            cold_account_access: 20,
+            warm_sload: 50,
+            cold_sload: 50,
+            sstore_set: 20000,
+            sstore_reset: 5000,
+            sstore_noop: 5000,
+            sstore_cold_slot: 0,
+            sstore_clears_refund: 15000,
+            transient: 100,
+            call_value: 9000,
+            new_account: 0,
+            selfdestruct: 0,
+            selfdestruct_cold_beneficiary: 0,
+        };
+        // Tangerine Whistle (EIP-150) and Spurious Dragon (EIP-160) came before Byzantium
+        if fork >= Fork::Byzantium {
+            schedule.warm_account_access = 700;
+            schedule.cold_account_access = 700;
+            schedule.warm_sload = 200;
+            schedule.cold_sload = 200;
+            schedule.exp_byte = 50;
+            schedule.new_account = 25000;
+            schedule.selfdestruct = 5000;
+        }
+        // EIP-1884, EIP-2200
+        if fork >= Fork::Istanbul {
+            schedule.warm_sload = 800;
+            schedule.cold_sload = 800;
+            schedule.sstore_noop = 800;
+        }
+        // EIP-2929
+        if fork >= Fork::Berlin {
+            schedule.warm_account_access = 100;
+            schedule.cold_account_access = 2600;
+            schedule.warm_sload = 100;
+            schedule.cold_sload = 2100;
+            schedule.sstore_reset = 2900;
+            schedule.sstore_noop = 100;
+            schedule.sstore_cold_slot = 2100;
+            schedule.selfdestruct_cold_beneficiary = 2600;
+        }
+        // EIP-3529
+        if fork >= Fork::London {
+            schedule.sstore_clears_refund = 4800;
+        }
+        schedule
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latest_is_the_default() {
+        let schedule = GasSchedule::default();
+        assert_eq!(schedule, GasSchedule::for_fork(Fork::Osaka));
+        assert_eq!(schedule.cold_sload, 2100);
+        assert_eq!(schedule.sstore_clears_refund, 4800);
+    }
+
+    #[test]
+    fn test_repricings_apply_from_their_fork_on() {
+        assert_eq!(GasSchedule::for_fork(Fork::Homestead).exp_byte, 10);
+        assert_eq!(GasSchedule::for_fork(Fork::Byzantium).exp_byte, 50);
+        assert_eq!(GasSchedule::for_fork(Fork::Constantinople).cold_sload, 200);
+        assert_eq!(GasSchedule::for_fork(Fork::Istanbul).cold_sload, 800);
+        let berlin = GasSchedule::for_fork(Fork::Berlin);
+        assert_eq!((berlin.warm_sload, berlin.cold_sload), (100, 2100));
+        assert_eq!(berlin.sstore_clears_refund, 15000);
+    }
+}
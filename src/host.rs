@@ -0,0 +1,454 @@
+// Everything an instruction needs from outside the running frame: accounts, storage, logs and
+// the block it runs in. Handlers only reach the world through this trait, so another state
+// backend plugs in by implementing it. `DatabaseHost` is one over any `Database`,
+// `InMemoryHost` (no database at all) is the one `EVM::new` uses.
+
+use std::collections::{HashMap, HashSet};
+
+use alloy_primitives::{Address, B256, U256};
+
+use crate::{
+    db::{CacheDB, Database, EmptyDB},
+    evm::Log,
+};
+
+// the transaction and block the code runs in
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Env {
+    // transaction
+    pub origin: Address,
+    pub gas_price: U256,
+    // block
+    pub coinbase: Address,
+    pub number: u64,
+    pub timestamp: u64,
+    pub gas_limit: u64,
+    pub base_fee: U256,
+    pub prev_randao: B256,
+    pub chain_id: u64,
+}
+
+// The warm/cold bookkeeping of EIP-2929 is kept apart from the state reads: handlers ask
+// `is_*_warm` to price an access, pay for it, and only then call `warm_*`, so an instruction
+// that runs out of gas leaves the access lists as they were.
+pub trait Host {
+    fn env(&self) -> &Env;
+
+    // access lists
+    fn is_account_warm(&self, address: Address) -> bool;
+    fn warm_account(&mut self, address: Address);
+    fn is_slot_warm(&self, address: Address, key: U256) -> bool;
+    fn warm_slot(&mut self, address: Address, key: U256);
+
+    // accounts, an account that doesn't exist has no balance, no code and a zero code hash
+    fn balance(&mut self, address: Address) -> U256;
+    fn nonce(&mut self, address: Address) -> u64;
+    fn code(&mut self, address: Address) -> Vec<u8>;
+    fn code_hash(&mut self, address: Address) -> B256;
+    // only asked for one of the 256 blocks before the current one
+    fn block_hash(&mut self, number: u64) -> B256;
+
+    // storage
+    fn sload(&mut self, address: Address, key: U256) -> U256;
+    fn sstore(&mut self, address: Address, key: U256, value: U256);
+    fn tload(&mut self, address: Address, key: U256) -> U256;
+    fn tstore(&mut self, address: Address, key: U256, value: U256);
+
+    fn log(&mut self, log: Log);
+    // moves `value` from one balance to the other, false and nothing moved if `from` has too little
+    fn transfer(&mut self, from: Address, to: Address, value: U256) -> bool;
+    // sends the whole balance of `address` to `target`
+    fn selfdestruct(&mut self, address: Address, target: Address);
+
+    // sub-calls: `checkpoint` before one, then `checkpoint_revert` if it reverts or halts, which
+    // undoes every change since, or `checkpoint_commit` to keep them. Checkpoints nest like calls.
+    fn checkpoint(&mut self);
+    fn checkpoint_commit(&mut self);
+    fn checkpoint_revert(&mut self);
+
+    // drops what only lives for one transaction: transient storage, logs, self-destructs and the
+    // access lists. Accounts and storage stay.
+    fn clear_transaction(&mut self);
+}
+
+// keeps everything a transaction changes in memory, on top of whatever database `D` holds
+// the database itself is never written, `db.commit()` hands the changes down when wanted
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DatabaseHost<D> {
+    pub env: Env,
+    pub db: CacheDB<D>,
+    pub transient_storage: HashMap<(Address, U256), U256>,
+    pub logs: Vec<Log>,
+    // accounts that ran SELFDESTRUCT, in order
+    pub destructed: Vec<Address>,
+    warm_accounts: HashSet<Address>,
+    warm_slots: HashSet<(Address, U256)>,
+    checkpoints: Vec<Checkpoint>,
+    // how to undo every change made through `Host` since the oldest open checkpoint, writes
+    // straight to `db` are not recorded
+    journal: Vec<JournalEntry>,
+}
+
+// where `checkpoint_revert` rolls back to, logs and self-destructs are only ever appended
+#[derive(Debug, Clone, PartialEq)]
+struct Checkpoint {
+    journal: usize,
+    logs: usize,
+    destructed: usize,
+}
+
+// what a change overwrote
+#[derive(Debug, Clone, PartialEq)]
+enum JournalEntry {
+    Balance { address: Address, balance: U256, exists: bool },
+    // None if the slot wasn't cached yet
+    Storage { address: Address, key: U256, value: Option<U256>, exists: bool },
+    Transient { address: Address, key: U256, value: U256 },
+    WarmAccount(Address),
+    WarmSlot(Address, U256),
+}
+
+// the whole world lives in the host, there is no database underneath
+pub type InMemoryHost = DatabaseHost<EmptyDB>;
+
+impl<D: Database> DatabaseHost<D> {
+    pub fn with_db(env: Env, db: D) -> Self {
+        Self {
+            env,
+            db: CacheDB::new(db),
+            transient_storage: HashMap::new(),
+            logs: Vec::new(),
+            destructed: Vec::new(),
+            warm_accounts: HashSet::new(),
+            warm_slots: HashSet::new(),
+            checkpoints: Vec::new(),
+            journal: Vec::new(),
+        }
+    }
+
+    // only kept while a checkpoint could be reverted to
+    fn record(&mut self, entry: JournalEntry) {
+        if !self.checkpoints.is_empty() {
+            self.journal.push(entry);
+        }
+    }
+
+    fn record_balance(&mut self, address: Address) {
+        let account = self.db.account_mut(address);
+        let (balance, exists) = (account.info.balance, account.exists);
+        self.record(JournalEntry::Balance { address, balance, exists });
+    }
+
+    fn undo(&mut self, entry: JournalEntry) {
+        match entry {
+            JournalEntry::Balance { address, balance, exists } => {
+                let account = self.db.account_mut(address);
+                account.info.balance = balance;
+                account.exists = exists;
+            }
+            JournalEntry::Storage { address, key, value, exists } => {
+                let account = self.db.account_mut(address);
+                match value {
+                    Some(value) => account.storage.insert(key, value),
+                    None => account.storage.remove(&key),
+                };
+                account.exists = exists;
+            }
+            JournalEntry::Transient { address, key, value } => {
+                if value == U256::ZERO {
+                    self.transient_storage.remove(&(address, key));
+                } else {
+                    self.transient_storage.insert((address, key), value);
+                }
+            }
+            JournalEntry::WarmAccount(address) => {
+                self.warm_accounts.remove(&address);
+            }
+            JournalEntry::WarmSlot(address, key) => {
+                self.warm_slots.remove(&(address, key));
+            }
+        }
+    }
+}
+
+impl<D: Database + Default> DatabaseHost<D> {
+    pub fn new(env: Env) -> Self {
+        Self::with_db(env, D::default())
+    }
+}
+
+impl<D: Database> Host for DatabaseHost<D> {
+    fn env(&self) -> &Env {
+        &self.env
+    }
+
+    fn is_account_warm(&self, address: Address) -> bool {
+        self.warm_accounts.contains(&address)
+    }
+
+    fn warm_account(&mut self, address: Address) {
+        if self.warm_accounts.insert(address) {
+            self.record(JournalEntry::WarmAccount(address));
+        }
+    }
+
+    fn is_slot_warm(&self, address: Address, key: U256) -> bool {
+        self.warm_slots.contains(&(address, key))
+    }
+
+    fn warm_slot(&mut self, address: Address, key: U256) {
+        if self.warm_slots.insert((address, key)) {
+            self.record(JournalEntry::WarmSlot(address, key));
+        }
+    }
+
+    fn balance(&mut self, address: Address) -> U256 {
+        self.db
+            .basic(address)
+            .map_or(U256::ZERO, |account| account.balance)
+    }
+
+    fn nonce(&mut self, address: Address) -> u64 {
+        self.db.basic(address).map_or(0, |account| account.nonce)
+    }
+
+    fn code(&mut self, address: Address) -> Vec<u8> {
+        self.db
+            .basic(address)
+            .and_then(|account| account.code)
+            .unwrap_or_default()
+    }
+
+    fn code_hash(&mut self, address: Address) -> B256 {
+        // EIP-1052: zero for accounts that don't exist or are empty
+        match self.db.basic(address) {
+            Some(account) if !account.is_empty() => account.code_hash,
+            _ => B256::ZERO,
+        }
+    }
+
+    fn block_hash(&mut self, number: u64) -> B256 {
+        self.db.block_hash(number)
+    }
+
+    fn sload(&mut self, address: Address, key: U256) -> U256 {
+        self.db.storage(address, key)
+    }
+
+    fn sstore(&mut self, address: Address, key: U256, value: U256) {
+        let account = self.db.account_mut(address);
+        let (previous, exists) = (account.storage.get(&key).copied(), account.exists);
+        self.record(JournalEntry::Storage { address, key, value: previous, exists });
+        self.db.insert_storage(address, key, value);
+    }
+
+    fn tload(&mut self, address: Address, key: U256) -> U256 {
+        *self
+            .transient_storage
+            .get(&(address, key))
+            .unwrap_or(&U256::ZERO)
+    }
+
+    fn tstore(&mut self, address: Address, key: U256, value: U256) {
+        let previous = self.tload(address, key);
+        self.record(JournalEntry::Transient { address, key, value: previous });
+        if value == U256::ZERO {
+            self.transient_storage.remove(&(address, key));
+        } else {
+            self.transient_storage.insert((address, key), value);
+        }
+    }
+
+    fn log(&mut self, log: Log) {
+        self.logs.push(log);
+    }
+
+    fn transfer(&mut self, from: Address, to: Address, value: U256) -> bool {
+        let sender = self.db.account_mut(from);
+        if sender.info.balance < value {
+            return false;
+        }
+        if value.is_zero() || from == to {
+            return true;
+        }
+        self.record_balance(from);
+        self.record_balance(to);
+        let sender = self.db.account_mut(from);
+        sender.info.balance -= value;
+        let receiver = self.db.account_mut(to);
+        receiver.info.balance += value;
+        receiver.exists = true;
+        true
+    }
+
+    fn selfdestruct(&mut self, address: Address, target: Address) {
+        // since Cancun (EIP-6780) the account itself stays, only its balance moves.
+        // sending to itself keeps the balance where it is
+        if address != target {
+            self.record_balance(address);
+            self.record_balance(target);
+            let balance = std::mem::take(&mut self.db.account_mut(address).info.balance);
+            let beneficiary = self.db.account_mut(target);
+            beneficiary.info.balance += balance;
+            beneficiary.exists = true;
+        }
+        self.destructed.push(address);
+    }
+
+    fn checkpoint(&mut self) {
+        self.checkpoints.push(Checkpoint {
+            journal: self.journal.len(),
+            logs: self.logs.len(),
+            destructed: self.destructed.len(),
+        });
+    }
+
+    // the changes stay in the journal for an outer checkpoint to undo
+    fn checkpoint_commit(&mut self) {
+        self.checkpoints.pop();
+        if self.checkpoints.is_empty() {
+            self.journal.clear();
+        }
+    }
+
+    fn checkpoint_revert(&mut self) {
+        let Some(checkpoint) = self.checkpoints.pop() else {
+            return;
+        };
+        // newest first, a slot written twice ends up with what it held before the first write
+        while self.journal.len() > checkpoint.journal {
+            let entry = self.journal.pop().unwrap();
+            self.undo(entry);
+        }
+        self.logs.truncate(checkpoint.logs);
+        self.destructed.truncate(checkpoint.destructed);
+    }
+
+    fn clear_transaction(&mut self) {
+        self.transient_storage.clear();
+        self.logs.clear();
+        self.destructed.clear();
+        self.warm_accounts.clear();
+        self.warm_slots.clear();
+        self.checkpoints.clear();
+        self.journal.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::keccak256;
+
+    use super::*;
+    use crate::db::AccountInfo;
+
+    #[test]
+    fn test_warming_is_separate_from_reads() {
+        let mut host = InMemoryHost::default();
+        let address = Address::repeat_byte(0x11);
+        let key = U256::from(1);
+        host.sstore(address, key, U256::from(7));
+        assert_eq!(host.sload(address, key), U256::from(7));
+        assert!(!host.is_slot_warm(address, key));
+        assert!(!host.is_account_warm(address));
+        host.warm_account(address);
+        assert!(host.is_account_warm(address));
+        host.warm_slot(address, key);
+        assert!(host.is_slot_warm(address, key));
+    }
+
+    #[test]
+    fn test_missing_and_empty_accounts() {
+        let mut host = InMemoryHost::default();
+        let address = Address::repeat_byte(0x11);
+        assert_eq!(host.balance(address), U256::ZERO);
+        assert!(host.code(address).is_empty());
+        assert_eq!(host.code_hash(address), B256::ZERO);
+
+        host.db.insert_account(address, AccountInfo::new(U256::ZERO, 0, vec![]));
+        assert_eq!(host.code_hash(address), B256::ZERO);
+        host.db.insert_account(address, AccountInfo::new(U256::from(1), 0, vec![]));
+        assert_eq!(host.code_hash(address), keccak256([]));
+    }
+
+    #[test]
+    fn test_selfdestruct_moves_the_balance() {
+        let mut host = InMemoryHost::default();
+        let address = Address::repeat_byte(0x11);
+        let target = Address::repeat_byte(0x22);
+        host.db.insert_account(address, AccountInfo::new(U256::from(50), 0, vec![0xFF]));
+        host.selfdestruct(address, target);
+        assert_eq!(host.balance(address), U256::ZERO);
+        assert_eq!(host.balance(target), U256::from(50));
+        assert_eq!(host.code(address), vec![0xFF]);
+        assert_eq!(host.destructed, vec![address]);
+    }
+
+    #[test]
+    fn test_checkpoint_revert_and_commit() {
+        let mut host = InMemoryHost::default();
+        let (from, to) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+        host.db.insert_account(from, AccountInfo::new(U256::from(50), 0, vec![]));
+        assert!(!host.transfer(from, to, U256::from(51)));
+
+        host.checkpoint();
+        assert!(host.transfer(from, to, U256::from(20)));
+        host.sstore(to, U256::from(1), U256::from(2));
+        host.warm_account(to);
+        host.checkpoint();
+        host.tstore(to, U256::from(1), U256::from(3));
+        host.log(Log::new(to, vec![], vec![]));
+        host.checkpoint_commit();
+        host.checkpoint_revert();
+        assert_eq!(host.balance(from), U256::from(50));
+        assert_eq!(host.balance(to), U256::ZERO);
+        assert_eq!(host.sload(to, U256::from(1)), U256::ZERO);
+        assert_eq!(host.tload(to, U256::from(1)), U256::ZERO);
+        assert!(host.logs.is_empty());
+        assert!(!host.is_account_warm(to));
+
+        host.checkpoint();
+        assert!(host.transfer(from, to, U256::from(20)));
+        host.checkpoint_commit();
+        assert_eq!(host.balance(to), U256::from(20));
+    }
+
+    #[test]
+    fn test_inner_checkpoint_revert_keeps_the_outer_changes() {
+        let mut host = InMemoryHost::default();
+        let address = Address::repeat_byte(0x11);
+        let key = U256::from(1);
+        host.sstore(address, key, U256::from(1));
+
+        host.checkpoint();
+        host.sstore(address, key, U256::from(2));
+        host.tstore(address, key, U256::from(5));
+        host.checkpoint();
+        host.sstore(address, key, U256::from(3));
+        host.sstore(address, key, U256::from(4));
+        host.tstore(address, key, U256::ZERO);
+        host.warm_slot(address, key);
+        host.checkpoint_revert();
+        assert_eq!(host.sload(address, key), U256::from(2));
+        assert_eq!(host.tload(address, key), U256::from(5));
+        assert!(!host.is_slot_warm(address, key));
+
+        host.checkpoint_revert();
+        assert_eq!(host.sload(address, key), U256::from(1));
+        assert_eq!(host.tload(address, key), U256::ZERO);
+        assert!(host.journal.is_empty());
+    }
+
+    #[test]
+    fn test_writes_stay_out_of_the_database() {
+        let address = Address::repeat_byte(0x11);
+        let mut db = crate::db::InMemoryDB::default();
+        db.insert_storage(address, U256::from(1), U256::from(2));
+        let mut host = DatabaseHost::with_db(Env::default(), db);
+        assert_eq!(host.sload(address, U256::from(1)), U256::from(2));
+        host.sstore(address, U256::from(1), U256::from(3));
+        assert_eq!(host.db.db.storage(address, U256::from(1)), U256::from(2));
+        host.db.commit();
+        assert_eq!(host.db.db.storage(address, U256::from(1)), U256::from(3));
+    }
+}
@@ -0,0 +1,110 @@
+// Hooks into execution for tracers, coverage and security checks. Attach one with
+// `EVM::set_inspector`, run, and get it back with `EVM::inspector` or `EVM::take_inspector`.
+// Every hook sees the EVM read-only, so the stack, memory, pc, gas and host are all at hand:
+//   struct OpCounter(usize);
+//   impl<H: Host> Inspector<H> for OpCounter {
+//       fn step(&mut self, _: &EVM<H>) { self.0 += 1; }
+//   }
+// Without an inspector attached, every hook site is a single `None` check.
+
+use std::any::Any;
+
+use alloy_primitives::{Address, U256};
+
+use crate::{
+    evm::{EVM, EvmError, Log},
+    host::Host,
+};
+
+// what a message call (EXTCALL) is about to do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallInputs {
+    pub caller: Address,
+    pub target: Address,
+    pub value: U256,
+    pub input: Vec<u8>,
+    // what the callee may spend
+    pub gas_limit: u64,
+    pub is_static: bool,
+}
+
+// what a contract creation is about to do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreateInputs {
+    pub caller: Address,
+    pub value: U256,
+    pub init_code: Vec<u8>,
+    pub gas_limit: u64,
+}
+
+// how a call or creation ended, as its caller sees it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallStatus {
+    Success,
+    Revert,
+    // an exceptional halt, all the gas given is gone
+    Halt,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallOutcome {
+    pub status: CallStatus,
+    pub output: Vec<u8>,
+    pub gas_used: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreateOutcome {
+    pub status: CallStatus,
+    // the new contract, None if the creation failed
+    pub address: Option<Address>,
+    pub output: Vec<u8>,
+    pub gas_used: u64,
+}
+
+// Every hook does nothing by default, implement the ones you need.
+// No instruction creates contracts yet, so `create` and `create_end` are never called for now.
+pub trait Inspector<H: Host> {
+    // before the instruction at `vm.pc` runs, once its opcode is known to be the next one
+    fn step(&mut self, _vm: &EVM<H>) {}
+    // after it ran, `vm.pc` already points at the next one
+    fn step_end(&mut self, _vm: &EVM<H>, _result: &Result<(), EvmError>) {}
+
+    fn call(&mut self, _vm: &EVM<H>, _inputs: &CallInputs) {}
+    fn call_end(&mut self, _vm: &EVM<H>, _inputs: &CallInputs, _outcome: &CallOutcome) {}
+    fn create(&mut self, _vm: &EVM<H>, _inputs: &CreateInputs) {}
+    fn create_end(&mut self, _vm: &EVM<H>, _inputs: &CreateInputs, _outcome: &CreateOutcome) {}
+
+    // state changes, once they are paid for and before the host sees them
+    fn log(&mut self, _vm: &EVM<H>, _log: &Log) {}
+    fn sstore(&mut self, _vm: &EVM<H>, _address: Address, _key: U256, _old: U256, _new: U256) {}
+    // `value` is the balance about to move to `target`
+    fn selfdestruct(&mut self, _vm: &EVM<H>, _address: Address, _target: Address, _value: U256) {}
+}
+
+// what `EVM` keeps: an inspector it can hand back as its concrete type
+pub(crate) trait AnyInspector<H: Host>: Inspector<H> {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+    fn as_inspector(&mut self) -> &mut dyn Inspector<H>;
+}
+
+impl<H: Host, T: Inspector<H> + 'static> AnyInspector<H> for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+
+    fn as_inspector(&mut self) -> &mut dyn Inspector<H> {
+        self
+    }
+}
+
@@ -1,6 +1,18 @@
 pub mod stack;
 pub mod memory;
-pub mod storage;
 pub mod evm;
 pub mod opcodes;
+pub mod eof;
+pub mod db;
+pub mod host;
+pub mod fork;
+pub mod gas;
+pub mod inspector;
+pub mod precompile;
+pub mod tracing;
+pub mod disassembler;
+pub mod sourcemap;
+pub mod abi;
+pub mod statetest;
+pub mod vectors;
 pub mod helpers;
\ No newline at end of file
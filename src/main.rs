@@ -1,6 +1,75 @@
-fn main() {
-    println!("Hello, world!");
-}
+// Runs bytecode from the command line:
+//   cargo run --bin evm -- [--gas <limit>] [--trace] <hex bytecode>
+//   cargo run --bin evm -- [--gas <limit>] [--trace] --artifact <out.json> [--contract <name>]
+// The output goes to stdout, `--trace` writes an EIP-3155 trace to stderr like
+// `geth evm --json run`.
+// With `--artifact` (solc --combined-json bin-runtime,srcmap-runtime) the runtime code of the
+// contract is run and every trace line says which Solidity line it belongs to.
+
+use std::{env, io, process};
+
+use alloy_primitives::{Address, U256, hex};
+use anyhow::{Context, bail};
+use evm::{evm::EVM, sourcemap::Artifact, tracing::Eip3155Tracer};
 
+const USAGE: &str = "usage: evm [--gas <limit>] [--trace] \
+                     (<hex bytecode> | --artifact <combined json> [--contract <name>])";
 
-// @note -> Build a Terminal User Interface (TUI) using the ratatui crate.
\ No newline at end of file
+fn main() -> Result<(), anyhow::Error> {
+    let mut gas = 10_000_000;
+    let mut trace = false;
+    let mut code = None;
+    let mut artifact = None;
+    let mut contract = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--trace" => trace = true,
+            "--gas" => {
+                let limit = args.next().context(USAGE)?;
+                gas = limit.parse().with_context(|| format!("invalid gas limit {limit}"))?;
+            }
+            "--artifact" => artifact = Some(args.next().context(USAGE)?),
+            "--contract" => contract = Some(args.next().context(USAGE)?),
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return Ok(());
+            }
+            _ if code.is_none() => code = Some(arg),
+            _ => bail!(USAGE),
+        }
+    }
+    let (program, debug_info) = match (code, artifact) {
+        (Some(code), None) => (hex::decode(code.trim()).context("invalid bytecode")?, None),
+        (None, Some(path)) => {
+            let artifact = Artifact::load(&path, contract.as_deref())
+                .map_err(|err| anyhow::anyhow!("{:?}", err))
+                .with_context(|| format!("could not load {path}"))?;
+            (artifact.code, Some(artifact.debug_info))
+        }
+        _ => bail!(USAGE),
+    };
+
+    let mut evm = EVM::new(Address::ZERO, program, gas, U256::ZERO, vec![]);
+    if trace {
+        evm.set_inspector(match debug_info {
+            Some(debug_info) => Eip3155Tracer::with_debug_info(io::stderr(), debug_info),
+            None => Eip3155Tracer::new(io::stderr()),
+        });
+    }
+    let result = evm.run();
+    if let Some(mut tracer) = evm.take_inspector::<Eip3155Tracer<io::Stderr>>() {
+        tracer.write_summary(&evm, &result)?;
+    }
+
+    println!("0x{}", hex::encode(&evm.output));
+    if let Err(err) = result {
+        eprintln!("error: {:?}", err);
+        process::exit(1);
+    }
+    if evm.revert_flag {
+        eprintln!("error: execution reverted");
+        process::exit(1);
+    }
+    Ok(())
+}
@@ -1,33 +1,121 @@
-use crate::evm::EvmError;
+use alloy_primitives::U256;
 
+use crate::{evm::EvmError, gas::GasSchedule};
+
+const WORD_SIZE: usize = 32;
+
+// the cap of an unmetered run without one of its own, there is no gas to stop memory from growing
+pub const UNMETERED_LIMIT: usize = 32 * 1024 * 1024;
+
+// Memory is charged before it is allocated:
+//   1. `expansion_cost` works out what growing to [offset, offset + size) costs, without touching the buffer
+//   2. the handler charges that cost together with the rest of the instruction
+//   3. only then `resize`, `load`, `store`, `read` or `copy_padded` grow the buffer
+// An offset nobody could pay for fails in step 1 with OutOfGas, so a huge CALLDATACOPY never allocates.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Memory {
     pub memory: Vec<u8>,
+    // optional hard cap in bytes, checked on top of gas
+    pub limit: Option<usize>,
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// number of 32-byte words needed to hold `size` bytes, saturating for sizes no one can pay for
+pub fn word_count(size: U256) -> u64 {
+    size.div_ceil(U256::from(WORD_SIZE)).saturating_to::<u64>()
+}
+
+// `per_word` gas for every word of `size` bytes, a price past u64 is more than anyone can pay
+pub fn word_gas(per_word: u64, size: U256) -> Result<u64, EvmError> {
+    per_word.checked_mul(word_count(size)).ok_or(EvmError::OutOfGas)
+}
+
+// adds up the parts of an instruction's price, OutOfGas if the total doesn't fit in a u64
+pub fn sum_gas(parts: &[u64]) -> Result<u64, EvmError> {
+    parts
+        .iter()
+        .try_fold(0u64, |total, part| total.checked_add(*part))
+        .ok_or(EvmError::OutOfGas)
 }
 
 impl Memory {
     pub fn new() -> Self {
-        Self { memory: Vec::new() }
+        Self {
+            memory: Vec::new(),
+            limit: None,
+        }
     }
-    pub fn ensure_capacity(&mut self, offset: usize, size: usize) -> u64 {
-        let required_len = offset.saturating_add(size);
-        if required_len <= self.memory.len() {
-            return 0u64;
+
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            memory: Vec::new(),
+            limit: Some(limit),
         }
-        // old cost
-        let current_words = (self.memory.len() as u64 + 31) / 32;
-        let old_cost = Self::calculate_memory_gas(current_words);
+    }
 
-        // new cost
-        let required_words = (required_len as u64 + 31) / 32;
-        let new_cost = Self::calculate_memory_gas(required_words);
+    // gas needed to make [offset, offset + size) addressable, the buffer is left untouched
+    // a zero size never expands memory, whatever the offset
+    // Ok guarantees that offset + size fits in a usize, so callers may convert both after paying
+    pub fn expansion_cost(
+        &self,
+        offset: U256,
+        size: U256,
+        schedule: &GasSchedule,
+    ) -> Result<u64, EvmError> {
+        self.expansion_cost_within(offset, size, schedule, self.limit)
+    }
 
-        let expansion_cost = new_cost.saturating_sub(old_cost);
+    // `expansion_cost` under `limit` instead of the memory's own
+    pub fn expansion_cost_within(
+        &self,
+        offset: U256,
+        size: U256,
+        schedule: &GasSchedule,
+        limit: Option<usize>,
+    ) -> Result<u64, EvmError> {
+        if size.is_zero() {
+            return Ok(0);
+        }
+        // anything past usize could never be paid for, fail before doing math with it
+        let required_len = offset
+            .checked_add(size)
+            .and_then(|end| usize::try_from(end).ok())
+            .ok_or(EvmError::OutOfGas)?;
+        if let Some(limit) = limit
+            && required_len > limit
+        {
+            return Err(EvmError::MemoryLimitExceeded {
+                required: required_len,
+                limit,
+            });
+        }
+        if required_len <= self.memory.len() {
+            return Ok(0);
+        }
+        let old_cost = Self::calculate_memory_gas(self.memory.len().div_ceil(WORD_SIZE), schedule)?;
+        let new_cost = Self::calculate_memory_gas(required_len.div_ceil(WORD_SIZE), schedule)?;
+        Ok(new_cost - old_cost)
+    }
 
-        let new_size_bytes = (required_words * 32) as usize;
+    // grows the buffer to cover [offset, offset + size), rounded up to a whole word
+    // the expansion must have been paid for with `expansion_cost` first
+    pub fn resize(&mut self, offset: usize, size: usize) {
+        if size == 0 {
+            return;
+        }
+        let required_len = offset.saturating_add(size);
+        if required_len <= self.memory.len() {
+            return;
+        }
+        let new_size_bytes = required_len.div_ceil(WORD_SIZE) * WORD_SIZE;
         self.memory.resize(new_size_bytes, 0);
-
-        expansion_cost
     }
+
     pub fn access(&self, offset: usize, size: usize) -> Result<&[u8], EvmError> {
         let end = offset.saturating_add(size);
         if end > self.memory.len() {
@@ -39,35 +127,72 @@ impl Memory {
         }
         Ok(&self.memory[offset..end])
     }
-    pub fn load(&mut self, offset: usize) -> ([u8; 32], u64) {
-        const WORD_SIZE: usize = 32;
-        let required_len = offset.saturating_add(WORD_SIZE);
-        let expansion_cost = self.ensure_capacity(offset, WORD_SIZE);
+
+    pub fn load(&mut self, offset: usize) -> [u8; WORD_SIZE] {
+        self.resize(offset, WORD_SIZE);
         let mut word = [0u8; WORD_SIZE];
-        let available_data = &self.memory[offset..required_len];
-        word.copy_from_slice(available_data);
-        (word, expansion_cost)
+        word.copy_from_slice(&self.memory[offset..offset + WORD_SIZE]);
+        word
     }
-    pub fn store(&mut self, offset: usize, value: &[u8]) -> u64 {
+
+    // writes `value` at `offset`, the buffer only grows as far as the last byte written
+    // MSTORE and MSTORE8 `resize` to whole words first, like every other instruction
+    pub fn store(&mut self, offset: usize, value: &[u8]) {
         let size = value.len();
         if size == 0 {
-            return 0;
+            return;
+        }
+        let end = offset + size;
+        if end > self.memory.len() {
+            self.memory.resize(end, 0);
+        }
+        self.memory[offset..end].copy_from_slice(value);
+    }
+
+    // copies [offset, offset + size) out of memory, growing it with zeros if needed
+    // a zero size returns nothing, whatever the offset
+    pub fn read(&mut self, offset: U256, size: U256) -> Vec<u8> {
+        if size.is_zero() {
+            return Vec::new();
         }
-        let expansion_cost = self.ensure_capacity(offset, size); // expands memory to be able to store value
-        let dest = &mut self.memory[offset..offset + size];
-        dest.copy_from_slice(value);
-        expansion_cost
+        let (offset, size) = (offset.to::<usize>(), size.to::<usize>());
+        self.resize(offset, size);
+        self.memory[offset..offset + size].to_vec()
     }
 
-    fn calculate_memory_gas(size_in_words: u64) -> u64 {
-        let linear_cost = size_in_words * 3;
-        let quadratic_cost = (size_in_words * size_in_words) / 512;
-        linear_cost + quadratic_cost
+    // writes `size` bytes of `source`, starting at `src_offset`, to memory at `dest_offset`
+    // bytes past the end of `source` are written as zeros
+    // copies straight into the buffer, so no temporary allocation of `size` bytes is made
+    pub fn copy_padded(&mut self, dest_offset: U256, source: &[u8], src_offset: U256, size: U256) {
+        if size.is_zero() {
+            return;
+        }
+        let (dest_offset, size) = (dest_offset.to::<usize>(), size.to::<usize>());
+        self.resize(dest_offset, size);
+        let dest = &mut self.memory[dest_offset..dest_offset + size];
+
+        let src_offset = src_offset.saturating_to::<usize>();
+        let copy_len = source.len().saturating_sub(src_offset).min(size);
+        if copy_len > 0 {
+            dest[..copy_len].copy_from_slice(&source[src_offset..src_offset + copy_len]);
+        }
+        dest[copy_len..].fill(0);
+    }
+
+    // a price per word plus a quadratic term, computed in 128 bits so it can't overflow
+    pub(crate) fn calculate_memory_gas(
+        size_in_words: usize,
+        schedule: &GasSchedule,
+    ) -> Result<u64, EvmError> {
+        let size_in_words = size_in_words as u128;
+        let linear_cost = size_in_words * schedule.memory_word as u128;
+        let quadratic_cost =
+            (size_in_words * size_in_words) / schedule.memory_quad_divisor.get() as u128;
+        u64::try_from(linear_cost + quadratic_cost).map_err(|_| EvmError::OutOfGas)
     }
 }
 
 #[cfg(test)]
-
 mod tests {
     use super::*;
 
@@ -75,6 +200,10 @@ mod tests {
         Memory::new()
     }
 
+    fn expansion_cost(mem: &Memory, offset: U256, size: U256) -> Result<u64, EvmError> {
+        mem.expansion_cost(offset, size, &GasSchedule::default())
+    }
+
     #[test]
     fn test_memory_init() {
         let mem = init_memory();
@@ -123,7 +252,7 @@ mod tests {
         let mut mem = init_memory();
         mem.store(0, &[0x01, 0x02, 0x03, 0x04]);
         assert_eq!(
-            mem.load(0).0,
+            mem.load(0),
             [
                 1, 2, 3, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
                 0, 0, 0, 0
@@ -136,7 +265,7 @@ mod tests {
         let mut mem = init_memory();
         mem.store(0, &[0x01, 0x02, 0x03, 0x04]);
         assert_eq!(
-            mem.load(1).0,
+            mem.load(1),
             [
                 2, 3, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
                 0, 0, 0, 0
@@ -148,11 +277,79 @@ mod tests {
         let mut mem = init_memory();
         mem.store(0, &[0x01, 0x02, 0x03, 0x04]);
         assert_eq!(
-            mem.load(3).0,
+            mem.load(3),
             [
                 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
                 0, 0, 0, 0
             ]
         );
     }
+
+    #[test]
+    fn test_expansion_cost_does_not_allocate() {
+        let mem = init_memory();
+        assert_eq!(expansion_cost(&mem, U256::ZERO, U256::from(32)), Ok(3));
+        assert_eq!(expansion_cost(&mem, U256::ZERO, U256::from(1024)), Ok(98));
+        assert_eq!(mem.memory.len(), 0);
+    }
+
+    #[test]
+    fn test_expansion_cost_of_grown_memory() {
+        let mut mem = init_memory();
+        mem.store(0, &[0x01]);
+        assert_eq!(expansion_cost(&mem, U256::ZERO, U256::from(32)), Ok(0));
+        assert_eq!(expansion_cost(&mem, U256::from(32), U256::ONE), Ok(3));
+    }
+
+    #[test]
+    fn test_expansion_cost_zero_size_ignores_offset() {
+        let mem = init_memory();
+        assert_eq!(expansion_cost(&mem, U256::MAX, U256::ZERO), Ok(0));
+    }
+
+    #[test]
+    fn test_expansion_cost_unpayable_offset() {
+        let mem = init_memory();
+        assert_eq!(
+            expansion_cost(&mem, U256::MAX, U256::ONE),
+            Err(EvmError::OutOfGas)
+        );
+        assert_eq!(
+            expansion_cost(&mem, U256::from(u64::MAX), U256::from(u64::MAX)),
+            Err(EvmError::OutOfGas)
+        );
+        // fits in a usize, but the quadratic cost is way past u64
+        assert_eq!(
+            expansion_cost(&mem, U256::ZERO, U256::from(usize::MAX)),
+            Err(EvmError::OutOfGas)
+        );
+    }
+
+    #[test]
+    fn test_memory_limit() {
+        let mem = Memory::with_limit(64);
+        assert_eq!(expansion_cost(&mem, U256::from(32), U256::from(32)), Ok(6));
+        assert_eq!(
+            expansion_cost(&mem, U256::from(33), U256::from(32)),
+            Err(EvmError::MemoryLimitExceeded {
+                required: 65,
+                limit: 64
+            })
+        );
+    }
+
+    #[test]
+    fn test_copy_padded() {
+        let mut mem = init_memory();
+        mem.copy_padded(U256::from(2), &[0xAA, 0xBB, 0xCC], U256::from(1), U256::from(4));
+        assert_eq!(mem.memory.len(), 32);
+        assert_eq!(&mem.memory[..7], &[0, 0, 0xBB, 0xCC, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_read_zero_size() {
+        let mut mem = init_memory();
+        assert!(mem.read(U256::MAX, U256::ZERO).is_empty());
+        assert_eq!(mem.memory.len(), 0);
+    }
 }
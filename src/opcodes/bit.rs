@@ -1,13 +1,15 @@
 use alloy_primitives::{I256, U256};
 
-use crate::evm::{EVM, EvmError};
+use crate::{
+    evm::{EVM, EvmError},
+    host::Host,
+};
 
 // gets one byte from a 32-byte Word
-pub fn byte(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(3)?;
+pub fn byte<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.very_low)?;
 
-    let i = vm.stack.pop()?;
-    let x = vm.stack.pop()?;
+    let (i, x) = vm.stack.pop2();
 
     let result = if i < U256::from(32) {
         // i < 32
@@ -19,28 +21,26 @@ pub fn byte(vm: &mut EVM) -> Result<(), EvmError> {
         U256::ZERO
     };
 
-    vm.stack.push(result)?;
+    vm.stack.push_unchecked(result);
     vm.pc += 1;
     Ok(())
 }
 
 // bit shift left
-pub fn shl(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(3)?;
-    let shift = vm.stack.pop()?;
-    let value = vm.stack.pop()?;
-    vm.stack.push(value << shift)?;
+pub fn shl<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.very_low)?;
+    let (shift, value) = vm.stack.pop2();
+    vm.stack.push_unchecked(value << shift);
 
     vm.pc += 1;
     Ok(())
 }
 
 // bit shift right
-pub fn shr(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(3)?;
-    let shift = vm.stack.pop()?;
-    let value = vm.stack.pop()?;
-    vm.stack.push(value >> shift)?;
+pub fn shr<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.very_low)?;
+    let (shift, value) = vm.stack.pop2();
+    vm.stack.push_unchecked(value >> shift);
 
     vm.pc += 1;
     Ok(())
@@ -48,10 +48,9 @@ pub fn shr(vm: &mut EVM) -> Result<(), EvmError> {
 
 // what's the difference btw an Arithmetic shift and a normal shift, how's it done in binary terms
 // signed shift right
-pub fn sar(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(3)?;
-    let shift = vm.stack.pop()?;
-    let value = vm.stack.pop()?;
+pub fn sar<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.very_low)?;
+    let (shift, value) = vm.stack.pop2();
 
     // some code
     let result;
@@ -74,7 +73,7 @@ pub fn sar(vm: &mut EVM) -> Result<(), EvmError> {
         result = shifted.into_raw();
     }
 
-    vm.stack.push(result)?;
+    vm.stack.push_unchecked(result);
     vm.pc += 1;
     Ok(())
 }
@@ -0,0 +1,82 @@
+// these opcodes read the block the transaction is in, straight from the host's environment
+
+use alloy_primitives::U256;
+
+use crate::{
+    evm::{EVM, EvmError},
+    host::Host,
+};
+
+// only the 256 most recent blocks are reachable, anything else (including the current block) is zero
+pub fn blockhash<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.blockhash)?;
+    let requested = vm.stack.pop1();
+    let current = vm.host.env().number;
+    let hash = match u64::try_from(requested) {
+        Ok(number) if number < current && number >= current.saturating_sub(256) => {
+            vm.host.block_hash(number).into()
+        }
+        _ => U256::ZERO,
+    };
+    vm.stack.push_unchecked(hash);
+    vm.pc += 1;
+    Ok(())
+}
+
+pub fn coinbase<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.base)?;
+    vm.stack.push_unchecked(vm.host.env().coinbase.into_word().into());
+    vm.pc += 1;
+    Ok(())
+}
+
+pub fn timestamp<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.base)?;
+    vm.stack.push_unchecked(U256::from(vm.host.env().timestamp));
+    vm.pc += 1;
+    Ok(())
+}
+
+pub fn number<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.base)?;
+    vm.stack.push_unchecked(U256::from(vm.host.env().number));
+    vm.pc += 1;
+    Ok(())
+}
+
+pub fn prevrandao<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.base)?;
+    vm.stack.push_unchecked(vm.host.env().prev_randao.into());
+    vm.pc += 1;
+    Ok(())
+}
+
+pub fn gas_limit<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.base)?;
+    vm.stack.push_unchecked(U256::from(vm.host.env().gas_limit));
+    vm.pc += 1;
+    Ok(())
+}
+
+pub fn chain_id<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.base)?;
+    vm.stack.push_unchecked(U256::from(vm.host.env().chain_id));
+    vm.pc += 1;
+    Ok(())
+}
+
+// the running account's balance, which is always warm
+pub fn self_balance<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.low)?;
+    let balance = vm.host.balance(vm.address);
+    vm.stack.push_unchecked(balance);
+    vm.pc += 1;
+    Ok(())
+}
+
+pub fn base_fee<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.base)?;
+    vm.stack.push_unchecked(vm.host.env().base_fee);
+    vm.pc += 1;
+    Ok(())
+}
@@ -1,70 +1,68 @@
 use alloy_primitives::{I256, U256};
 
-use crate::evm::{EVM, EvmError};
+use crate::{
+    evm::{EVM, EvmError},
+    host::Host,
+};
 
-pub fn lt(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(3)?;
-    let a = vm.stack.pop()?;
-    let b = vm.stack.pop()?;
+pub fn lt<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.very_low)?;
+    let (a, b) = vm.stack.pop2();
     let result = if a < b { U256::ONE } else { U256::ZERO };
-    vm.stack.push(result)?;
+    vm.stack.push_unchecked(result);
     vm.pc += 1;
     Ok(())
 }
 
-pub fn slt(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(3)?;
-    let a_raw = vm.stack.pop()?;
-    let b_raw = vm.stack.pop()?;
+pub fn slt<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.very_low)?;
+    let (a_raw, b_raw) = vm.stack.pop2();
     let a = I256::from_raw(a_raw);
     let b = I256::from_raw(b_raw);
     let result = if a < b { U256::ONE } else { U256::ZERO };
-    vm.stack.push(result)?;
+    vm.stack.push_unchecked(result);
     vm.pc += 1;
     Ok(())
 }
 
-pub fn gt(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(3)?;
-    let a = vm.stack.pop()?;
-    let b = vm.stack.pop()?;
+pub fn gt<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.very_low)?;
+    let (a, b) = vm.stack.pop2();
     let result = if a > b { U256::ONE } else { U256::ZERO };
-    vm.stack.push(result)?;
+    vm.stack.push_unchecked(result);
     vm.pc += 1;
     Ok(())
 }
 
-pub fn sgt(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(3)?;
-    let a_raw = vm.stack.pop()?;
-    let b_raw = vm.stack.pop()?;
+pub fn sgt<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.very_low)?;
+    let (a_raw, b_raw) = vm.stack.pop2();
     let a = I256::from_raw(a_raw);
     let b = I256::from_raw(b_raw);
     let result = if a > b { U256::ONE } else { U256::ZERO };
-    vm.stack.push(result)?;
+    vm.stack.push_unchecked(result);
     vm.pc += 1;
     Ok(())
 }
 
-pub fn eq(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(3)?;
-    let a = vm.stack.pop()?;
-    let b = vm.stack.pop()?;
+pub fn eq<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.very_low)?;
+    let (a, b) = vm.stack.pop2();
     let result = if a == b { U256::ONE } else { U256::ZERO };
-    vm.stack.push(result)?;
+    vm.stack.push_unchecked(result);
     vm.pc += 1;
     Ok(())
 }
 
-pub fn is_zero(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(3)?;
-    let value = vm.stack.pop()?;
+pub fn is_zero<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.very_low)?;
+    let value = vm.stack.pop1();
     let result = if value == U256::ZERO {
         U256::ONE
     } else {
         U256::ZERO
     };
-    vm.stack.push(result)?;
+    vm.stack.push_unchecked(result);
     vm.pc += 1;
     Ok(())
 }
@@ -1,16 +1,35 @@
-use crate::evm::{EVM, EvmError};
-
-pub fn revert(vm: &mut EVM) -> Result<(), EvmError> {
-    let offset_raw = vm.stack.pop()?;
-    let size_raw = vm.stack.pop()?;
-    let offset = offset_raw.saturating_to::<usize>();
-    let size = size_raw.saturating_to::<usize>();
-    
-    let expansion_cost = vm.memory.ensure_capacity(offset, size);
+use alloy_primitives::U256;
+
+use crate::{
+    evm::{EVM, EvmError},
+    host::Host,
+    opcodes::environment::to_address,
+};
+
+// copies memory[offset..offset + size] into the frame's output, shared by RETURN and REVERT
+// this is what the caller will see as its return data, the frame's own `return_data` is left alone
+fn write_output<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    // read the arguments in place, nothing changes until the gas is paid
+    let offset = vm.stack.nth(0);
+    let size = vm.stack.nth(1);
+
+    let expansion_cost = vm.memory_expansion_cost(offset, size)?;
     // static gas cost is zero, only memory epansion cost is paid
     vm.gas_dec(expansion_cost)?;
+    vm.stack.pop_n(2);
+
+    vm.output = vm.memory.read(offset, size);
+    Ok(())
+}
+
+pub fn vm_return<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    write_output(vm)?;
+    vm.stop_flag = true;
+    Ok(())
+}
 
-    vm.return_data = vm.memory.access(offset, size)?.to_vec();
+pub fn revert<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    write_output(vm)?;
 
     vm.revert_flag = true;
     vm.stop_flag = true;
@@ -19,3 +38,31 @@ pub fn revert(vm: &mut EVM) -> Result<(), EvmError> {
     // i.e the vm wont check pc again
     Ok(())
 }
+
+// hands the whole balance to the beneficiary and halts, since Cancun (EIP-6780) the account itself stays
+pub fn selfdestruct<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.ensure_not_static()?;
+    let target = to_address(vm.stack.nth(0));
+
+    // a cold beneficiary (EIP-2929), and one that has to be created to receive the value (EIP-161)
+    // an account with neither balance nor code counts as missing
+    let access_cost = if vm.host.is_account_warm(target) {
+        0
+    } else {
+        vm.gas_schedule.selfdestruct_cold_beneficiary
+    };
+    let balance = vm.host.balance(vm.address);
+    let creates_account = balance != U256::ZERO
+        && vm.host.balance(target) == U256::ZERO
+        && vm.host.code(target).is_empty();
+    let new_account_cost = if creates_account { vm.gas_schedule.new_account } else { 0 };
+    vm.gas_dec(vm.gas_schedule.selfdestruct + access_cost + new_account_cost)?;
+    vm.stack.pop_n(1);
+
+    vm.host.warm_account(target);
+    let address = vm.address;
+    vm.inspect(|inspector, vm| inspector.selfdestruct(vm, address, target, balance));
+    vm.host.selfdestruct(vm.address, target);
+    vm.stop_flag = true;
+    Ok(())
+}
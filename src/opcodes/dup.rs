@@ -1,13 +1,16 @@
-use crate::evm::{EVM, EvmError};
+use crate::{
+    evm::{EVM, EvmError},
+    host::Host,
+};
 
 // DUP N
 // Duplicate a stack item by putting it on top of the stack.
 // DUP1 means duplicate the first item on the stack; so we peek at the offset 0
 // DUP2 means duplicate the first item on the stack; so we peek at the offset 1
-pub fn dup(vm: &mut EVM, n: usize) -> Result<(), EvmError> {
-    vm.gas_dec(3)?;
-    let value = vm.stack.peek(n - 1)?;
-    vm.stack.push(value)?;
+pub fn dup<H: Host>(vm: &mut EVM<H>, n: usize) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.very_low)?;
+    let value = vm.stack.nth(n - 1);
+    vm.stack.push_unchecked(value);
     
     vm.pc += 1;
 
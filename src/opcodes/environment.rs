@@ -2,52 +2,74 @@
 
 use std::cmp::min;
 
-use alloy_primitives::U256;
+use alloy_primitives::{Address, U256};
 
-use crate::evm::{EVM, EvmError};
+use crate::{
+    evm::{EVM, EvmError},
+    host::Host,
+    memory::{sum_gas, word_gas},
+};
 
-pub fn address(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(2)?;
-    vm.stack.push(vm.sender.into_word().into())?;
+// legacy code keeps the low 20 bytes of an address argument and ignores the rest
+pub(crate) fn to_address(word: U256) -> Address {
+    Address::from_word(word.into())
+}
+
+// EIP-2929: the first touch of an account is cold, every later one warm
+// precompiles are warm from the start
+pub(crate) fn account_access_cost<H: Host>(vm: &EVM<H>, address: Address) -> u64 {
+    if vm.host.is_account_warm(address) || vm.precompiles.contains(address) {
+        vm.gas_schedule.warm_account_access
+    } else {
+        vm.gas_schedule.cold_account_access
+    }
+}
+
+pub fn address<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.base)?;
+    vm.stack.push_unchecked(vm.address.into_word().into());
     vm.pc += 1;
     Ok(())
 }
 
-pub fn balance(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(2600)?;
-    let _address = vm.stack.pop()?;
-    vm.stack.push(U256::from(9999999))?;
+pub fn balance<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    let address = to_address(vm.stack.nth(0));
+    vm.gas_dec(account_access_cost(vm, address))?;
+    vm.stack.pop_n(1);
+    vm.host.warm_account(address);
+    let balance = vm.host.balance(address);
+    vm.stack.push_unchecked(balance);
     vm.pc += 1;
     Ok(())
 }
 
-pub fn origin(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(2)?;
-    vm.stack.push(vm.sender.into_word().into())?;
+pub fn origin<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.base)?;
+    vm.stack.push_unchecked(vm.host.env().origin.into_word().into());
     vm.pc += 1;
     Ok(())
 }
 
-// pub fn caller(vm: &mut EVM) -> Result<(), EvmError> {
-//     vm.gas_dec(2)?;
-//     vm.stack.push("0x414b60745072088d013721b4a28a0559b1A9d213")?;
-//     vm.pc += 1;
-//     Ok(())
-// }
+pub fn caller<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.base)?;
+    vm.stack.push_unchecked(vm.sender.into_word().into());
+    vm.pc += 1;
+    Ok(())
+}
 
-pub fn call_value(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(2)?;
-    vm.stack.push(vm.value)?;
+pub fn call_value<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.base)?;
+    vm.stack.push_unchecked(vm.value);
     vm.pc += 1;
     Ok(())
 }
 
 // reads input data of stuffs like functions into the Stack
 // also handles infinite zero shi
-pub fn call_data_load(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(3)?;
+pub fn call_data_load<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.very_low)?;
 
-    let i = vm.stack.pop()?;
+    let i = vm.stack.pop1();
     let start_index = i.saturating_to::<usize>();
     let mut word_bytes = [0u8; 32]; // handles infinite zeros automatically
     // infinite zero case: if the size to be copied is larger than what is available,
@@ -60,156 +82,132 @@ pub fn call_data_load(vm: &mut EVM) -> Result<(), EvmError> {
         let data = &vm.calldata[start_index..end_index];
         word_bytes[..data.len()].copy_from_slice(data);
     }
-    vm.stack.push(U256::from_be_bytes(word_bytes))?;
+    vm.stack.push_unchecked(U256::from_be_bytes(word_bytes));
     vm.pc += 1;
     Ok(())
 }
 
 // pushes the size of data onto the stack
-pub fn call_data_size(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(2)?;
-    vm.stack.push(U256::from(vm.calldata.len()))?;
+pub fn call_data_size<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.base)?;
+    vm.stack.push_unchecked(U256::from(vm.calldata.len()));
     vm.pc += 1;
 
     Ok(())
 }
 
 // stores a specific part of the calldata in memory
-pub fn call_data_copy(vm: &mut EVM) -> Result<(), EvmError> {
-    let dest_offset_raw = vm.stack.pop()?;
-    let src_offset_raw = vm.stack.pop()?;
-    let size_raw = vm.stack.pop()?;
-
-    let src_offset = src_offset_raw.saturating_to::<usize>();
-    let size = size_raw.saturating_to::<usize>();
-    let dest_offset = dest_offset_raw.saturating_to::<usize>();
-
-    let mut data = vec![0u8; size];
-    // copy if source offset is within the bound of calldata.len()
-    if src_offset < vm.calldata.len() {
-        let available_bytes = vm.calldata.len() - src_offset;
-        let copy_len = min(size, available_bytes);
-        let copy_slice = &vm.calldata[src_offset..src_offset + copy_len];
-        data[..copy_len].copy_from_slice(copy_slice);
-    }
-    // store in memory
-    let expansion_cost = vm.memory.store(dest_offset, &data);
-
-    // calculate gas
-    let min_word_size = (size as u64 + 31) / 32;
-    let dynamic_gas = 3 * min_word_size + expansion_cost;
-    let static_gas = 3u64;
-    vm.gas_dec(dynamic_gas + static_gas)?;
+pub fn call_data_copy<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    // read the arguments in place, nothing changes until the gas is paid
+    let dest_offset = vm.stack.nth(0);
+    let src_offset = vm.stack.nth(1);
+    let size = vm.stack.nth(2);
+
+    // calculate gas, before memory is touched
+    let expansion_cost = vm.memory_expansion_cost(dest_offset, size)?;
+    let copy_gas = word_gas(vm.gas_schedule.copy_word, size)?;
+    let static_gas = vm.gas_schedule.very_low;
+    vm.gas_dec(sum_gas(&[copy_gas, expansion_cost, static_gas])?)?;
+    vm.stack.pop_n(3);
+
+    // store in memory, padding with zeros past the end of calldata
+    vm.memory.copy_padded(dest_offset, &vm.calldata, src_offset, size);
 
     vm.pc += 1;
     Ok(())
 }
 
 // puts the size of the running program on the stack
-pub fn code_size(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(2)?;
-    vm.stack.push(U256::from(vm.program.len()))?;
+pub fn code_size<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.base)?;
+    vm.stack.push_unchecked(U256::from(vm.program.len()));
     vm.pc += 1;
 
     Ok(())
 }
 
 // stores a specified part of the program in memory
-pub fn code_copy(vm: &mut EVM) -> Result<(), EvmError> {
-    let dest_offset_raw = vm.stack.pop()?;
-    let src_offset_raw = vm.stack.pop()?;
-    let size_raw = vm.stack.pop()?;
-
-    let src_offset = src_offset_raw.saturating_to::<usize>();
-    let size = size_raw.saturating_to::<usize>();
-    let dest_offset = dest_offset_raw.saturating_to::<usize>();
-
-    let mut data = vec![0u8; size];
-    // copy if source offset is within the bound of program.len()
-    if src_offset < vm.program.len() {
-        let available_bytes = vm.program.len() - src_offset;
-        let copy_len = min(size, available_bytes);
-        let copy_slice = &vm.program[src_offset..src_offset + copy_len];
-        data[..copy_len].copy_from_slice(copy_slice);
-    }
-    // store copied program in memory
-    let expansion_cost = vm.memory.store(dest_offset, &data);
-    // calculate gas
-    let min_word_size = (size as u64 + 31) / 32;
-    let dynamic_gas = 3 * min_word_size + expansion_cost;
-    let static_gas = 3u64;
-    vm.gas_dec(dynamic_gas + static_gas)?;
+pub fn code_copy<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    // read the arguments in place, nothing changes until the gas is paid
+    let dest_offset = vm.stack.nth(0);
+    let src_offset = vm.stack.nth(1);
+    let size = vm.stack.nth(2);
+
+    // calculate gas, before memory is touched
+    let expansion_cost = vm.memory_expansion_cost(dest_offset, size)?;
+    let copy_gas = word_gas(vm.gas_schedule.copy_word, size)?;
+    let static_gas = vm.gas_schedule.very_low;
+    vm.gas_dec(sum_gas(&[copy_gas, expansion_cost, static_gas])?)?;
+    vm.stack.pop_n(3);
+
+    // store copied program in memory, padding with zeros past the end of the program
+    vm.memory.copy_padded(dest_offset, &vm.program, src_offset, size);
 
     vm.pc += 1;
     Ok(())
 }
 
-// The current gas price. Because we are running everything locally, the gas price is simply 0.
-pub fn gas_price(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(2)?;
-    vm.stack.push(U256::ZERO)?;
+// the gas price of the transaction
+pub fn gas_price<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.base)?;
+    vm.stack.push_unchecked(vm.host.env().gas_price);
     vm.pc += 1;
     Ok(())
 }
 
 // checks the size of a code at an address
-// push zero because we'll be running this locally
-pub fn ext_code_size(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(2600)?;
-    let _address = vm.stack.pop()?; // pops address off the stack
-    vm.stack.push(U256::ZERO)?;
+pub fn ext_code_size<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    let address = to_address(vm.stack.nth(0));
+    vm.gas_dec(account_access_cost(vm, address))?;
+    vm.stack.pop_n(1);
+    vm.host.warm_account(address);
+    let size = vm.host.code(address).len();
+    vm.stack.push_unchecked(U256::from(size));
     vm.pc += 1;
     Ok(())
 }
 
 // copies size bytes from ext_code into memory
 // pads result with zeros if not up to the required bytes
-pub fn ext_code_copy(vm: &mut EVM) -> Result<(), EvmError> {
-    let _address = vm.stack.pop()?;
-    let dest_offset_raw = vm.stack.pop()?;
-    let src_offset_raw = vm.stack.pop()?;
-    let size_raw = vm.stack.pop()?;
-
-    let ext_code = [];  // no external code, just a mock
+pub fn ext_code_copy<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    // read the arguments in place, nothing changes until the gas is paid
+    let address = to_address(vm.stack.nth(0));
+    let dest_offset = vm.stack.nth(1);
+    let src_offset = vm.stack.nth(2);
+    let size = vm.stack.nth(3);
+
+    // calculate gas, before memory is touched or the account warmed
+    let access_cost = account_access_cost(vm, address);
+    let expansion_cost = vm.memory_expansion_cost(dest_offset, size)?;
+    let copy_gas = word_gas(vm.gas_schedule.copy_word, size)?;
+    vm.gas_dec(sum_gas(&[copy_gas, expansion_cost, access_cost])?)?;
+    vm.stack.pop_n(4);
+    vm.host.warm_account(address);
 
-    let src_offset = src_offset_raw.saturating_to::<usize>();
-    let size = size_raw.saturating_to::<usize>();
-    let dest_offset = dest_offset_raw.saturating_to::<usize>();
-    
-    let mut data = vec![0u8;size];
-    if src_offset < ext_code.len(){
-        let available_bytes = ext_code.len() - src_offset;
-        let copy_len = min(size, available_bytes);
-        let copy_slice = &ext_code[src_offset..src_offset + copy_len];
-        data[..copy_len].copy_from_slice(copy_slice);
-    }
     // store copied ext_code in memory
-    let expansion_cost = vm.memory.store(dest_offset, &data);
-    // calculate gas
-    let min_word_size = (size as u64 + 31) / 32;
-    let dynamic_gas = 3 * min_word_size + expansion_cost;
-    let static_gas = 3u64;
-    vm.gas_dec(dynamic_gas + static_gas)?;
-    
+    let ext_code = vm.host.code(address);
+    vm.memory.copy_padded(dest_offset, &ext_code, src_offset, size);
+
     vm.pc += 1;
     Ok(())
 }
 
-pub fn return_data_size(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(2)?;
-    vm.stack.push(U256::ZERO)?;
+// pushes the size of the last sub-call's return data
+pub fn return_data_size<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.base)?;
+    vm.stack.push_unchecked(U256::from(vm.return_data.len()));
     vm.pc += 1;
     Ok(())
 }
 
 // Stores a specified part of the previous return data in memory
 // unlike call_data_copy, it does not pad with zeros
-pub fn return_data_copy(vm: &mut EVM) -> Result<(), EvmError> {
-    let dest_offset_raw = vm.stack.pop()?;
-    let src_offset_raw = vm.stack.pop()?;
-    let size_raw = vm.stack.pop()?;
+pub fn return_data_copy<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    // read the arguments in place, nothing changes until the gas is paid
+    let dest_offset_raw = vm.stack.nth(0);
+    let src_offset_raw = vm.stack.nth(1);
+    let size_raw = vm.stack.nth(2);
 
-    let dest_offset = dest_offset_raw.saturating_to::<usize>();
     let src_offset = src_offset_raw.saturating_to::<usize>();
     let size = size_raw.saturating_to::<usize>();
 
@@ -224,29 +222,27 @@ pub fn return_data_copy(vm: &mut EVM) -> Result<(), EvmError> {
             max: vm.return_data.len(),
         });
     }
-    // gas cost
-    let expansion_cost = vm.memory.ensure_capacity(src_offset, size);
-    let min_word_size = (size as u64 + 31) /32;
-    let dynamic_gas = 3 * min_word_size + expansion_cost;
-    let static_gas = 3u64;
-    vm.gas_dec(dynamic_gas + static_gas )?;
+    // gas cost, memory grows at the destination
+    let expansion_cost = vm.memory_expansion_cost(dest_offset_raw, size_raw)?;
+    let copy_gas = word_gas(vm.gas_schedule.copy_word, size_raw)?;
+    let static_gas = vm.gas_schedule.very_low;
+    vm.gas_dec(sum_gas(&[copy_gas, expansion_cost, static_gas])?)?;
+    vm.stack.pop_n(3);
 
     // slice return_data, cos we've checked for outOfBound case
-    let data = &vm.return_data[src_offset..end_index];
-    // Since we already paid for expansion above, we ignore the return value here
-    vm.memory.store(dest_offset, data);
+    vm.memory.copy_padded(dest_offset_raw, &vm.return_data, src_offset_raw, size_raw);
     vm.pc += 1;
     Ok(())
 }
 
-// The hash of another program given by its address.
-// There are no other programs in our simplified world so we simply return 0.
-pub fn ext_code_hash(vm: &mut EVM) -> Result<(), EvmError> {
-    let _address = vm.stack.pop()?;
-    vm.gas_dec(2600)?;
-    vm.stack.push(U256::ZERO)?;
+// The hash of another program given by its address, zero if there is no account there.
+pub fn ext_code_hash<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    let address = to_address(vm.stack.nth(0));
+    vm.gas_dec(account_access_cost(vm, address))?;
+    vm.stack.pop_n(1);
+    vm.host.warm_account(address);
+    let hash = vm.host.code_hash(address);
+    vm.stack.push_unchecked(hash.into());
     vm.pc += 1;
     Ok(())
 }
-
-// leave out blockhash, coinbase, timestamp, prevrandao: for Now
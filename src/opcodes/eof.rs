@@ -0,0 +1,323 @@
+// Instructions that only exist inside an EOF container.
+// Every immediate read here was bounds checked when the container was validated,
+// so the handlers can index `vm.program` directly.
+
+use std::cmp::min;
+
+use alloy_primitives::U256;
+
+use crate::{
+    eof::Container,
+    evm::{EVM, EvmError, ReturnFrame},
+    host::Host,
+    inspector::{CallInputs, CallOutcome, CallStatus},
+    memory::{sum_gas, word_gas},
+    opcodes::environment::{account_access_cost, to_address},
+    precompile::PrecompileContext,
+    stack::MAXIMUM_STACK_SIZE,
+};
+
+// the maximum depth of CALLF frames
+pub const MAXIMUM_RETURN_STACK_SIZE: usize = 1024;
+
+fn read_u16<H: Host>(vm: &EVM<H>, pos: usize) -> usize {
+    ((vm.program[pos] as usize) << 8) | vm.program[pos + 1] as usize
+}
+
+fn read_i16<H: Host>(vm: &EVM<H>, pos: usize) -> isize {
+    i16::from_be_bytes([vm.program[pos], vm.program[pos + 1]]) as isize
+}
+
+fn container<H: Host>(vm: &EVM<H>) -> Result<&Container, EvmError> {
+    // only reachable when the EVM runs in EOF mode, but never trust the caller
+    vm.container.as_ref().ok_or(EvmError::UnknownOpcode {
+        opcode: format!("0x{:02x}", vm.program[vm.pc]),
+    })
+}
+
+// moves pc by a signed offset counted from the end of the current instruction
+fn relative_jump<H: Host>(vm: &mut EVM<H>, instruction_size: usize, offset: isize) {
+    vm.pc = (vm.pc as isize + instruction_size as isize + offset) as usize;
+}
+
+// RJUMP <i16>: unconditional jump, the destination was checked at deploy time
+pub fn rjump<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.base)?;
+    let offset = read_i16(vm, vm.pc + 1);
+    relative_jump(vm, 3, offset);
+    Ok(())
+}
+
+// RJUMPI <i16>: jumps if the condition on the stack is not zero
+pub fn rjumpi<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.rjumpi)?;
+    let condition = vm.stack.pop1();
+    let offset = if condition != U256::ZERO {
+        read_i16(vm, vm.pc + 1)
+    } else {
+        0
+    };
+    relative_jump(vm, 3, offset);
+    Ok(())
+}
+
+// RJUMPV <max_index: u8> <i16 * (max_index + 1)>: jump table indexed by the top of the stack
+// an index past the end of the table falls through to the next instruction
+pub fn rjumpv<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.rjumpi)?;
+    let case = vm.stack.pop1();
+    let max_index = vm.program[vm.pc + 1] as usize;
+    let instruction_size = 2 + (max_index + 1) * 2;
+    let offset = if case <= U256::from(max_index) {
+        read_i16(vm, vm.pc + 2 + case.to::<usize>() * 2)
+    } else {
+        0
+    };
+    relative_jump(vm, instruction_size, offset);
+    Ok(())
+}
+
+// CALLF <u16>: calls a code section, saving where to come back to on the return stack
+pub fn callf<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.low)?;
+    let target = read_u16(vm, vm.pc + 1);
+    let container = container(vm)?;
+    let max_stack_increase = container.types[target].max_stack_increase as usize;
+    let target_offset = container.code_offset(target);
+
+    if vm.stack.len() + max_stack_increase > MAXIMUM_STACK_SIZE {
+        return Err(EvmError::StackOverflow);
+    }
+    if vm.return_stack.len() >= MAXIMUM_RETURN_STACK_SIZE {
+        return Err(EvmError::ReturnStackOverflow);
+    }
+    vm.return_stack.push(ReturnFrame {
+        code_section: vm.code_section,
+        pc: vm.pc + 3,
+    });
+    vm.code_section = target;
+    vm.pc = target_offset;
+    Ok(())
+}
+
+// RETF: returns to the instruction after the CALLF that entered this section
+pub fn retf<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.very_low)?;
+    // validation guarantees the entry section never returns, so the return stack can't be empty
+    let frame = vm.return_stack.pop().ok_or(EvmError::StackUnderflow)?;
+    vm.code_section = frame.code_section;
+    vm.pc = frame.pc;
+    Ok(())
+}
+
+// JUMPF <u16>: tail call, the target section returns directly to our caller
+pub fn jumpf<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.low)?;
+    let target = read_u16(vm, vm.pc + 1);
+    let container = container(vm)?;
+    let max_stack_increase = container.types[target].max_stack_increase as usize;
+    let target_offset = container.code_offset(target);
+
+    if vm.stack.len() + max_stack_increase > MAXIMUM_STACK_SIZE {
+        return Err(EvmError::StackOverflow);
+    }
+    vm.code_section = target;
+    vm.pc = target_offset;
+    Ok(())
+}
+
+// DUPN <u8>: like DUP1..DUP16 but reaches down to 256 items
+pub fn dupn<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.very_low)?;
+    let n = vm.program[vm.pc + 1] as usize;
+    // the depth comes from the immediate, so the opcode table can't check the height for us
+    vm.stack.check(n + 1, n + 2)?;
+    let value = vm.stack.nth(n);
+    vm.stack.push_unchecked(value);
+    vm.pc += 2;
+    Ok(())
+}
+
+// SWAPN <u8>: swaps the top of the stack with the item (imm + 1) below it
+pub fn swapn<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.very_low)?;
+    let n = vm.program[vm.pc + 1] as usize + 1;
+    vm.stack.check(n + 1, n + 1)?;
+    vm.stack.swap(0, n);
+    vm.pc += 2;
+    Ok(())
+}
+
+// EXCHANGE <u8>: the high nibble n and low nibble m select two items below the top,
+// the items at depth n + 1 and n + m + 1 are swapped
+pub fn exchange<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.very_low)?;
+    let imm = vm.program[vm.pc + 1];
+    let n = (imm >> 4) as usize + 1;
+    let m = (imm & 0x0F) as usize + 1;
+    vm.stack.check(n + m + 1, n + m + 1)?;
+    vm.stack.swap(n, n + m);
+    vm.pc += 2;
+    Ok(())
+}
+
+// reads a 32-byte word from the data section, padding with zeros past its end
+fn read_data_word(data: &[u8], offset: usize) -> U256 {
+    let mut word = [0u8; 32];
+    if offset < data.len() {
+        let end = min(offset.saturating_add(32), data.len());
+        word[..end - offset].copy_from_slice(&data[offset..end]);
+    }
+    U256::from_be_bytes(word)
+}
+
+// DATALOAD: loads a word from the data section at the offset on the stack
+pub fn dataload<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.dataload)?;
+    let offset = vm.stack.pop1().saturating_to::<usize>();
+    let word = read_data_word(container(vm)?.data(), offset);
+    vm.stack.push_unchecked(word);
+    vm.pc += 1;
+    Ok(())
+}
+
+// DATALOADN <u16>: loads a word from the data section at an immediate offset
+pub fn dataloadn<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.very_low)?;
+    let offset = read_u16(vm, vm.pc + 1);
+    let word = read_data_word(container(vm)?.data(), offset);
+    vm.stack.push_unchecked(word);
+    vm.pc += 3;
+    Ok(())
+}
+
+// DATASIZE: pushes the size of the data section
+pub fn datasize<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.base)?;
+    let size = container(vm)?.data().len();
+    vm.stack.push_unchecked(U256::from(size));
+    vm.pc += 1;
+    Ok(())
+}
+
+// DATACOPY: copies part of the data section to memory, padding with zeros
+pub fn datacopy<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    // read the arguments in place, nothing changes until the gas is paid
+    let dest_offset = vm.stack.nth(0);
+    let src_offset = vm.stack.nth(1);
+    let size = vm.stack.nth(2);
+
+    let expansion_cost = vm.memory_expansion_cost(dest_offset, size)?;
+    let copy_gas = word_gas(vm.gas_schedule.copy_word, size)?;
+    let static_gas = vm.gas_schedule.very_low;
+    vm.gas_dec(sum_gas(&[copy_gas, expansion_cost, static_gas])?)?;
+    vm.stack.pop_n(3);
+
+    // borrow the container field directly so memory can be written at the same time
+    let Some(container) = vm.container.as_ref() else {
+        return Err(EvmError::UnknownOpcode {
+            opcode: format!("0x{:02x}", vm.program[vm.pc]),
+        });
+    };
+    vm.memory.copy_padded(dest_offset, container.data(), src_offset, size);
+
+    vm.pc += 1;
+    Ok(())
+}
+
+// EXTCALL: message call without a gas argument, pushes 0 on success, 1 on revert and 2 on failure
+// Precompiles and accounts without code can be called. There are no frames to run another
+// account's code in yet, calling one is an `UnsupportedCall` error rather than a pretend success.
+// The value moves like in any call, and a revert or halt undoes it with the rest of the callee's
+// changes.
+pub fn extcall<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    // read the arguments in place, nothing changes until the gas is paid
+    let target = vm.stack.nth(0);
+    let input_offset = vm.stack.nth(1);
+    let input_size = vm.stack.nth(2);
+    let value = vm.stack.nth(3);
+
+    // EOF refuses to silently truncate addresses
+    if target >> 160 != U256::ZERO {
+        return Err(EvmError::AddressOutOfRange);
+    }
+    // a read-only frame may call, but not send value
+    if value != U256::ZERO {
+        vm.ensure_not_static()?;
+    }
+    let address = to_address(target);
+    if !vm.precompiles.contains(address) && !vm.host.code(address).is_empty() {
+        return Err(EvmError::UnsupportedCall { target: address });
+    }
+
+    let expansion_cost = vm.memory_expansion_cost(input_offset, input_size)?;
+    let access_cost = account_access_cost(vm, address);
+    let value_cost = if value != U256::ZERO { vm.gas_schedule.call_value } else { 0 };
+    vm.gas_dec(sum_gas(&[access_cost, value_cost, expansion_cost])?)?;
+    vm.stack.pop_n(4);
+    vm.host.warm_account(address);
+    let inputs = CallInputs {
+        caller: vm.address,
+        target: address,
+        value,
+        input: vm.memory.read(input_offset, input_size),
+        // EIP-150: the callee gets all but one 64th of what is left
+        gas_limit: vm.gas - vm.gas / 64,
+        is_static: vm.is_static,
+    };
+    vm.inspect(|inspector, vm| inspector.call(vm, &inputs));
+
+    vm.host.checkpoint();
+    let outcome = if !vm.host.transfer(inputs.caller, address, value) {
+        // a caller that can't pay the value fails without running the callee (EIP-7069)
+        CallOutcome {
+            status: CallStatus::Revert,
+            output: Vec::new(),
+            gas_used: 0,
+        }
+    } else if let Some(precompile) = vm.precompiles.get(address) {
+        let mut ctx = PrecompileContext {
+            caller: inputs.caller,
+            address,
+            value,
+            is_static: inputs.is_static,
+            host: &mut vm.host,
+        };
+        match precompile.run(&inputs.input, inputs.gas_limit, &mut ctx) {
+            Ok(output) if output.gas_used <= inputs.gas_limit => CallOutcome {
+                status: if output.reverted { CallStatus::Revert } else { CallStatus::Success },
+                output: output.bytes,
+                gas_used: output.gas_used,
+            },
+            // a halt, or a precompile charging more than it was given, burns all of it
+            _ => CallOutcome {
+                status: CallStatus::Halt,
+                output: Vec::new(),
+                gas_used: inputs.gas_limit,
+            },
+        }
+    } else {
+        // an account without code returns nothing
+        CallOutcome {
+            status: CallStatus::Success,
+            output: Vec::new(),
+            gas_used: 0,
+        }
+    };
+    match outcome.status {
+        CallStatus::Success => vm.host.checkpoint_commit(),
+        CallStatus::Revert | CallStatus::Halt => vm.host.checkpoint_revert(),
+    }
+    vm.gas_dec(outcome.gas_used)?;
+    vm.return_data.clone_from(&outcome.output);
+    vm.inspect(|inspector, vm| inspector.call_end(vm, &inputs, &outcome));
+
+    let status = match outcome.status {
+        CallStatus::Success => 0,
+        CallStatus::Revert => 1,
+        CallStatus::Halt => 2,
+    };
+    vm.stack.push_unchecked(U256::from(status));
+    vm.pc += 1;
+    Ok(())
+}
@@ -0,0 +1,355 @@
+// Static facts about every instruction, the single source of truth for names, stack effects,
+// immediates, base gas and the fork that introduced it.
+// The interpreter, the EOF validator, the disassembler and the TUI all read from this table.
+// An `OpcodeTable` is a copy of it that custom instructions can be added to, see `EVM::register_opcode`.
+
+use crate::{fork::Fork, opcodes::opcodes::RJUMPV};
+
+// the kind of code an instruction is defined in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeKind {
+    Any,
+    // instructions that observe the code layout or gas, undefined inside an EOF container
+    Legacy,
+    // only valid inside an EOF container
+    Eof,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpcodeInfo {
+    pub mnemonic: &'static str,
+    // items popped from and pushed to the stack
+    // DUPN, SWAPN, EXCHANGE, CALLF, JUMPF and RETF depend on their immediate or the target section,
+    // they are listed as 0 and checked by their handler and the EOF validator instead
+    pub inputs: u8,
+    pub outputs: u8,
+    // bytes following the opcode, RJUMPV only counts its first byte: the jump table size depends on it
+    pub immediate_size: u8,
+    // the static part of the cost on the latest fork, memory expansion, cold access and the like are
+    // added by the handler, which charges from the EVM's `GasSchedule`
+    pub base_gas: u64,
+    pub fork: Fork,
+    // halts the current frame or leaves the current code section
+    pub terminating: bool,
+    pub code_kind: CodeKind,
+    // false for instructions the interpreter has no handler for yet, they fail as unknown opcodes
+    // before their stack is checked
+    pub implemented: bool,
+}
+
+impl OpcodeInfo {
+    // custom instructions are described with `new` and `immediate` too
+    pub const fn new(mnemonic: &'static str, inputs: u8, outputs: u8, base_gas: u64, fork: Fork) -> Self {
+        Self {
+            mnemonic,
+            inputs,
+            outputs,
+            immediate_size: 0,
+            base_gas,
+            fork,
+            terminating: false,
+            code_kind: CodeKind::Any,
+            implemented: true,
+        }
+    }
+
+    pub const fn immediate(mut self, size: u8) -> Self {
+        self.immediate_size = size;
+        self
+    }
+
+    pub const fn terminating(mut self) -> Self {
+        self.terminating = true;
+        self
+    }
+
+    const fn unimplemented(mut self) -> Self {
+        self.implemented = false;
+        self
+    }
+
+    const fn legacy(mut self) -> Self {
+        self.code_kind = CodeKind::Legacy;
+        self
+    }
+
+    const fn eof(mut self) -> Self {
+        self.code_kind = CodeKind::Eof;
+        self
+    }
+
+    pub fn is_valid_in_legacy(&self) -> bool {
+        self.code_kind != CodeKind::Eof
+    }
+
+    pub fn is_valid_in_eof(&self) -> bool {
+        self.code_kind != CodeKind::Legacy
+    }
+}
+
+// looks up an opcode, None if it is not assigned
+pub fn opcode_info(opcode: u8) -> Option<&'static OpcodeInfo> {
+    OPCODES[opcode as usize].as_ref()
+}
+
+// number of immediate bytes following the opcode at `pc`, including a whole RJUMPV jump table
+// returns None for unassigned opcodes and if the code ends before the size of a jump table can be read
+pub fn immediate_size(code: &[u8], pc: usize) -> Option<usize> {
+    immediate_size_of(opcode_info(code[pc])?, code, pc)
+}
+
+fn immediate_size_of(info: &OpcodeInfo, code: &[u8], pc: usize) -> Option<usize> {
+    if code[pc] == RJUMPV {
+        let max_index = *code.get(pc + 1)? as usize;
+        return Some(1 + (max_index + 1) * 2);
+    }
+    Some(info.immediate_size as usize)
+}
+
+// the standard table plus whatever custom instructions were defined on top of it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpcodeTable {
+    infos: [Option<OpcodeInfo>; 256],
+}
+
+impl Default for OpcodeTable {
+    fn default() -> Self {
+        Self { infos: OPCODES }
+    }
+}
+
+impl OpcodeTable {
+    pub fn get(&self, opcode: u8) -> Option<&OpcodeInfo> {
+        self.infos[opcode as usize].as_ref()
+    }
+
+    // defines `opcode`, returning what was there before
+    pub fn insert(&mut self, opcode: u8, info: OpcodeInfo) -> Option<OpcodeInfo> {
+        self.infos[opcode as usize].replace(info)
+    }
+
+    // same as `immediate_size`, custom instructions included
+    pub fn immediate_size(&self, code: &[u8], pc: usize) -> Option<usize> {
+        immediate_size_of(self.get(code[pc])?, code, pc)
+    }
+
+    // same as `get_supported_opcode_name`, custom instructions included
+    pub fn name(&self, opcode: u8) -> String {
+        match self.get(opcode) {
+            Some(info) => info.mnemonic.to_string(),
+            None => format!("UNKNOWN(0x{:02x})", opcode),
+        }
+    }
+}
+
+const PUSH_NAMES: [&str; 33] = [
+    "PUSH0", "PUSH1", "PUSH2", "PUSH3", "PUSH4", "PUSH5", "PUSH6", "PUSH7", "PUSH8", "PUSH9",
+    "PUSH10", "PUSH11", "PUSH12", "PUSH13", "PUSH14", "PUSH15", "PUSH16", "PUSH17", "PUSH18",
+    "PUSH19", "PUSH20", "PUSH21", "PUSH22", "PUSH23", "PUSH24", "PUSH25", "PUSH26", "PUSH27",
+    "PUSH28", "PUSH29", "PUSH30", "PUSH31", "PUSH32",
+];
+const DUP_NAMES: [&str; 16] = [
+    "DUP1", "DUP2", "DUP3", "DUP4", "DUP5", "DUP6", "DUP7", "DUP8", "DUP9", "DUP10", "DUP11",
+    "DUP12", "DUP13", "DUP14", "DUP15", "DUP16",
+];
+const SWAP_NAMES: [&str; 16] = [
+    "SWAP1", "SWAP2", "SWAP3", "SWAP4", "SWAP5", "SWAP6", "SWAP7", "SWAP8", "SWAP9", "SWAP10",
+    "SWAP11", "SWAP12", "SWAP13", "SWAP14", "SWAP15", "SWAP16",
+];
+const LOG_NAMES: [&str; 5] = ["LOG0", "LOG1", "LOG2", "LOG3", "LOG4"];
+
+// EOFCREATE, RETURNCODE, RETURNDATALOAD, EXTDELEGATECALL and EXTSTATICCALL are not supported yet
+// the entries marked `unimplemented` are known by name, but have no handler in the interpreter
+pub static OPCODES: [Option<OpcodeInfo>; 256] = build_table();
+
+const fn build_table() -> [Option<OpcodeInfo>; 256] {
+    use Fork::*;
+    type I = OpcodeInfo;
+
+    let mut t: [Option<OpcodeInfo>; 256] = [None; 256];
+
+    // stop and arithmetic
+    t[0x00] = Some(I::new("STOP", 0, 0, 0, Frontier).terminating());
+    t[0x01] = Some(I::new("ADD", 2, 1, 3, Frontier));
+    t[0x02] = Some(I::new("MUL", 2, 1, 5, Frontier));
+    t[0x03] = Some(I::new("SUB", 2, 1, 3, Frontier));
+    t[0x04] = Some(I::new("DIV", 2, 1, 5, Frontier));
+    t[0x05] = Some(I::new("SDIV", 2, 1, 5, Frontier));
+    t[0x06] = Some(I::new("MOD", 2, 1, 5, Frontier));
+    t[0x07] = Some(I::new("SMOD", 2, 1, 5, Frontier));
+    t[0x08] = Some(I::new("ADDMOD", 3, 1, 8, Frontier));
+    t[0x09] = Some(I::new("MULMOD", 3, 1, 8, Frontier));
+    t[0x0A] = Some(I::new("EXP", 2, 1, 10, Frontier));
+    t[0x0B] = Some(I::new("SIGNEXTEND", 2, 1, 5, Frontier));
+
+    // comparison and bitwise logic
+    t[0x10] = Some(I::new("LT", 2, 1, 3, Frontier));
+    t[0x11] = Some(I::new("GT", 2, 1, 3, Frontier));
+    t[0x12] = Some(I::new("SLT", 2, 1, 3, Frontier));
+    t[0x13] = Some(I::new("SGT", 2, 1, 3, Frontier));
+    t[0x14] = Some(I::new("EQ", 2, 1, 3, Frontier));
+    t[0x15] = Some(I::new("ISZERO", 1, 1, 3, Frontier));
+    t[0x16] = Some(I::new("AND", 2, 1, 3, Frontier));
+    t[0x17] = Some(I::new("OR", 2, 1, 3, Frontier));
+    t[0x18] = Some(I::new("XOR", 2, 1, 3, Frontier));
+    t[0x19] = Some(I::new("NOT", 1, 1, 3, Frontier));
+    t[0x1A] = Some(I::new("BYTE", 2, 1, 3, Frontier));
+    t[0x1B] = Some(I::new("SHL", 2, 1, 3, Constantinople));
+    t[0x1C] = Some(I::new("SHR", 2, 1, 3, Constantinople));
+    t[0x1D] = Some(I::new("SAR", 2, 1, 3, Constantinople));
+
+    t[0x20] = Some(I::new("SHA3", 2, 1, 30, Frontier));
+
+    // environment
+    t[0x30] = Some(I::new("ADDRESS", 0, 1, 2, Frontier));
+    t[0x31] = Some(I::new("BALANCE", 1, 1, 100, Frontier));
+    t[0x32] = Some(I::new("ORIGIN", 0, 1, 2, Frontier));
+    t[0x33] = Some(I::new("CALLER", 0, 1, 2, Frontier));
+    t[0x34] = Some(I::new("CALLVALUE", 0, 1, 2, Frontier));
+    t[0x35] = Some(I::new("CALLDATALOAD", 1, 1, 3, Frontier));
+    t[0x36] = Some(I::new("CALLDATASIZE", 0, 1, 2, Frontier));
+    t[0x37] = Some(I::new("CALLDATACOPY", 3, 0, 3, Frontier));
+    t[0x38] = Some(I::new("CODESIZE", 0, 1, 2, Frontier).legacy());
+    t[0x39] = Some(I::new("CODECOPY", 3, 0, 3, Frontier).legacy());
+    t[0x3A] = Some(I::new("GASPRICE", 0, 1, 2, Frontier));
+    t[0x3B] = Some(I::new("EXTCODESIZE", 1, 1, 100, Frontier).legacy());
+    t[0x3C] = Some(I::new("EXTCODECOPY", 4, 0, 100, Frontier).legacy());
+    t[0x3D] = Some(I::new("RETURNDATASIZE", 0, 1, 2, Byzantium));
+    t[0x3E] = Some(I::new("RETURNDATACOPY", 3, 0, 3, Byzantium));
+    t[0x3F] = Some(I::new("EXTCODEHASH", 1, 1, 100, Constantinople).legacy());
+
+    // block information
+    t[0x40] = Some(I::new("BLOCKHASH", 1, 1, 20, Frontier));
+    t[0x41] = Some(I::new("COINBASE", 0, 1, 2, Frontier));
+    t[0x42] = Some(I::new("TIMESTAMP", 0, 1, 2, Frontier));
+    t[0x43] = Some(I::new("NUMBER", 0, 1, 2, Frontier));
+    // the table has no fork, the name covers both meanings: DIFFICULTY until Paris (EIP-4399)
+    t[0x44] = Some(I::new("DIFFICULTY/PREVRANDAO", 0, 1, 2, Frontier));
+    t[0x45] = Some(I::new("GASLIMIT", 0, 1, 2, Frontier));
+    t[0x46] = Some(I::new("CHAINID", 0, 1, 2, Istanbul));
+    t[0x47] = Some(I::new("SELFBALANCE", 0, 1, 5, Istanbul));
+    t[0x48] = Some(I::new("BASEFEE", 0, 1, 2, London));
+    t[0x49] = Some(I::new("BLOBHASH", 1, 1, 3, Cancun).unimplemented());
+    t[0x4A] = Some(I::new("BLOBBASEFEE", 0, 1, 2, Cancun).unimplemented());
+
+    // stack, memory, storage and flow
+    t[0x50] = Some(I::new("POP", 1, 0, 2, Frontier));
+    t[0x51] = Some(I::new("MLOAD", 1, 1, 3, Frontier));
+    t[0x52] = Some(I::new("MSTORE", 2, 0, 3, Frontier));
+    t[0x53] = Some(I::new("MSTORE8", 2, 0, 3, Frontier));
+    t[0x54] = Some(I::new("SLOAD", 1, 1, 100, Frontier));
+    t[0x55] = Some(I::new("SSTORE", 2, 0, 100, Frontier));
+    t[0x56] = Some(I::new("JUMP", 1, 0, 8, Frontier).legacy());
+    t[0x57] = Some(I::new("JUMPI", 2, 0, 10, Frontier).legacy());
+    t[0x58] = Some(I::new("PC", 0, 1, 2, Frontier).legacy());
+    t[0x59] = Some(I::new("MSIZE", 0, 1, 2, Frontier).unimplemented());
+    t[0x5A] = Some(I::new("GAS", 0, 1, 2, Frontier).legacy().unimplemented());
+    // a no-op in EOF, where jumps are static
+    t[0x5B] = Some(I::new("JUMPDEST", 0, 0, 1, Frontier));
+    t[0x5C] = Some(I::new("TLOAD", 1, 1, 100, Cancun));
+    t[0x5D] = Some(I::new("TSTORE", 2, 0, 100, Cancun));
+    t[0x5E] = Some(I::new("MCOPY", 3, 0, 3, Cancun).unimplemented());
+    t[0x5F] = Some(I::new("PUSH0", 0, 1, 2, Shanghai).unimplemented());
+
+    let mut n = 1;
+    while n <= 32 {
+        t[0x5F + n] = Some(I::new(PUSH_NAMES[n], 0, 1, 3, Frontier).immediate(n as u8));
+        n += 1;
+    }
+    let mut n = 0;
+    while n < 16 {
+        t[0x80 + n] = Some(I::new(DUP_NAMES[n], n as u8 + 1, n as u8 + 2, 3, Frontier));
+        t[0x90 + n] = Some(I::new(SWAP_NAMES[n], n as u8 + 2, n as u8 + 2, 3, Frontier));
+        n += 1;
+    }
+    let mut n = 0;
+    while n <= 4 {
+        t[0xA0 + n] = Some(I::new(LOG_NAMES[n], n as u8 + 2, 0, 375 * (n as u64 + 1), Frontier));
+        n += 1;
+    }
+
+    // EOF data section
+    t[0xD0] = Some(I::new("DATALOAD", 1, 1, 4, Osaka).eof());
+    t[0xD1] = Some(I::new("DATALOADN", 0, 1, 3, Osaka).eof().immediate(2));
+    t[0xD2] = Some(I::new("DATASIZE", 0, 1, 2, Osaka).eof());
+    t[0xD3] = Some(I::new("DATACOPY", 3, 0, 3, Osaka).eof());
+
+    // EOF control flow and stack
+    t[0xE0] = Some(I::new("RJUMP", 0, 0, 2, Osaka).eof().immediate(2));
+    t[0xE1] = Some(I::new("RJUMPI", 1, 0, 4, Osaka).eof().immediate(2));
+    t[0xE2] = Some(I::new("RJUMPV", 1, 0, 4, Osaka).eof().immediate(1));
+    t[0xE3] = Some(I::new("CALLF", 0, 0, 5, Osaka).eof().immediate(2));
+    t[0xE4] = Some(I::new("RETF", 0, 0, 3, Osaka).eof().terminating());
+    t[0xE5] = Some(I::new("JUMPF", 0, 0, 5, Osaka).eof().immediate(2).terminating());
+    t[0xE6] = Some(I::new("DUPN", 0, 0, 3, Osaka).eof().immediate(1));
+    t[0xE7] = Some(I::new("SWAPN", 0, 0, 3, Osaka).eof().immediate(1));
+    t[0xE8] = Some(I::new("EXCHANGE", 0, 0, 3, Osaka).eof().immediate(1));
+
+    // system
+    t[0xF0] = Some(I::new("CREATE", 3, 1, 32000, Frontier).legacy().unimplemented());
+    t[0xF1] = Some(I::new("CALL", 7, 1, 100, Frontier).legacy().unimplemented());
+    t[0xF2] = Some(I::new("CALLCODE", 7, 1, 100, Frontier).legacy().unimplemented());
+    t[0xF3] = Some(I::new("RETURN", 2, 0, 0, Frontier).terminating());
+    t[0xF4] = Some(I::new("DELEGATECALL", 6, 1, 100, Homestead).legacy().unimplemented());
+    t[0xF5] = Some(I::new("CREATE2", 4, 1, 32000, Constantinople).legacy().unimplemented());
+    t[0xF8] = Some(I::new("EXTCALL", 4, 1, 100, Osaka).eof());
+    t[0xFA] = Some(I::new("STATICCALL", 6, 1, 100, Byzantium).legacy().unimplemented());
+    t[0xFD] = Some(I::new("REVERT", 2, 0, 0, Byzantium).terminating());
+    t[0xFE] = Some(I::new("INVALID", 0, 0, 0, Frontier).terminating());
+    t[0xFF] = Some(I::new("SELFDESTRUCT", 1, 0, 5000, Frontier).legacy().terminating());
+
+    t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        let push2 = opcode_info(0x61).unwrap();
+        assert_eq!(push2.mnemonic, "PUSH2");
+        assert_eq!(push2.immediate_size, 2);
+        assert_eq!((push2.inputs, push2.outputs), (0, 1));
+
+        let revert = opcode_info(0xFD).unwrap();
+        assert!(revert.terminating);
+        assert_eq!(revert.fork, Fork::Byzantium);
+
+        assert_eq!(opcode_info(0xA4).unwrap().base_gas, 1875);
+        // named for before and after the merge alike
+        assert_eq!(opcode_info(0x44).unwrap().mnemonic, "DIFFICULTY/PREVRANDAO");
+        assert_eq!(opcode_info(0x9F).unwrap().inputs, 17);
+        assert!(opcode_info(0x0C).is_none());
+    }
+
+    #[test]
+    fn test_code_kind() {
+        assert!(!opcode_info(0x56).unwrap().is_valid_in_eof());
+        assert!(!opcode_info(0xE0).unwrap().is_valid_in_legacy());
+        assert!(opcode_info(0x01).unwrap().is_valid_in_eof());
+        assert!(!opcode_info(0xF1).unwrap().implemented);
+        assert!(opcode_info(0xF8).unwrap().implemented);
+    }
+
+    #[test]
+    fn test_immediate_size_of_jump_table() {
+        // RJUMPV with 3 entries: count byte + 3 * i16
+        assert_eq!(immediate_size(&[0xE2, 0x02, 0, 0, 0, 0, 0, 0], 0), Some(7));
+        assert_eq!(immediate_size(&[0xE2], 0), None);
+        assert_eq!(immediate_size(&[0x0C], 0), None);
+    }
+
+    #[test]
+    fn test_table_starts_as_the_standard_set() {
+        let mut table = OpcodeTable::default();
+        assert_eq!(table.get(0x61), opcode_info(0x61));
+        assert_eq!(table.name(0x0C), "UNKNOWN(0x0c)");
+
+        let info = OpcodeInfo::new("MYPUSH", 0, 1, 3, Fork::LATEST).immediate(1);
+        assert_eq!(table.insert(0x0C, info), None);
+        assert_eq!(table.name(0x0C), "MYPUSH");
+        assert_eq!(table.immediate_size(&[0x0C, 0x01], 0), Some(1));
+        assert!(opcode_info(0x0C).is_none());
+    }
+}
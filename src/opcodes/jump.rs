@@ -1,12 +1,15 @@
 use alloy_primitives::U256;
 
-use crate::evm::{EVM, EvmError};
+use crate::{
+    evm::{EVM, EvmError},
+    host::Host,
+};
 
 const OP_JUMPDEST: u8 = 0x5B;
 
-pub fn jump(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(8)?;
-    let dest_raw = vm.stack.pop()?;
+pub fn jump<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.mid)?;
+    let dest_raw = vm.stack.pop1();
     let dest = dest_raw.saturating_to::<usize>();
 
     if dest >= vm.program.len() {
@@ -27,12 +30,12 @@ pub fn jump(vm: &mut EVM) -> Result<(), EvmError> {
 }
 
 // this is a conditional jump, known as jump-if
-pub fn jumpi(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(10)?;
+pub fn jumpi<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.high)?;
 
-    let dest_raw = vm.stack.pop()?;
+    let dest_raw = vm.stack.pop1();
     let dest = dest_raw.saturating_to::<usize>();
-    let condition = vm.stack.pop()?;
+    let condition = vm.stack.pop1();
     // if condition  is not zero, that means true => jump to dest
     if condition != U256::ZERO {
         // in jumping to dest, we must check that it is safe, e.g dest is not greater than vm.program
@@ -60,9 +63,9 @@ pub fn jumpi(vm: &mut EVM) -> Result<(), EvmError> {
 // this opcode bookmarks the last instruction execution line so the EVM can find its way back
 // suppose that there's a function A which calls another function B within it, the line where B call is initiated is saved on the stack
 // sortof like saving its frame of reference, so after B is executed , it has a record of where it was and simple Jumps back
-pub fn pc(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(2)?;
-    vm.stack.push(U256::from(vm.pc))?;
+pub fn pc<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.base)?;
+    vm.stack.push_unchecked(U256::from(vm.pc));
     vm.pc += 1;
     Ok(())
 }
@@ -72,8 +75,8 @@ pub fn pc(vm: &mut EVM) -> Result<(), EvmError> {
 // it exists solely for static analysis
 // it's the opcode that clearly indicates which part of an instruction the EVM should jump to
 // JUMP and JUMPI are dependent on it
-pub fn jump_dest(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(1)?;
+pub fn jump_dest<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.jumpdest)?;
     vm.pc += 1;
     Ok(())
 }
\ No newline at end of file
@@ -1,29 +1,39 @@
 use alloy_primitives::U256;
 
-use crate::evm::{EVM, EvmError, Log};
+use crate::{
+    evm::{EVM, EvmError, Log},
+    host::Host,
+    memory::sum_gas,
+};
 
-pub fn log(vm: &mut EVM, n: usize) -> Result<(), EvmError> {
-    let offset_raw = vm.stack.pop()?;
-    let size_raw = vm.stack.pop()?;
+pub fn log<H: Host>(vm: &mut EVM<H>, n: usize) -> Result<(), EvmError> {
+    vm.ensure_not_static()?;
+    // read the arguments in place, nothing changes until the gas is paid
+    let offset = vm.stack.nth(0);
+    let size = vm.stack.nth(1);
 
-    let offset = offset_raw.saturating_to::<usize>();
-    let size = size_raw.saturating_to::<usize>();
-    // pop n-topics from stack and store in topics Vec
+    // read n-topics from stack and store in topics Vec
     let mut topics: Vec<U256> = Vec::new();
-    for _ in 0..n {
-        topics.push(vm.stack.pop()?);
+    for i in 0..n {
+        topics.push(vm.stack.nth(2 + i));
     }
-    let static_gas = 375;
-    let topic_gas = static_gas * n as u64;
-    let data_gas = 8 * (size as u64);
+    // the expansion fails for sizes no one can pay for, so size fits in a u64 after it
+    let expansion_cost = vm.memory_expansion_cost(offset, size)?;
+    let static_gas = vm.gas_schedule.log;
+    let topic_gas = vm.gas_schedule.log_topic * n as u64;
+    let data_gas = vm
+        .gas_schedule
+        .log_data_byte
+        .checked_mul(size.to::<u64>())
+        .ok_or(EvmError::OutOfGas)?;
+    vm.gas_dec(sum_gas(&[static_gas, topic_gas, data_gas, expansion_cost])?)?;
+    vm.stack.pop_n(2 + n);
 
-    let expansion_cost = vm.memory.ensure_capacity(offset, size);
-    vm.gas_dec(static_gas + topic_gas + data_gas + expansion_cost)?;
-
-    let data = vm.memory.access(offset, size)?.to_vec();
+    let data = vm.memory.read(offset, size);
     // create log with data and topics
-    let log_entry = Log::new(data, topics);
-    vm.logs.push(log_entry);
+    let log_entry = Log::new(vm.address, data, topics);
+    vm.inspect(|inspector, vm| inspector.log(vm, &log_entry));
+    vm.host.log(log_entry);
     vm.pc += 1;
     Ok(())
 }
@@ -1,48 +1,51 @@
-use crate::evm::{EVM, EvmError};
+use crate::{
+    evm::{EVM, EvmError},
+    host::Host,
+};
 
 
-pub fn and(vm : &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(3)?;
+pub fn and<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.very_low)?;
 
-    let a = vm.stack.pop()?;
-    let b = vm.stack.pop()?;
+    let a = vm.stack.pop1();
+    let b = vm.stack.top_mut();
 
-    vm.stack.push(a & b)?;
+    *b = a & *b;
     vm.pc += 1;
 
     Ok(())
 }
 
-pub fn or(vm : &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(3)?;
+pub fn or<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.very_low)?;
 
-    let a = vm.stack.pop()?;
-    let b = vm.stack.pop()?;
+    let a = vm.stack.pop1();
+    let b = vm.stack.top_mut();
 
-    vm.stack.push(a | b)?;
+    *b = a | *b;
     vm.pc += 1;
 
     Ok(())
 }
 
-pub fn xor(vm : &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(3)?;
+pub fn xor<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.very_low)?;
 
-    let a = vm.stack.pop()?;
-    let b = vm.stack.pop()?;
+    let a = vm.stack.pop1();
+    let b = vm.stack.top_mut();
 
-    vm.stack.push(a ^ b)?;
+    *b = a ^ *b;
     vm.pc += 1;
 
     Ok(())
 }
 
-pub fn not(vm : &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(3)?;
+pub fn not<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.very_low)?;
 
-    let val = vm.stack.pop()?;
+    let val = vm.stack.top_mut();
 
-    vm.stack.push(!val)?;
+    *val = !*val;
     vm.pc += 1;
 
     Ok(())
@@ -1,66 +1,64 @@
 use alloy_primitives::{I256, U256};
 
-use crate::evm::{EVM, EvmError};
-
-pub fn add(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(3)?;
-
-    // pop the 2 values
-    let a = vm.stack.pop()?;
-    let b = vm.stack.pop()?;
-    // add them
-    vm.stack.push(a.wrapping_add(b))?;
+use crate::{
+    evm::{EVM, EvmError},
+    host::Host,
+};
+
+pub fn add<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.very_low)?;
+
+    // pop a and overwrite b with the sum in place
+    let a = vm.stack.pop1();
+    let b = vm.stack.top_mut();
+    *b = a.wrapping_add(*b);
     // increase pc
     vm.pc += 1;
     Ok(())
 }
 
-pub fn mul(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(5)?;
+pub fn mul<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.low)?;
 
-    // pop the 2 values
-    let a = vm.stack.pop()?;
-    let b = vm.stack.pop()?;
-    // multiply and push to the stack
-    vm.stack.push(a.wrapping_mul(b))?;
+    // pop a and overwrite b with the product in place
+    let a = vm.stack.pop1();
+    let b = vm.stack.top_mut();
+    *b = a.wrapping_mul(*b);
     // increase pc
     vm.pc += 1;
     Ok(())
 }
 
-pub fn sub(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(3)?;
+pub fn sub<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.very_low)?;
 
-    // pop the 2 values
-    let a = vm.stack.pop()?;
-    let b = vm.stack.pop()?;
-    // add them
-    vm.stack.push(a.wrapping_sub(b))?;
+    // pop a and overwrite b with the difference in place
+    let a = vm.stack.pop1();
+    let b = vm.stack.top_mut();
+    *b = a.wrapping_sub(*b);
     // increase pc
     vm.pc += 1;
     Ok(())
 }
 
-pub fn div(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(5)?;
+pub fn div<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.low)?;
 
     // pop the 2 values
-    let a = vm.stack.pop()?;
-    let b = vm.stack.pop()?;
+    let (a, b) = vm.stack.pop2();
     // divide and push to the stack
     let result = if b == U256::ZERO { U256::ZERO } else { a / b };
-    vm.stack.push(result)?;
+    vm.stack.push_unchecked(result);
     // increase pc
     vm.pc += 1;
     Ok(())
 }
 
-pub fn sdiv(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(5)?;
+pub fn sdiv<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.low)?;
 
     // pop the 2 values
-    let a_raw = vm.stack.pop()?;
-    let b_raw = vm.stack.pop()?;
+    let (a_raw, b_raw) = vm.stack.pop2();
 
     let a = I256::from_raw(a_raw);
     let b = I256::from_raw(b_raw);
@@ -74,108 +72,97 @@ pub fn sdiv(vm: &mut EVM) -> Result<(), EvmError> {
         a / b
     };
 
-    vm.stack.push(result.into_raw())?;
+    vm.stack.push_unchecked(result.into_raw());
     // // increase pc
     vm.pc += 1;
     Ok(())
 }
 
-pub fn vm_mod(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(5)?;
+pub fn vm_mod<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.low)?;
     // pop the 2 values
-    let a = vm.stack.pop()?;
-    let b = vm.stack.pop()?;
+    let (a, b) = vm.stack.pop2();
     let result = if b == U256::ZERO { U256::ZERO } else { a % b };
     // push result to Evm stack
-    vm.stack.push(result)?;
+    vm.stack.push_unchecked(result);
     // increase pc
     vm.pc += 1;
     Ok(())
 }
 
-pub fn smod(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(5)?;
+pub fn smod<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.low)?;
     // pop the 2 values
-    let a_raw = vm.stack.pop()?;
-    let b_raw = vm.stack.pop()?;
+    let (a_raw, b_raw) = vm.stack.pop2();
     //  to signed
     let a = I256::from_raw(a_raw);
     let b = I256::from_raw(b_raw);
 
-    if b == I256::ZERO {
-        vm.stack.push(U256::ZERO)?;
-        return Ok(());
-    }
-
-    let result = a % b;
-    vm.stack.push(result.into_raw())?;
+    let result = if b == I256::ZERO { I256::ZERO } else { a % b };
+    vm.stack.push_unchecked(result.into_raw());
     vm.pc += 1;
     Ok(())
 }
 
-pub fn add_mod(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(8)?;
+pub fn add_mod<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.mid)?;
     // pop the 2 values
-    let a = vm.stack.pop()?;
-    let b = vm.stack.pop()?;
-    let n = vm.stack.pop()?;
+    let (a, b, n) = vm.stack.pop3();
     let result = if n == U256::ZERO {
         U256::ZERO
     } else {
         a.add_mod(b, n)
     };
 
-    vm.stack.push(result)?;
+    vm.stack.push_unchecked(result);
     vm.pc += 1;
     Ok(())
 }
 
-pub fn mul_mod(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(8)?;
+pub fn mul_mod<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.mid)?;
     // pop the 2 values
-    let a = vm.stack.pop()?;
-    let b = vm.stack.pop()?;
-    let n = vm.stack.pop()?;
+    let (a, b, n) = vm.stack.pop3();
     let result = if n == U256::ZERO {
         U256::ZERO
     } else {
         a.mul_mod(b, n)
     };
 
-    vm.stack.push(result)?;
+    vm.stack.push_unchecked(result);
     vm.pc += 1;
     Ok(())
 }
 
 pub fn size_in_bytes(num: &U256) -> u64 {
     let bits = num.bit_len() as u64;
-    (bits + 7) / 8
+    bits.div_ceil(8)
 }
 
-pub fn exp(vm: &mut EVM) -> Result<(), EvmError> {
-    // pop the 2 values
-    let base = vm.stack.pop()?;
-    let exponent = vm.stack.pop()?;
+pub fn exp<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    // read the 2 values, they stay on the stack until the gas is paid
+    let base = vm.stack.nth(0);
+    let exponent = vm.stack.nth(1);
 
     // calculate gas
     let exponent_byte_len = size_in_bytes(&exponent);
-    let gas_cost = 10 + (50 * exponent_byte_len);
+    let gas_cost = vm.gas_schedule.exp + (vm.gas_schedule.exp_byte * exponent_byte_len);
     vm.gas_dec(gas_cost)?;
+    vm.stack.pop_n(2);
 
     let result = base.pow(exponent);
 
-    vm.stack.push(result)?;
+    vm.stack.push_unchecked(result);
 
     vm.pc += 1;
 
     Ok(())
 }
 
-pub fn signextend(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(5)?;
+pub fn signextend<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.low)?;
     // pop the 2 values
-    let size_marker = vm.stack.pop()?;
-    let value: alloy_primitives::Uint<256, 4> = vm.stack.pop()?;
+    let (size_marker, value) = vm.stack.pop2();
     let result;
     if size_marker < U256::from(31) {
         let byte_index = size_marker.to::<usize>();
@@ -197,13 +184,12 @@ pub fn signextend(vm: &mut EVM) -> Result<(), EvmError> {
         // no extension is needed
         result = value;
     }
-    vm.stack.push(result)?;
+    vm.stack.push_unchecked(result);
     vm.pc += 1;
     Ok(())
 }
 
 #[cfg(test)]
-
 mod tests {
     use super::*;
 
@@ -1,48 +1,64 @@
 use alloy_primitives::U256;
 
-use crate::evm::{EVM, EvmError};
+use crate::{
+    evm::{EVM, EvmError},
+    host::Host,
+    memory::sum_gas,
+};
+
+const WORD_SIZE: U256 = U256::from_limbs([32, 0, 0, 0]);
 
 // loads one word (32 bytes) from memory onto the stack
-pub fn mload(vm: &mut EVM) -> Result<(), EvmError> {
-    let offset_raw = vm.stack.pop()?;
-    let offset = offset_raw.saturating_to::<usize>();
+pub fn mload<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    let offset = vm.stack.nth(0);
 
-    let static_gas = 3;
-    let (word, _expansion_cost) = vm.memory.load(offset);
-    vm.gas_dec(static_gas + _expansion_cost)?;
+    let static_gas = vm.gas_schedule.very_low;
+    let expansion_cost = vm.memory_expansion_cost(offset, WORD_SIZE)?;
+    vm.gas_dec(sum_gas(&[static_gas, expansion_cost])?)?;
+    vm.stack.pop_n(1);
 
-    vm.stack.push(U256::from_be_bytes(word))?;
+    // the expansion has been paid for, so the offset fits in memory
+    let word = vm.memory.load(offset.to::<usize>());
+    vm.stack.push_unchecked(U256::from_be_bytes(word));
     vm.pc += 1;
     Ok(())
 }
 
 // stores one word (32 bytes) in memory
-pub fn mstore(vm: &mut EVM) -> Result<(), EvmError> {
-    let offset_raw = vm.stack.pop()?;
-    let word = vm.stack.pop()?;
+pub fn mstore<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    // read the arguments in place, nothing changes until the gas is paid
+    let offset = vm.stack.nth(0);
+    let word = vm.stack.nth(1);
 
-    let offset = offset_raw.saturating_to::<usize>();
-    let word_in_bytes: [u8; 32] = word.to_be_bytes();
-    let expansion_cost = vm.memory.store(offset, &word_in_bytes);
+    let static_gas = vm.gas_schedule.very_low;
+    let expansion_cost = vm.memory_expansion_cost(offset, WORD_SIZE)?;
+    vm.gas_dec(sum_gas(&[expansion_cost, static_gas])?)?;
+    vm.stack.pop_n(2);
 
-    let static_gas = 3;
-    vm.gas_dec(expansion_cost + static_gas)?;
+    let word_in_bytes: [u8; 32] = word.to_be_bytes();
+    let offset = offset.to::<usize>();
+    vm.memory.resize(offset, 32);
+    vm.memory.store(offset, &word_in_bytes);
     vm.pc += 1;
     Ok(())
 }
 
 // stores 1byte of a Word in memory
-pub fn mstore8(vm: &mut EVM) -> Result<(), EvmError> {
-    let offset_raw = vm.stack.pop()?;
-    let word = vm.stack.pop()?;
+pub fn mstore8<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    // read the arguments in place, nothing changes until the gas is paid
+    let offset = vm.stack.nth(0);
+    let word = vm.stack.nth(1);
+
+    let static_gas = vm.gas_schedule.very_low;
+    let expansion_cost = vm.memory_expansion_cost(offset, U256::ONE)?;
+    vm.gas_dec(sum_gas(&[expansion_cost, static_gas])?)?;
+    vm.stack.pop_n(2);
 
-    let offset = offset_raw.saturating_to::<usize>();
     let word_in_bytes: [u8; 32] = word.to_be_bytes();
     let single_byte_of_word = word_in_bytes[31];
-    let expansion_cost = vm.memory.store(offset, &[single_byte_of_word]);
-
-    let static_gas = 3;
-    vm.gas_dec(expansion_cost + static_gas)?;
+    let offset = offset.to::<usize>();
+    vm.memory.resize(offset, 1);
+    vm.memory.store(offset, &[single_byte_of_word]);
     vm.pc += 1;
     Ok(())
 }
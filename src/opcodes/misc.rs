@@ -1,18 +1,27 @@
 use alloy_primitives::keccak256;
 
-use crate::evm::{EVM, EvmError};
+use crate::{
+    evm::{EVM, EvmError},
+    host::Host,
+    memory::{sum_gas, word_gas},
+};
 
-pub fn sha3(vm: &mut EVM) -> Result<(), EvmError> {
-    let offset = vm.stack.pop()?;
-    let size = vm.stack.pop()?;
-    let size_u64 = size.saturating_to::<usize>();
-    let min_word_size = (size_u64 + 31) / 32;
-    let dynamic_gas = 6 * min_word_size;
-    let static_gas = 30;
-    vm.gas_dec((dynamic_gas + static_gas) as u64)?;
+// hashes memory[offset..offset + size], the region is zero-extended like any other memory read
+// a zero size hashes the empty string and never touches memory, whatever the offset
+pub fn sha3<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    // read the arguments in place, nothing changes until the gas is paid
+    let offset = vm.stack.nth(0);
+    let size = vm.stack.nth(1);
 
-    let value = vm.memory.access(offset.to::<usize>(), size.to::<usize>())?;
-    vm.stack.push(keccak256(value).into())?;
+    // fails with OutOfGas for regions past usize, so the conversions in `read` are safe afterwards
+    let expansion_cost = vm.memory_expansion_cost(offset, size)?;
+    let hash_gas = word_gas(vm.gas_schedule.keccak256_word, size)?;
+    let static_gas = vm.gas_schedule.keccak256;
+    vm.gas_dec(sum_gas(&[hash_gas, expansion_cost, static_gas])?)?;
+    vm.stack.pop_n(2);
+
+    let value = vm.memory.read(offset, size);
+    vm.stack.push_unchecked(keccak256(value).into());
 
     vm.pc += 1;
     Ok(())
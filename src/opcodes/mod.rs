@@ -1,8 +1,11 @@
 pub mod bit;
+pub mod block;
 pub mod comparisons;
 pub mod contract;
 pub mod dup;
 pub mod environment;
+pub mod eof;
+pub mod info;
 pub mod jump;
 pub mod log;
 pub mod logic;
@@ -15,4 +18,5 @@ pub mod stop;
 pub mod storage;
 pub mod swap;
 pub mod transient;
+#[allow(clippy::module_inception)]
 pub mod opcodes;
\ No newline at end of file
@@ -51,15 +51,15 @@ pub const RETURNDATACOPY: u8 = 0x3E; // Copy last call's return data
 pub const EXTCODEHASH:    u8 = 0x3F; // Hash of external contract code
 
 // block information
-// pub const BLOCKHASH:   u8 = 0x40; // Hash of recent blocks
-// pub const COINBASE:    u8 = 0x41; // block.coinbase (Validator address)
-// pub const TIMESTAMP:   u8 = 0x42; // block.timestamp
-// pub const NUMBER:      u8 = 0x43; // block.number
-// pub const DIFFICULTY:  u8 = 0x44; // block.difficulty (Now PREVRANDAO)
-// pub const GASLIMIT:    u8 = 0x45; // block.gaslimit
-// pub const CHAINID:     u8 = 0x46; // chainid (e.g., 1 for Mainnet)
-// pub const SELFBALANCE: u8 = 0x47; // Cheaper version of BALANCE(address(this))
-// pub const BASEFEE:     u8 = 0x48; // EIP-1559 Base Fee
+pub const BLOCKHASH:   u8 = 0x40; // Hash of recent blocks
+pub const COINBASE:    u8 = 0x41; // block.coinbase (Validator address)
+pub const TIMESTAMP:   u8 = 0x42; // block.timestamp
+pub const NUMBER:      u8 = 0x43; // block.number
+pub const PREVRANDAO:  u8 = 0x44; // block.prevrandao (was DIFFICULTY before the merge)
+pub const GASLIMIT:    u8 = 0x45; // block.gaslimit
+pub const CHAINID:     u8 = 0x46; // chainid (e.g., 1 for Mainnet)
+pub const SELFBALANCE: u8 = 0x47; // Cheaper version of BALANCE(address(this))
+pub const BASEFEE:     u8 = 0x48; // EIP-1559 Base Fee
 
 
 pub const POP: u8 = 0x50;
@@ -98,4 +98,29 @@ pub const SWAP16: u8 = 0x9F;
 pub const LOG0:   u8 = 0xA0;
 pub const LOG4:   u8 = 0xA4;
 
-pub const REVERT:       u8 = 0xFD; 
\ No newline at end of file
+// EOF (EVM Object Format) - only valid inside an 0xEF00 container
+// data section access
+pub const DATALOAD:  u8 = 0xD0; // Load 32 bytes of the data section
+pub const DATALOADN: u8 = 0xD1; // Load 32 bytes of the data section at an immediate offset
+pub const DATASIZE:  u8 = 0xD2; // Size of the data section
+pub const DATACOPY:  u8 = 0xD3; // Copy part of the data section to memory
+
+// static relative jumps and functions
+pub const RJUMP:    u8 = 0xE0; // Relative jump
+pub const RJUMPI:   u8 = 0xE1; // Conditional relative jump
+pub const RJUMPV:   u8 = 0xE2; // Relative jump through a jump table
+pub const CALLF:    u8 = 0xE3; // Call a code section
+pub const RETF:     u8 = 0xE4; // Return from a code section
+pub const JUMPF:    u8 = 0xE5; // Tail call a code section
+
+// stack
+pub const DUPN:     u8 = 0xE6; // DUP with an immediate depth
+pub const SWAPN:    u8 = 0xE7; // SWAP with an immediate depth
+pub const EXCHANGE: u8 = 0xE8; // Swap two items below the top of the stack
+
+// system
+pub const RETURN:       u8 = 0xF3; // Halt and hand memory back to the caller
+pub const EXTCALL:      u8 = 0xF8; // Message call with EOF semantics
+pub const REVERT:       u8 = 0xFD;
+pub const INVALID:      u8 = 0xFE; // Designated invalid instruction
+pub const SELFDESTRUCT: u8 = 0xFF; // Send the whole balance away and halt
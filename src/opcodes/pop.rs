@@ -1,9 +1,12 @@
-use crate::evm::{EVM, EvmError};
+use crate::{
+    evm::{EVM, EvmError},
+    host::Host,
+};
 
 // pops first item off stack
-pub fn pop(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(2)?;
-    vm.stack.pop()?;
+pub fn pop<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.base)?;
+    vm.stack.pop1();
     vm.pc += 1;
     Ok(())
 }
@@ -2,12 +2,15 @@ use std::cmp::min;
 
 use alloy_primitives::U256;
 
-use crate::evm::{EVM, EvmError};
+use crate::{
+    evm::{EVM, EvmError},
+    host::Host,
+};
 
 // PUSH N 
 // pushes items onto the stack
-pub fn push(vm: &mut EVM, n:usize) -> Result<(), EvmError> {
-    vm.gas_dec(3)?;
+pub fn push<H: Host>(vm: &mut EVM<H>, n:usize) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.very_low)?;
 
     let start_index = vm.pc + 1;
     let mut data_bytes = vec![0u8; n];
@@ -19,7 +22,7 @@ pub fn push(vm: &mut EVM, n:usize) -> Result<(), EvmError> {
         data_bytes[..copy_len].copy_from_slice(code_slice);
     }
     let value = U256::from_be_slice(&data_bytes);
-    vm.stack.push(value)?;
+    vm.stack.push_unchecked(value);
     vm.pc += 1 + n; // added 1 because We skip the Opcode
     Ok(())
 }
@@ -1,6 +1,9 @@
-use crate::evm::{EVM, EvmError};
+use crate::{
+    evm::{EVM, EvmError},
+    host::Host,
+};
 
-pub fn stop(vm: &mut EVM) -> Result<(), EvmError> {
+pub fn stop<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
     vm.stop_flag = true;
     Ok(())
 }
@@ -2,48 +2,62 @@
 
 use alloy_primitives::U256;
 
-use crate::evm::{EVM, EvmError};
+use crate::{
+    evm::{EVM, EvmError},
+    host::Host,
+};
 
 // loads one word (32 bytes) from storage by a `key`` onto the stack
-pub fn sload(vm: &mut EVM) -> Result<(), EvmError> {
-    let key = vm.stack.pop()?;
-    let (is_warm, word) = vm.storage.load(key);
-    let cost = if is_warm { 100 } else { 2100 };
+pub fn sload<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    let key = vm.stack.nth(0);
+    // the slot is only warmed once the access is paid for
+    let is_warm = vm.host.is_slot_warm(vm.address, key);
+    let cost = if is_warm {
+        vm.gas_schedule.warm_sload
+    } else {
+        vm.gas_schedule.cold_sload
+    };
     vm.gas_dec(cost)?;
-    vm.stack.push(word)?;
+    vm.stack.pop_n(1);
+
+    vm.host.warm_slot(vm.address, key);
+    let word = vm.host.sload(vm.address, key);
+    vm.stack.push_unchecked(word);
 
     vm.pc += 1;
     Ok(())
 }
 
 // get the key and word from the stack, and store in storage, where storage[key] = value
-pub fn s_store(vm: &mut EVM) -> Result<(), EvmError> {
-    let key = vm.stack.pop()?;
-    let new_value = vm.stack.pop()?;
-    // peek is used here instead of storing directly to prevent mutating state before charging gas costs
-    // peek does not mutate storage state
-    let (is_warm, old_value) = vm.storage.peek(&key);
-    let access_cost = if is_warm { 100 } else { 2100 };
-    let mut base_dynamic_gas = 0u64;
-    if new_value != old_value {
-        if old_value == U256::ZERO {
-            // very expensive, cos we storing in a new storage slot
-            // sort of like the total amount a tenant pays when moving into a new apartment
-            base_dynamic_gas = 20000;
-        } else if new_value == U256::ZERO {
-            // old_value -> new_value : non_zero -> zero
-            base_dynamic_gas = 2900; // pay standard modification fee
-        } else {
-            // old_value -> new_value : non_zero -> non_zero
-            base_dynamic_gas = 2900;
-        }
-    }
-    vm.gas_dec(base_dynamic_gas + access_cost)?;
+pub fn s_store<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.ensure_not_static()?;
+    let key = vm.stack.nth(0);
+    let new_value = vm.stack.nth(1);
+    // only read the slot here, nothing is stored or warmed before charging gas costs
+    let is_warm = vm.host.is_slot_warm(vm.address, key);
+    let old_value = vm.host.sload(vm.address, key);
+    let base_dynamic_gas = if new_value == old_value {
+        vm.gas_schedule.sstore_noop
+    } else if old_value == U256::ZERO {
+        // very expensive, cos we storing in a new storage slot
+        // sort of like the total amount a tenant pays when moving into a new apartment
+        vm.gas_schedule.sstore_set
+    } else {
+        // non_zero -> zero or non_zero -> non_zero, pay standard modification fee
+        vm.gas_schedule.sstore_reset
+    };
+    // only a surcharge, the slot access itself is part of the store
+    let cold_cost = if is_warm { 0 } else { vm.gas_schedule.sstore_cold_slot };
+    vm.gas_dec(base_dynamic_gas + cold_cost)?;
+    vm.stack.pop_n(2);
     if new_value != old_value && new_value == U256::ZERO {
-        vm.refund += 4800;
+        vm.refund += vm.gas_schedule.sstore_clears_refund;
     }
     // now that gas has been deducted successfully and we have the value to be moved to storage
-    vm.storage.store(key, new_value);
+    let address = vm.address;
+    vm.inspect(|inspector, vm| inspector.sstore(vm, address, key, old_value, new_value));
+    vm.host.warm_slot(vm.address, key);
+    vm.host.sstore(vm.address, key, new_value);
 
     vm.pc += 1;
     Ok(())
@@ -1,29 +1,26 @@
 // These opcodes behave almost identically to storage but changes are discarded after every transaction.
 
-use alloy_primitives::U256;
-
-use crate::evm::{EVM, EvmError};
+use crate::{
+    evm::{EVM, EvmError},
+    host::Host,
+};
 
 // loads data from storage to stack temporarily
-pub fn tload(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(100)?;
-    let key = vm.stack.pop()?;
-    let value = *vm.transient_storage.get(&key).unwrap_or(&U256::ZERO);
-    vm.stack.push(value)?;
+pub fn tload<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.gas_dec(vm.gas_schedule.transient)?;
+    let key = vm.stack.pop1();
+    let value = vm.host.tload(vm.address, key);
+    vm.stack.push_unchecked(value);
     vm.pc += 1;
     Ok(())
 }
 
 // stores data in storage temporarily i.e if I run TSTORE(key=1,val=99) and run SLOAD(key=1), I should not see 99
-pub fn tstore(vm: &mut EVM) -> Result<(), EvmError> {
-    vm.gas_dec(100)?;
-    let key = vm.stack.pop()?;
-    let value = vm.stack.pop()?;
-    if value == U256::ZERO {
-        vm.transient_storage.remove(&key);
-    } else {
-        vm.transient_storage.insert(key, value);
-    }
+pub fn tstore<H: Host>(vm: &mut EVM<H>) -> Result<(), EvmError> {
+    vm.ensure_not_static()?;
+    vm.gas_dec(vm.gas_schedule.transient)?;
+    let (key, value) = vm.stack.pop2();
+    vm.host.tstore(vm.address, key, value);
     vm.pc += 1;
     Ok(())
 }
@@ -0,0 +1,174 @@
+// Native contracts living at fixed addresses. A call to one of them runs Rust instead of bytecode,
+// which is how chains add an oracle, a system contract or a mock for testing.
+// The EVM has no standard precompiles yet, every one it has is registered by the user:
+//   evm.register_precompile(ORACLE, |_: &[u8], _: u64, _: &mut PrecompileContext| {
+//       Ok(PrecompileOutput::new(100, price.to_be_bytes_vec()))
+//   });
+// Precompiles that keep state between calls hold it behind a `Cell` or `RefCell`, or in the host.
+// What a precompile writes to the host is undone when it reverts or halts, like a callee's writes.
+// Only EXTCALL reaches them for now, legacy CALL and its variants aren't implemented.
+
+use std::collections::HashMap;
+
+use alloy_primitives::{Address, U256};
+
+use crate::host::Host;
+
+// what a precompile may know about the call that reached it
+pub struct PrecompileContext<'a> {
+    pub caller: Address,
+    // the precompile's own address
+    pub address: Address,
+    pub value: U256,
+    // a read-only frame called it, the precompile must not change state
+    pub is_static: bool,
+    pub host: &'a mut dyn Host,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrecompileOutput {
+    pub gas_used: u64,
+    pub bytes: Vec<u8>,
+    // a revert hands the unused gas and `bytes` back, like REVERT in bytecode
+    pub reverted: bool,
+}
+
+impl PrecompileOutput {
+    pub fn new(gas_used: u64, bytes: Vec<u8>) -> Self {
+        Self {
+            gas_used,
+            bytes,
+            reverted: false,
+        }
+    }
+
+    pub fn revert(gas_used: u64, bytes: Vec<u8>) -> Self {
+        Self {
+            gas_used,
+            bytes,
+            reverted: true,
+        }
+    }
+}
+
+// halts the call, which loses all the gas it was given, like an exceptional halt in bytecode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrecompileError {
+    OutOfGas,
+    Other(String),
+}
+
+pub type PrecompileResult = Result<PrecompileOutput, PrecompileError>;
+
+pub trait Precompile {
+    // `gas_limit` is all the call may spend, charging more than that halts it
+    fn run(&self, input: &[u8], gas_limit: u64, ctx: &mut PrecompileContext<'_>)
+    -> PrecompileResult;
+}
+
+// stateless precompiles can be plain functions or closures
+impl<F> Precompile for F
+where
+    F: Fn(&[u8], u64, &mut PrecompileContext<'_>) -> PrecompileResult,
+{
+    fn run(&self, input: &[u8], gas_limit: u64, ctx: &mut PrecompileContext<'_>) -> PrecompileResult {
+        self(input, gas_limit, ctx)
+    }
+}
+
+// the precompiles of one EVM by address, `EVM::register_precompile` and `remove_precompile`
+// change them between runs
+#[derive(Default)]
+pub struct Precompiles {
+    precompiles: HashMap<Address, Box<dyn Precompile>>,
+}
+
+impl Precompiles {
+    // installs `precompile` at `address`, returning the one it replaces
+    pub fn insert(
+        &mut self,
+        address: Address,
+        precompile: impl Precompile + 'static,
+    ) -> Option<Box<dyn Precompile>> {
+        self.precompiles.insert(address, Box::new(precompile))
+    }
+
+    pub fn remove(&mut self, address: Address) -> Option<Box<dyn Precompile>> {
+        self.precompiles.remove(&address)
+    }
+
+    pub fn get(&self, address: Address) -> Option<&dyn Precompile> {
+        self.precompiles.get(&address).map(|precompile| precompile.as_ref())
+    }
+
+    pub fn contains(&self, address: Address) -> bool {
+        self.precompiles.contains_key(&address)
+    }
+
+    pub fn addresses(&self) -> impl Iterator<Item = &Address> {
+        self.precompiles.keys()
+    }
+
+    pub fn len(&self) -> usize {
+        self.precompiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.precompiles.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::host::InMemoryHost;
+
+    // counts its calls, the state a precompile keeps for itself
+    #[derive(Default)]
+    struct Counter {
+        calls: Cell<u64>,
+    }
+
+    impl Precompile for Counter {
+        fn run(&self, _: &[u8], _: u64, _: &mut PrecompileContext<'_>) -> PrecompileResult {
+            self.calls.set(self.calls.get() + 1);
+            Ok(PrecompileOutput::new(10, self.calls.get().to_be_bytes().to_vec()))
+        }
+    }
+
+    fn call(precompiles: &Precompiles, address: Address, input: &[u8]) -> PrecompileResult {
+        let mut host = InMemoryHost::default();
+        let mut ctx = PrecompileContext {
+            caller: Address::ZERO,
+            address,
+            value: U256::ZERO,
+            is_static: false,
+            host: &mut host,
+        };
+        precompiles.get(address).unwrap().run(input, 1000, &mut ctx)
+    }
+
+    #[test]
+    fn test_insert_replace_and_remove() {
+        let address = Address::with_last_byte(0x42);
+        let mut precompiles = Precompiles::default();
+        precompiles.insert(address, Counter::default());
+        assert_eq!(call(&precompiles, address, &[]).unwrap().bytes, 1u64.to_be_bytes());
+        assert_eq!(call(&precompiles, address, &[]).unwrap().bytes, 2u64.to_be_bytes());
+
+        let echo = |input: &[u8], _: u64, _: &mut PrecompileContext<'_>| {
+            Ok(PrecompileOutput::revert(5, input.to_vec()))
+        };
+        assert!(precompiles.insert(address, echo).is_some());
+        assert_eq!(
+            call(&precompiles, address, &[1, 2]),
+            Ok(PrecompileOutput::revert(5, vec![1, 2]))
+        );
+
+        assert!(precompiles.remove(address).is_some());
+        assert!(!precompiles.contains(address));
+        assert!(precompiles.is_empty());
+    }
+}
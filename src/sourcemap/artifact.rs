@@ -0,0 +1,172 @@
+// Runtime code and its debug info from the output of
+//   solc --combined-json bin-runtime,srcmap-runtime Contract.sol > out.json
+// which looks like
+//   { "contracts": { "Contract.sol:Contract": { "bin-runtime": "6080..", "srcmap-runtime": "..." } },
+//     "sourceList": ["Contract.sol"] }
+// The position of a path in `sourceList` is its file id in the source map. solc doesn't copy the
+// sources into the output, they are read from disk, relative to the output's directory.
+// Compiled with `--combined-json abi,bin-runtime,srcmap-runtime` the ABI comes along, older solc
+// versions write it as a JSON string rather than an array.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use alloy_primitives::hex;
+use serde::Deserialize;
+
+use crate::{
+    abi::{Abi, AbiError},
+    sourcemap::{DebugInfo, SourceFile, SourceMapError},
+};
+
+#[derive(Debug)]
+pub enum ArtifactError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    // a source file listed in the artifact could not be read
+    Source { path: PathBuf, error: std::io::Error },
+    // no contract by that name, or no name given and the artifact has several
+    UnknownContract { name: Option<String>, available: Vec<String> },
+    // the contract was compiled without `bin-runtime` or `srcmap-runtime`
+    MissingField(&'static str),
+    InvalidBytecode(hex::FromHexError),
+    SourceMap(SourceMapError),
+    Abi(AbiError),
+}
+
+#[derive(Debug, Deserialize)]
+struct CombinedJson {
+    contracts: BTreeMap<String, CombinedContract>,
+    #[serde(rename = "sourceList", default)]
+    source_list: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CombinedContract {
+    #[serde(rename = "bin-runtime")]
+    bin_runtime: Option<String>,
+    #[serde(rename = "srcmap-runtime")]
+    srcmap_runtime: Option<String>,
+    abi: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Artifact {
+    // as solc keys it, `path:Name`
+    pub name: String,
+    pub code: Vec<u8>,
+    pub debug_info: DebugInfo,
+    // empty when the artifact was compiled without `abi`
+    pub abi: Abi,
+}
+
+impl Artifact {
+    // `contract` is `Name` or `path:Name`, and may be left out if there is only one contract
+    pub fn load(path: impl AsRef<Path>, contract: Option<&str>) -> Result<Self, ArtifactError> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path).map_err(ArtifactError::Io)?;
+        let base_dir = path.parent().unwrap_or(Path::new("."));
+        from_combined_json(&json, contract, |source| {
+            let source = base_dir.join(source);
+            fs::read_to_string(&source).map_err(|error| ArtifactError::Source {
+                path: source,
+                error,
+            })
+        })
+    }
+}
+
+// the same as `Artifact::load`, with `read_source` turning a path of `sourceList` into its content
+pub fn from_combined_json(
+    json: &str,
+    contract: Option<&str>,
+    mut read_source: impl FnMut(&str) -> Result<String, ArtifactError>,
+) -> Result<Artifact, ArtifactError> {
+    let combined: CombinedJson = serde_json::from_str(json).map_err(ArtifactError::Json)?;
+    let matches: Vec<_> = combined
+        .contracts
+        .iter()
+        .filter(|(key, _)| match contract {
+            Some(name) => *key == name || key.rsplit(':').next() == Some(name),
+            None => true,
+        })
+        .collect();
+    let [(name, entry)] = matches[..] else {
+        return Err(ArtifactError::UnknownContract {
+            name: contract.map(str::to_string),
+            available: combined.contracts.keys().cloned().collect(),
+        });
+    };
+
+    let bin = entry.bin_runtime.as_deref().ok_or(ArtifactError::MissingField("bin-runtime"))?;
+    let code = hex::decode(bin.trim()).map_err(ArtifactError::InvalidBytecode)?;
+    let source_map = entry
+        .srcmap_runtime
+        .as_deref()
+        .ok_or(ArtifactError::MissingField("srcmap-runtime"))?;
+    let mut sources = Vec::new();
+    for (id, path) in combined.source_list.iter().enumerate() {
+        sources.push(SourceFile::new(id, path, read_source(path)?));
+    }
+    let debug_info = DebugInfo::new(source_map, &code, sources).map_err(ArtifactError::SourceMap)?;
+    let abi = match &entry.abi {
+        None => Ok(Abi::default()),
+        Some(serde_json::Value::String(json)) => Abi::from_json(json),
+        Some(items) => Abi::from_json(&items.to_string()),
+    };
+    Ok(Artifact {
+        name: name.clone(),
+        code,
+        debug_info,
+        abi: abi.map_err(ArtifactError::Abi)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JSON: &str = r#"{
+        "contracts": {
+            "A.sol:A": { "bin-runtime": "6001600055", "srcmap-runtime": "30:6:0;;;",
+                         "abi": [{ "type": "function", "name": "f", "inputs": [] }] },
+            "B.sol:B": { "bin-runtime": "00" }
+        },
+        "sourceList": ["A.sol", "B.sol"],
+        "version": "0.8.28"
+    }"#;
+
+    const A: &str = "contract A {\n  function f() {\n    x = 1;\n  }\n}\n";
+
+    fn read(path: &str) -> Result<String, ArtifactError> {
+        Ok(if path == "A.sol" { A.to_string() } else { String::new() })
+    }
+
+    #[test]
+    fn test_load_combined_json() {
+        let artifact = from_combined_json(JSON, Some("A"), read).unwrap();
+        assert_eq!(artifact.name, "A.sol:A");
+        assert_eq!(artifact.code, vec![0x60, 0x01, 0x60, 0x00, 0x55]);
+        assert_eq!(artifact.debug_info.sources.len(), 2);
+        let location = artifact.debug_info.location(4).unwrap();
+        assert_eq!(location.to_string(), "A.sol:3");
+        assert_eq!(location.text(), "x = 1;");
+        assert_eq!(artifact.abi.functions[0].signature(), "f()");
+    }
+
+    #[test]
+    fn test_contract_must_be_known_and_complete() {
+        let ambiguous = from_combined_json(JSON, None, read);
+        assert!(matches!(
+            ambiguous,
+            Err(ArtifactError::UnknownContract { name: None, available }) if available.len() == 2
+        ));
+        let missing = from_combined_json(JSON, Some("C"), read);
+        assert!(matches!(missing, Err(ArtifactError::UnknownContract { .. })));
+        let incomplete = from_combined_json(JSON, Some("B.sol:B"), read);
+        assert!(matches!(incomplete, Err(ArtifactError::MissingField("srcmap-runtime"))));
+    }
+}
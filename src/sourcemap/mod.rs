@@ -0,0 +1,284 @@
+// Solidity source maps, as solc writes them in `evm.deployedBytecode.sourceMap`:
+//   "s:l:f:j:m;s:l:f:j:m;..." -> one entry per instruction (not per byte):
+//     s = byte offset in the source file, l = length, f = file id (-1: no file),
+//     j = jump type (i into a function, o out of one, - a plain jump), m = modifier depth
+// The map is compressed: a field left empty, or missing at the end of an entry, repeats the one of
+// the entry before, so "1:2:1;:9;2:1:2;;" is 1:2:1, 1:9:1, 2:1:2, 2:1:2.
+// `SourceMap::new` also walks the bytecode once, so a pc can be turned into its entry directly.
+// `DebugInfo` puts a map together with its source files, to go from a pc to a line of Solidity:
+//   pc -> instruction index -> (file, offset, length, jump) -> line
+// `artifact` loads both from what solc writes.
+
+use std::{collections::HashMap, fmt};
+
+use crate::disassembler::disassemble;
+
+pub mod artifact;
+
+pub use artifact::{Artifact, ArtifactError};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceMapError {
+    // `field` of the entry at `index` is not a number
+    InvalidField { index: usize, field: String },
+    InvalidJumpType { index: usize, jump: String },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum JumpType {
+    // into a function
+    In,
+    // back out of one
+    Out,
+    #[default]
+    Regular,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SourceElement {
+    pub offset: usize,
+    pub length: usize,
+    // None for code the compiler generated without a source, e.g. the dispatcher
+    pub file: Option<usize>,
+    pub jump: JumpType,
+    pub modifier_depth: u32,
+}
+
+// a source file as the compiler numbered it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceFile {
+    pub id: usize,
+    pub path: String,
+    pub content: String,
+    // byte offset of the start of every line
+    line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+    pub fn new(id: usize, path: impl Into<String>, content: impl Into<String>) -> Self {
+        let content = content.into();
+        let line_starts = std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            id,
+            path: path.into(),
+            content,
+            line_starts,
+        }
+    }
+
+    // the 1-based line a byte offset falls on
+    pub fn line_of(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|start| *start <= offset)
+    }
+
+    // the text of a 1-based line, without its newline
+    pub fn line(&self, line: usize) -> Option<&str> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self.line_starts.get(line).map_or(self.content.len(), |next| next - 1);
+        self.content.get(start..end)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceMap {
+    pub elements: Vec<SourceElement>,
+    // pc -> index of its instruction, and so of its entry
+    instruction_index: HashMap<usize, usize>,
+}
+
+impl SourceMap {
+    // `code` is the (legacy) bytecode the map was generated for
+    pub fn new(source_map: &str, code: &[u8]) -> Result<Self, SourceMapError> {
+        let elements = parse(source_map)?;
+        let instruction_index = disassemble(code)
+            .iter()
+            .enumerate()
+            .map(|(index, instruction)| (instruction.pc, index))
+            .collect();
+        Ok(Self {
+            elements,
+            instruction_index,
+        })
+    }
+
+    // the index of the instruction starting at `pc`, None inside push data or past the end
+    pub fn instruction_index(&self, pc: usize) -> Option<usize> {
+        self.instruction_index.get(&pc).copied()
+    }
+
+    pub fn element_at_pc(&self, pc: usize) -> Option<&SourceElement> {
+        self.elements.get(self.instruction_index(pc)?)
+    }
+}
+
+// where the instruction at a pc came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceLocation<'a> {
+    pub file: &'a SourceFile,
+    pub offset: usize,
+    pub length: usize,
+    // 1-based
+    pub line: usize,
+    pub jump: JumpType,
+}
+
+impl SourceLocation<'_> {
+    // the whole line the instruction starts on, trimmed
+    pub fn text(&self) -> &str {
+        self.file.line(self.line).unwrap_or_default().trim()
+    }
+}
+
+// path:line
+impl fmt::Display for SourceLocation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file.path, self.line)
+    }
+}
+
+// a source map and the files it points into
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugInfo {
+    pub source_map: SourceMap,
+    pub sources: Vec<SourceFile>,
+}
+
+impl DebugInfo {
+    pub fn new(
+        source_map: &str,
+        code: &[u8],
+        sources: Vec<SourceFile>,
+    ) -> Result<Self, SourceMapError> {
+        Ok(Self {
+            source_map: SourceMap::new(source_map, code)?,
+            sources,
+        })
+    }
+
+    pub fn source(&self, id: usize) -> Option<&SourceFile> {
+        self.sources.iter().find(|source| source.id == id)
+    }
+
+    // None for push data, and for instructions the compiler gave no file
+    pub fn location(&self, pc: usize) -> Option<SourceLocation<'_>> {
+        let element = self.source_map.element_at_pc(pc)?;
+        let file = self.source(element.file?)?;
+        Some(SourceLocation {
+            file,
+            offset: element.offset,
+            length: element.length,
+            line: file.line_of(element.offset),
+            jump: element.jump,
+        })
+    }
+}
+
+// decompresses a source map into one element per instruction
+pub fn parse(source_map: &str) -> Result<Vec<SourceElement>, SourceMapError> {
+    let mut elements = Vec::new();
+    if source_map.is_empty() {
+        return Ok(elements);
+    }
+    let mut last = SourceElement::default();
+    for (index, entry) in source_map.split(';').enumerate() {
+        let number = |field: &str| -> Result<i64, SourceMapError> {
+            field.parse().map_err(|_| SourceMapError::InvalidField {
+                index,
+                field: field.to_string(),
+            })
+        };
+        let mut element = last;
+        for (position, field) in entry.split(':').enumerate() {
+            if field.is_empty() {
+                continue;
+            }
+            match position {
+                0 => element.offset = number(field)?.max(0) as usize,
+                1 => element.length = number(field)?.max(0) as usize,
+                2 => element.file = usize::try_from(number(field)?).ok(),
+                3 => {
+                    element.jump = match field {
+                        "i" => JumpType::In,
+                        "o" => JumpType::Out,
+                        "-" => JumpType::Regular,
+                        _ => {
+                            return Err(SourceMapError::InvalidJumpType {
+                                index,
+                                jump: field.to_string(),
+                            });
+                        }
+                    }
+                }
+                4 => element.modifier_depth = number(field)?.max(0) as u32,
+                // later compilers may add fields, they don't concern us
+                _ => {}
+            }
+        }
+        elements.push(element);
+        last = element;
+    }
+    Ok(elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decompress() {
+        let elements = parse("1:2:1;:9;2:1:2:i;;-1:4:-1:o:1").unwrap();
+        let triples: Vec<_> = elements.iter().map(|e| (e.offset, e.length, e.file)).collect();
+        assert_eq!(
+            triples,
+            [(1, 2, Some(1)), (1, 9, Some(1)), (2, 1, Some(2)), (2, 1, Some(2)), (0, 4, None)]
+        );
+        assert_eq!(elements[2].jump, JumpType::In);
+        assert_eq!(elements[3].jump, JumpType::In);
+        assert_eq!(elements[4].jump, JumpType::Out);
+        assert_eq!(elements[4].modifier_depth, 1);
+        assert!(parse("1:x").is_err());
+        assert!(parse("1:2:0:q").is_err());
+    }
+
+    #[test]
+    fn test_pc_to_element_skips_push_data() {
+        // PUSH2 0x0102, PUSH1 0, SSTORE
+        let code = [0x61, 0x01, 0x02, 0x60, 0x00, 0x55];
+        let map = SourceMap::new("0:10:0;12:5;20:3", &code).unwrap();
+        assert_eq!(map.instruction_index(3), Some(1));
+        assert_eq!(map.instruction_index(1), None);
+        assert_eq!(map.element_at_pc(5).unwrap().offset, 20);
+    }
+
+    #[test]
+    fn test_location() {
+        let source = "contract A {\n  function f() public {\n    x = 1;\n  }\n}\n";
+        let offset = source.find("x = 1").unwrap();
+        // PUSH1 1, PUSH1 0, SSTORE: the push of the value comes from no file
+        let code = [0x60, 0x01, 0x60, 0x00, 0x55];
+        let map = format!("{offset}:6:-1;:::i;:::o");
+        let files = vec![SourceFile::new(0, "A.sol", source)];
+        let info = DebugInfo::new(&map, &code, files.clone()).unwrap();
+        assert_eq!(info.location(0), None);
+        let map = format!("{offset}:6:0;;:::o");
+        let info = DebugInfo::new(&map, &code, files).unwrap();
+        let location = info.location(4).unwrap();
+        assert_eq!(location.to_string(), "A.sol:3");
+        assert_eq!(location.text(), "x = 1;");
+        assert_eq!(location.jump, JumpType::Out);
+        assert_eq!(info.location(1), None);
+    }
+
+    #[test]
+    fn test_lines() {
+        let file = SourceFile::new(0, "A.sol", "contract A {\n  uint x;\n}\n");
+        assert_eq!(file.line_of(0), 1);
+        assert_eq!(file.line_of(12), 1);
+        assert_eq!(file.line_of(13), 2);
+        assert_eq!(file.line(2), Some("  uint x;"));
+        assert_eq!(file.line(3), Some("}"));
+        assert_eq!(file.line(9), None);
+    }
+}
@@ -1,43 +1,155 @@
+use std::fmt::{self, Debug};
+
 use alloy_primitives::U256;
 
 use crate::evm::EvmError;
 
 pub const MAXIMUM_STACK_SIZE: usize = 1024;
-#[derive(Debug, Clone)]
+
+// A fixed block of 1024 words with a height, nothing is allocated while the program runs.
+// There are two sets of operations:
+//   - `push`, `pop`, `peek` and `discard` check the height themselves and return an error
+//   - `pop1`, `pop2`, `pop3`, `top_mut`, `nth`, `pop_n`, `push_unchecked` and `swap` trust the caller:
+//     the interpreter checks the height once per instruction with `check`, before the handler runs,
+//     using the inputs and outputs from the opcode table. Debug builds assert the height in each of
+//     them, and tests/evm.rs runs every handler on exactly the items its table entry declares.
+#[derive(Clone)]
 pub struct Stack {
-    pub items: Vec<U256>,
+    data: Box<[U256; MAXIMUM_STACK_SIZE]>,
+    len: usize,
+}
+
+impl Default for Stack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// only the live items matter, whatever is left above the top is garbage
+impl PartialEq for Stack {
+    fn eq(&self, other: &Self) -> bool {
+        self.items() == other.items()
+    }
+}
+
+impl Debug for Stack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Stack").field("items", &self.items()).finish()
+    }
 }
 
 impl Stack {
     pub fn new() -> Self {
         Self {
-            items: Vec::with_capacity(MAXIMUM_STACK_SIZE),
+            data: Box::new([U256::ZERO; MAXIMUM_STACK_SIZE]),
+            len: 0,
+        }
+    }
+
+    // the live items, bottom first
+    pub fn items(&self) -> &[U256] {
+        &self.data[..self.len]
+    }
+
+    // fails unless `inputs` items can be popped and `outputs` pushed after that
+    pub fn check(&self, inputs: usize, outputs: usize) -> Result<(), EvmError> {
+        if self.len < inputs {
+            return Err(EvmError::StackUnderflow);
+        }
+        if self.len - inputs + outputs > MAXIMUM_STACK_SIZE {
+            return Err(EvmError::StackOverflow);
         }
+        Ok(())
     }
+
     pub fn push(&mut self, item: U256) -> Result<(), EvmError> {
-        if self.items.len() + 1 > MAXIMUM_STACK_SIZE {
+        if self.len == MAXIMUM_STACK_SIZE {
             return Err(EvmError::StackOverflow);
         }
-        self.items.push(item);
+        self.push_unchecked(item);
         Ok(())
     }
 
     pub fn pop(&mut self) -> Result<U256, EvmError> {
-        self.items.pop().ok_or(EvmError::StackUnderflow)
+        if self.len == 0 {
+            return Err(EvmError::StackUnderflow);
+        }
+        Ok(self.pop1())
+    }
+
+    // drops n items that were already read with `peek`
+    pub fn discard(&mut self, n: usize) -> Result<(), EvmError> {
+        if self.len < n {
+            return Err(EvmError::StackUnderflow);
+        }
+        self.pop_n(n);
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
     }
 
-    pub fn len(&self) -> usize{
-        self.items.len()
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
     }
 
     // peek at item n slots from the top of the stack
-    pub fn peek(&self, n_from_top : usize) -> Result<U256, EvmError> {
-        let stack_length = self.items.len();
-        if stack_length < n_from_top{
+    pub fn peek(&self, n_from_top: usize) -> Result<U256, EvmError> {
+        if self.len <= n_from_top {
             return Err(EvmError::StackUnderflow);
         }
-        let index = stack_length - 1 - n_from_top;  // subtract 1 because we want to start from index zero,
-        Ok(self.items[index])
+        Ok(self.nth(n_from_top))
+    }
+
+    // the unchecked operations below rely on `check` having passed for the current instruction
+
+    pub fn push_unchecked(&mut self, item: U256) {
+        debug_assert!(self.len < MAXIMUM_STACK_SIZE, "push on a full stack");
+        self.data[self.len] = item;
+        self.len += 1;
+    }
+
+    pub fn pop1(&mut self) -> U256 {
+        debug_assert!(self.len > 0, "pop on an empty stack");
+        self.len -= 1;
+        self.data[self.len]
+    }
+
+    // (top, second)
+    pub fn pop2(&mut self) -> (U256, U256) {
+        let a = self.pop1();
+        (a, self.pop1())
+    }
+
+    // (top, second, third)
+    pub fn pop3(&mut self) -> (U256, U256, U256) {
+        let (a, b) = self.pop2();
+        (a, b, self.pop1())
+    }
+
+    pub fn pop_n(&mut self, n: usize) {
+        debug_assert!(n <= self.len, "popping {n} items off {}", self.len);
+        self.len -= n;
+    }
+
+    // item n slots from the top
+    pub fn nth(&self, n_from_top: usize) -> U256 {
+        debug_assert!(n_from_top < self.len, "reading item {n_from_top} of {}", self.len);
+        self.data[self.len - 1 - n_from_top]
+    }
+
+    // the top item, to overwrite the result of an instruction in place
+    pub fn top_mut(&mut self) -> &mut U256 {
+        debug_assert!(self.len > 0, "top of an empty stack");
+        &mut self.data[self.len - 1]
+    }
+
+    // swaps the items a and b slots from the top
+    pub fn swap(&mut self, a: usize, b: usize) {
+        debug_assert!(a.max(b) < self.len, "swapping items {a} and {b} of {}", self.len);
+        let top = self.len - 1;
+        self.data.swap(top - a, top - b);
     }
 }
 
@@ -51,7 +163,7 @@ mod tests {
 
     #[test]
     fn test_create_stack() {
-        assert_eq!(create_stack().items.len(), 0);
+        assert_eq!(create_stack().items().len(), 0);
     }
 
     #[test]
@@ -59,7 +171,7 @@ mod tests {
         let mut new_stack = create_stack();
         let _ = new_stack.push(U256::from(0x9222));
         let _ = new_stack.push(U256::from(0x87222));
-        assert_eq!(new_stack.items.len(), 2);
+        assert_eq!(new_stack.items().len(), 2);
     }
     // fix
     #[test]
@@ -67,8 +179,8 @@ mod tests {
         let mut new_stack = create_stack();
         for _ in 0..=1024 {
             let res = new_stack.push(U256::from(0x9222));
-            if res.is_err() {
-                assert_eq!(res.unwrap_err(), EvmError::StackOverflow);
+            if let Err(err) = res {
+                assert_eq!(err, EvmError::StackOverflow);
             }
         }
     }
@@ -79,8 +191,8 @@ mod tests {
         let _ = new_stack.push(U256::from(0x9222));
         let _ = new_stack.push(U256::from(0x87222));
         let _ = new_stack.pop();
-        assert_eq!(new_stack.items[0], U256::from(0x9222));
-        assert_eq!(new_stack.items.len(), 1);
+        assert_eq!(new_stack.items()[0], U256::from(0x9222));
+        assert_eq!(new_stack.items().len(), 1);
     }
     // fix
     #[test]
@@ -90,9 +202,66 @@ mod tests {
         let _ = new_stack.push(U256::from(0x87222));
         for _ in 0..5 {
             let res = new_stack.pop();
-            if res.is_err() {
-                assert_eq!(res.unwrap_err(), EvmError::StackUnderflow);
+            if let Err(err) = res {
+                assert_eq!(err, EvmError::StackUnderflow);
             }
         }
     }
+
+    #[test]
+    fn test_peek_past_the_bottom() {
+        let mut new_stack = create_stack();
+        assert_eq!(new_stack.peek(0), Err(EvmError::StackUnderflow));
+        let _ = new_stack.push(U256::from(0x9222));
+        assert_eq!(new_stack.peek(0), Ok(U256::from(0x9222)));
+        assert_eq!(new_stack.peek(1), Err(EvmError::StackUnderflow));
+    }
+
+    #[test]
+    fn test_discard() {
+        let mut new_stack = create_stack();
+        let _ = new_stack.push(U256::from(0x9222));
+        let _ = new_stack.push(U256::from(0x87222));
+        assert_eq!(new_stack.discard(3), Err(EvmError::StackUnderflow));
+        assert_eq!(new_stack.len(), 2);
+        assert_eq!(new_stack.discard(1), Ok(()));
+        assert_eq!(new_stack.items(), vec![U256::from(0x9222)]);
+    }
+
+    #[test]
+    fn test_check() {
+        let mut new_stack = create_stack();
+        let _ = new_stack.push(U256::from(1));
+        assert_eq!(new_stack.check(1, 1), Ok(()));
+        assert_eq!(new_stack.check(2, 1), Err(EvmError::StackUnderflow));
+        for _ in 1..MAXIMUM_STACK_SIZE {
+            let _ = new_stack.push(U256::from(1));
+        }
+        assert_eq!(new_stack.check(0, 0), Ok(()));
+        assert_eq!(new_stack.check(1, 2), Err(EvmError::StackOverflow));
+    }
+
+    #[test]
+    fn test_unchecked_operations() {
+        let mut new_stack = create_stack();
+        for value in 1..=4 {
+            new_stack.push_unchecked(U256::from(value));
+        }
+        new_stack.swap(0, 3); // [4, 2, 3, 1]
+        *new_stack.top_mut() += U256::from(10); // [4, 2, 3, 11]
+        assert_eq!(new_stack.nth(1), U256::from(3));
+        assert_eq!(new_stack.pop2(), (U256::from(11), U256::from(3)));
+        assert_eq!(new_stack.pop1(), U256::from(2));
+        new_stack.pop_n(1);
+        assert!(new_stack.is_empty());
+    }
+
+    #[test]
+    fn test_popped_slots_are_ignored_by_eq() {
+        let mut a = create_stack();
+        let b = create_stack();
+        a.push_unchecked(U256::from(7));
+        a.pop1();
+        assert_eq!(a, b);
+    }
 }
@@ -0,0 +1,91 @@
+// The JSON of a filled state test, one file holds any number of tests by name:
+//   { "add11": {
+//       "env": { "currentCoinbase": "0x2adc...", "currentNumber": "0x01", "currentBaseFee": "0x0a",
+//                "currentGasLimit": "0x05f5e100", "currentTimestamp": "0x03e8", ... },
+//       "pre": { "0x0950...": { "balance": "0x0de0b6b3a7640000", "code": "0x600160010160005500",
+//                               "nonce": "0x00", "storage": {} } },
+//       "transaction": { "data": ["0x"], "gasLimit": ["0x04c4b400"], "value": ["0x01"],
+//                        "gasPrice": "0x0a", "nonce": "0x00", "sender": "0xa94f...",
+//                        "to": "0x0950..." },
+//       "post": { "Cancun": [{ "hash": "0x...", "logs": "0x...",
+//                              "indexes": { "data": 0, "gas": 0, "value": 0 } }] } } }
+// `data`, `gasLimit` and `value` list the variants of the transaction, every post entry picks one
+// of each with its `indexes`. An entry with `expectException` is a transaction that is invalid.
+
+use std::collections::BTreeMap;
+
+use alloy_primitives::{Address, B256, Bytes, U256};
+use serde::Deserialize;
+
+use crate::db::json::JsonAccount;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateTest {
+    pub env: TestEnv,
+    pub(crate) pre: BTreeMap<Address, JsonAccount>,
+    pub transaction: TestTransaction,
+    // by the fork name the fixture uses, e.g. `Cancun` or `ConstantinopleFix`
+    pub post: BTreeMap<String, Vec<PostState>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestEnv {
+    pub current_coinbase: Address,
+    pub current_gas_limit: U256,
+    pub current_number: U256,
+    pub current_timestamp: U256,
+    #[serde(default)]
+    pub current_difficulty: U256,
+    // PREVRANDAO since the merge
+    pub current_random: Option<B256>,
+    // London on
+    pub current_base_fee: Option<U256>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestTransaction {
+    pub data: Vec<Bytes>,
+    pub gas_limit: Vec<U256>,
+    pub value: Vec<U256>,
+    pub nonce: U256,
+    // empty for a contract creation
+    #[serde(default)]
+    pub to: String,
+    // older fixtures only have the `secretKey` it signs with, those can't be run
+    pub sender: Option<Address>,
+    // a legacy or access list transaction has a gas price, an EIP-1559 one the two fees
+    pub gas_price: Option<U256>,
+    pub max_fee_per_gas: Option<U256>,
+    pub max_priority_fee_per_gas: Option<U256>,
+    // access list and EIP-1559 transactions have one access list per `data`, a null one is empty
+    pub access_lists: Option<Vec<Option<Vec<AccessListItem>>>>,
+    pub blob_versioned_hashes: Option<Vec<B256>>,
+    pub authorization_list: Option<Vec<serde_json::Value>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessListItem {
+    pub address: Address,
+    pub storage_keys: Vec<U256>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostState {
+    // the state root after the transaction
+    pub hash: B256,
+    pub logs: B256,
+    pub indexes: Indexes,
+    pub expect_exception: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Indexes {
+    pub data: usize,
+    pub gas: usize,
+    pub value: usize,
+}
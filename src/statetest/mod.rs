@@ -0,0 +1,29 @@
+// Conformance against the Ethereum state tests, the `GeneralStateTests` of ethereum/tests or the
+// `state_tests` of execution-spec-tests: a pre-state, a transaction and, for every fork, the state
+// root and logs hash it has to end in.
+//   fixture -> the JSON of a test file
+//   transaction -> validating and executing the transaction of a test
+//   rlp, trie -> the encoding and the Merkle Patricia roots the results are compared by
+//   runner -> every file under a directory in parallel, tallied by fork and by directory
+// e.g. the Cancun tests of one directory, the `statetest` binary prints the same:
+//   let filter = Filter { forks: vec!["Cancun".to_string()], name: None };
+//   let report = runner::run(Path::new("GeneralStateTests/stExample"), &filter, 8)?;
+//   println!("{}", report.total());
+// Contracts that CALL or CREATE are skipped as unsupported, the interpreter has no frames for them
+// yet.
+
+pub mod fixture;
+pub mod rlp;
+pub mod runner;
+pub mod transaction;
+pub mod trie;
+
+pub use fixture::StateTest;
+pub use runner::{Filter, Report, Status, Tally, TestResult};
+pub use transaction::{Outcome, Transaction, TxError};
+
+#[derive(Debug)]
+pub enum StateTestError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
@@ -0,0 +1,71 @@
+// Recursive Length Prefix, the encoding of accounts, trie nodes and logs. Only the encoder is here,
+// the runner never has to read RLP back.
+//   a string of one byte below 0x80 is itself, otherwise a length prefix and the bytes
+//   a list is a length prefix and the concatenated encodings of its items
+// Integers are big-endian without leading zeros, zero is the empty string.
+
+use alloy_primitives::U256;
+
+fn prefix(offset: u8, len: usize) -> Vec<u8> {
+    if len < 56 {
+        return vec![offset + len as u8];
+    }
+    let bytes = len.to_be_bytes();
+    let len_bytes = &bytes[bytes.iter().position(|byte| *byte != 0).unwrap_or(bytes.len())..];
+    let mut out = vec![offset + 55 + len_bytes.len() as u8];
+    out.extend_from_slice(len_bytes);
+    out
+}
+
+pub fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+    if let [byte] = bytes
+        && *byte < 0x80
+    {
+        return vec![*byte];
+    }
+    let mut out = prefix(0x80, bytes.len());
+    out.extend_from_slice(bytes);
+    out
+}
+
+pub fn encode_u256(value: U256) -> Vec<u8> {
+    encode_bytes(&value.to_be_bytes_trimmed_vec())
+}
+
+pub fn encode_u64(value: u64) -> Vec<u8> {
+    encode_u256(U256::from(value))
+}
+
+// `items` are encoded already
+pub fn encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let len = items.iter().map(Vec::len).sum();
+    let mut out = prefix(0xc0, len);
+    for item in items {
+        out.extend_from_slice(item);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::hex;
+
+    use super::*;
+
+    #[test]
+    fn test_encoding() {
+        assert_eq!(encode_bytes(b"dog"), hex!("83646f67"));
+        assert_eq!(encode_bytes(&[0x0f]), vec![0x0f]);
+        assert_eq!(encode_bytes(&[0x80]), hex!("8180"));
+        assert_eq!(encode_u64(0), vec![0x80]);
+        assert_eq!(encode_u64(1024), hex!("820400"));
+        let list = encode_list(&[encode_bytes(b"cat"), encode_bytes(b"dog")]);
+        assert_eq!(list, hex!("c88363617483646f67"));
+        assert_eq!(encode_list(&[]), vec![0xc0]);
+
+        // 56 bytes and more take a length of the length
+        let long = encode_bytes(&[0xaa; 60]);
+        assert_eq!(long[..2], [0xb8, 60]);
+        assert_eq!(long.len(), 62);
+    }
+}
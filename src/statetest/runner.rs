@@ -0,0 +1,376 @@
+// Runs every state test under a directory, one file per job, and tallies the results:
+//   let filter = Filter { forks: vec!["Cancun".to_string()], name: Some("add".to_string()) };
+//   let report = runner::run(Path::new("GeneralStateTests"), &filter, 8)?;
+//   for (fork, tally) in report.by_fork() { println!("{fork}: {tally}") }
+// Every post entry is a test of its own. It passes when the state root and the logs hash match,
+// or when the transaction is rejected and the fixture expects an exception (any exception, the
+// names differ between the clients that filled them).
+
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+};
+
+use alloy_primitives::B256;
+
+use crate::{
+    fork::Fork,
+    host::{Env, InMemoryHost},
+    statetest::{
+        StateTestError,
+        fixture::{PostState, StateTest},
+        transaction::{Transaction, TxError, execute},
+        trie::{logs_hash, state_root},
+    },
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    // fork names as the fixtures spell them, all forks if empty
+    pub forks: Vec<String>,
+    // a part of the test name or of the file path
+    pub name: Option<String>,
+}
+
+impl Filter {
+    fn matches_fork(&self, fork: &str) -> bool {
+        self.forks.is_empty() || self.forks.iter().any(|name| name.eq_ignore_ascii_case(fork))
+    }
+
+    fn matches_test(&self, path: &Path, name: &str) -> bool {
+        self.name.as_ref().is_none_or(|part| {
+            name.contains(part.as_str()) || path.to_string_lossy().contains(part.as_str())
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Status {
+    Passed,
+    Failed(String),
+    // the fork or the transaction is something the runner can't do
+    Skipped(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestResult {
+    // the file, relative to the directory that was run
+    pub path: PathBuf,
+    pub name: String,
+    pub fork: String,
+    // the position of the post entry under its fork
+    pub index: usize,
+    pub status: Status,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tally {
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+}
+
+impl Tally {
+    fn add(&mut self, status: &Status) {
+        match status {
+            Status::Passed => self.passed += 1,
+            Status::Failed(_) => self.failed += 1,
+            Status::Skipped(_) => self.skipped += 1,
+        }
+    }
+}
+
+impl fmt::Display for Tally {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} passed, {} failed, {} skipped", self.passed, self.failed, self.skipped)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    pub results: Vec<TestResult>,
+    // files that couldn't be read or aren't state tests
+    pub errors: Vec<(PathBuf, StateTestError)>,
+}
+
+impl Report {
+    pub fn total(&self) -> Tally {
+        let mut tally = Tally::default();
+        self.results.iter().for_each(|result| tally.add(&result.status));
+        tally
+    }
+
+    pub fn by_fork(&self) -> BTreeMap<String, Tally> {
+        self.tally_by(|result| result.fork.clone())
+    }
+
+    // by the directory of the file, `.` for files right in the directory that was run
+    pub fn by_directory(&self) -> BTreeMap<String, Tally> {
+        self.tally_by(|result| match result.path.parent() {
+            Some(parent) if parent != Path::new("") => parent.display().to_string(),
+            _ => ".".to_string(),
+        })
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &TestResult> {
+        self.results
+            .iter()
+            .filter(|result| matches!(result.status, Status::Failed(_)))
+    }
+
+    fn tally_by(&self, key: impl Fn(&TestResult) -> String) -> BTreeMap<String, Tally> {
+        let mut tallies = BTreeMap::<_, Tally>::new();
+        for result in &self.results {
+            tallies.entry(key(result)).or_default().add(&result.status);
+        }
+        tallies
+    }
+}
+
+// the state before the transaction, in the block of the test
+fn pre_state(test: &StateTest, fork: Fork) -> InMemoryHost {
+    let env = &test.env;
+    let mut host = InMemoryHost::new(Env {
+        coinbase: env.current_coinbase,
+        number: env.current_number.saturating_to(),
+        timestamp: env.current_timestamp.saturating_to(),
+        gas_limit: env.current_gas_limit.saturating_to(),
+        base_fee: env.current_base_fee.unwrap_or_default(),
+        // 0x44 is DIFFICULTY before the Merge, whatever else the fixture carries
+        prev_randao: match env.current_random {
+            Some(random) if fork >= Fork::Paris => random,
+            _ => B256::from(env.current_difficulty),
+        },
+        chain_id: 1,
+        ..Env::default()
+    });
+    for (address, account) in &test.pre {
+        account.insert(*address, &mut host.db);
+    }
+    host
+}
+
+// runs the transaction of one post entry
+pub fn check(test: &StateTest, fork: Fork, post: &PostState) -> Status {
+    let tx = match Transaction::from_test(&test.transaction, post.indexes) {
+        Ok(tx) => tx,
+        Err(TxError::Unsupported(why)) => return Status::Skipped(why),
+        Err(err) => return Status::Failed(format!("{:?}", err)),
+    };
+    let mut host = pre_state(test, fork);
+    match (execute(fork, &mut host, &tx), &post.expect_exception) {
+        (Err(TxError::Unsupported(why)), _) => Status::Skipped(why),
+        (Err(_), Some(_)) => Status::Passed,
+        (Err(err), None) => Status::Failed(format!("transaction rejected: {:?}", err)),
+        (Ok(_), Some(expected)) => {
+            Status::Failed(format!("expected {expected}, the transaction ran"))
+        }
+        (Ok(outcome), None) => {
+            let root = state_root(&host.db);
+            let logs = logs_hash(&host.logs);
+            let halted = if outcome.success { "" } else { " (execution failed)" };
+            if root != post.hash {
+                Status::Failed(format!("state root {root}, expected {}{halted}", post.hash))
+            } else if logs != post.logs {
+                Status::Failed(format!("logs hash {logs}, expected {}{halted}", post.logs))
+            } else {
+                Status::Passed
+            }
+        }
+    }
+}
+
+// the results of the tests in one file, `path` is what they are reported under
+pub fn run_file(
+    file: &Path,
+    path: &Path,
+    filter: &Filter,
+) -> Result<Vec<TestResult>, StateTestError> {
+    let json = fs::read_to_string(file).map_err(StateTestError::Io)?;
+    let tests: BTreeMap<String, StateTest> =
+        serde_json::from_str(&json).map_err(StateTestError::Json)?;
+    let mut results = Vec::new();
+    for (name, test) in &tests {
+        if !filter.matches_test(path, name) {
+            continue;
+        }
+        for (fork_name, posts) in &test.post {
+            if !filter.matches_fork(fork_name) {
+                continue;
+            }
+            let fork = Fork::from_name(fork_name);
+            for (index, post) in posts.iter().enumerate() {
+                let status = match fork {
+                    Some(fork) => check(test, fork, post),
+                    None => Status::Skipped(format!("unknown fork {fork_name}")),
+                };
+                results.push(TestResult {
+                    path: path.to_path_buf(),
+                    name: name.clone(),
+                    fork: fork_name.clone(),
+                    index,
+                    status,
+                });
+            }
+        }
+    }
+    Ok(results)
+}
+
+// every `.json` file under `root`, sorted, or `root` itself if it's a file
+pub fn find_tests(root: &Path) -> Result<Vec<PathBuf>, StateTestError> {
+    if root.is_file() {
+        return Ok(vec![root.to_path_buf()]);
+    }
+    let mut files = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir).map_err(StateTestError::Io)? {
+            let path = entry.map_err(StateTestError::Io)?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|extension| extension == "json") {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+// runs the files under `root` on `jobs` threads
+pub fn run(root: &Path, filter: &Filter, jobs: usize) -> Result<Report, StateTestError> {
+    let files = find_tests(root)?;
+    let next = AtomicUsize::new(0);
+    let report = Mutex::new(Report::default());
+    thread::scope(|scope| {
+        for _ in 0..jobs.max(1) {
+            scope.spawn(|| {
+                while let Some(file) = files.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let path = file.strip_prefix(root).unwrap_or(file);
+                    let path = if path == Path::new("") { file } else { path };
+                    let result = run_file(file, path, filter);
+                    let mut report = report.lock().unwrap();
+                    match result {
+                        Ok(results) => report.results.extend(results),
+                        Err(err) => report.errors.push((path.to_path_buf(), err)),
+                    }
+                }
+            });
+        }
+    });
+    let mut report = report.into_inner().unwrap();
+    // the threads finish in any order
+    report.results.sort_by(|a, b| {
+        (&a.path, &a.name, &a.fork, a.index).cmp(&(&b.path, &b.name, &b.fork, b.index))
+    });
+    report.errors.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::U256;
+
+    use super::*;
+
+    // a transfer of 1 wei, once with a wrong root and once with too little gas
+    const FIXTURE: &str = r#"{
+      "transfer": {
+        "env": { "currentCoinbase": "0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba",
+                 "currentGasLimit": "0x05f5e100", "currentNumber": "0x01",
+                 "currentTimestamp": "0x03e8", "currentBaseFee": "0x0a",
+                 "currentRandom":
+                   "0x0000000000000000000000000000000000000000000000000000000000020000" },
+        "pre": {
+          "0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b": { "balance": "0x0de0b6b3a7640000",
+            "code": "0x", "nonce": "0x00", "storage": {} },
+          "0x1000000000000000000000000000000000000000": { "balance": "0x00",
+            "code": "0x600160005500", "nonce": "0x00", "storage": {} }
+        },
+        "transaction": { "data": ["0x"], "gasLimit": ["0x0186a0", "0x5207"], "value": ["0x01"],
+                         "gasPrice": "0x0a", "nonce": "0x00",
+                         "sender": "0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b",
+                         "to": "0x1000000000000000000000000000000000000000" },
+        "post": {
+          "Cancun": [
+            { "hash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+              "logs": "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347",
+              "indexes": { "data": 0, "gas": 0, "value": 0 } },
+            { "hash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+              "logs": "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347",
+              "indexes": { "data": 0, "gas": 1, "value": 0 },
+              "expectException": "TransactionException.INTRINSIC_GAS_TOO_LOW" }
+          ],
+          "EIP150": [
+            { "hash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+              "logs": "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347",
+              "indexes": { "data": 0, "gas": 0, "value": 0 } }
+          ]
+        }
+      }
+    }"#;
+
+    #[test]
+    fn test_run_reports_by_fork_and_directory() {
+        let root = std::env::temp_dir().join(format!("rsevm-statetest-{}", std::process::id()));
+        fs::create_dir_all(root.join("stTransfer")).unwrap();
+        fs::write(root.join("stTransfer/transfer.json"), FIXTURE).unwrap();
+        fs::write(root.join("broken.json"), "{").unwrap();
+        let report = run(&root, &Filter::default(), 2).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].0, Path::new("broken.json"));
+        let total = Tally {
+            passed: 1,
+            failed: 1,
+            skipped: 1,
+        };
+        assert_eq!(report.total(), total);
+        assert_eq!(report.by_directory()["stTransfer"], total);
+        assert_eq!(report.by_fork()["EIP150"].skipped, 1);
+        // the first Cancun entry ran, but its expected root is made up
+        let failure = report.failures().next().unwrap();
+        assert_eq!((failure.fork.as_str(), failure.index), ("Cancun", 0));
+        assert!(matches!(&failure.status, Status::Failed(why) if why.starts_with("state root")));
+    }
+
+    #[test]
+    fn test_difficulty_before_the_merge_random_after() {
+        let mut tests: BTreeMap<String, StateTest> = serde_json::from_str(FIXTURE).unwrap();
+        let test = tests.get_mut("transfer").unwrap();
+        test.env.current_difficulty = U256::from(5);
+        let difficulty = B256::from(U256::from(5));
+        let random = test.env.current_random.unwrap();
+        assert_eq!(pre_state(test, Fork::London).env.prev_randao, difficulty);
+        assert_eq!(pre_state(test, Fork::Paris).env.prev_randao, random);
+        assert_eq!(pre_state(test, Fork::Cancun).env.prev_randao, random);
+        test.env.current_random = None;
+        assert_eq!(pre_state(test, Fork::Cancun).env.prev_randao, difficulty);
+    }
+
+    #[test]
+    fn test_filter() {
+        let name = format!("rsevm-statetest-{}.json", std::process::id());
+        let file = std::env::temp_dir().join(name);
+        fs::write(&file, FIXTURE).unwrap();
+        let cancun = Filter {
+            forks: vec!["cancun".to_string()],
+            name: None,
+        };
+        assert_eq!(run_file(&file, Path::new("a.json"), &cancun).unwrap().len(), 2);
+        let other = Filter {
+            forks: vec![],
+            name: Some("transferFrom".to_string()),
+        };
+        assert!(run_file(&file, Path::new("a.json"), &other).unwrap().is_empty());
+        fs::remove_file(&file).unwrap();
+    }
+}
@@ -0,0 +1,538 @@
+// Runs one transaction against the state in a host, the part of a block a state test checks:
+//   validate   nonce, fees, intrinsic gas and balance, an invalid transaction changes nothing
+//   buy gas    gas limit * gas price from the sender, nonce + 1
+//   execute    value to the recipient and its code, or the initcode of a creation
+//   settle     unused gas and the refund back to the sender, the priority fee to the coinbase
+// Simplifications, on top of the ones of the interpreter (there are no CALL or CREATE frames, a
+// transaction whose code reaches one is `Unsupported`):
+//   - Tangerine Whistle and Spurious Dragon rules (empty account clearing, the code size limit,
+//     nonce 1 for new contracts) apply from Byzantium on, like in `GasSchedule`
+//   - blob (EIP-4844) and set-code (EIP-7702) transactions are not supported
+//   - precompiles are only warmed, a transaction sent to one runs no code
+
+use alloy_primitives::{Address, U256, keccak256};
+
+use crate::{
+    db::AccountInfo,
+    evm::{EVM, EvmError},
+    fork::Fork,
+    gas::GasSchedule,
+    host::{Host, InMemoryHost},
+    opcodes::info::opcode_info,
+    statetest::{
+        fixture::{AccessListItem, Indexes, TestTransaction},
+        rlp::{encode_bytes, encode_list, encode_u64},
+    },
+};
+
+const MAX_CODE_SIZE: usize = 24576;
+const MAX_INITCODE_SIZE: usize = 2 * MAX_CODE_SIZE;
+
+// why a transaction is invalid, or why it can't be run here
+#[derive(Debug, Clone, PartialEq)]
+pub enum TxError {
+    // the fixture asks for something this runner can't do, the test is skipped
+    Unsupported(String),
+    // a transaction type the fork doesn't have yet
+    TypeNotSupported,
+    NonceMismatch { account: u64, tx: u64 },
+    // the sender's nonce can't go up anymore (EIP-2681)
+    NonceOverflow,
+    // the sender has code (EIP-3607)
+    SenderNotEoa,
+    GasLimitAboveBlock { limit: u64, block: u64 },
+    IntrinsicGasTooLow { intrinsic: u64, limit: u64 },
+    FeeCapBelowBaseFee,
+    PriorityFeeAboveFeeCap,
+    InsufficientFunds { balance: U256, required: U256 },
+    InitcodeTooLarge(usize),
+}
+
+// one of the variants a `TestTransaction` lists
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transaction {
+    pub sender: Address,
+    // None for a contract creation
+    pub to: Option<Address>,
+    pub nonce: u64,
+    pub gas_limit: u64,
+    pub value: U256,
+    pub data: Vec<u8>,
+    pub gas_price: Option<U256>,
+    pub max_fee_per_gas: Option<U256>,
+    pub max_priority_fee_per_gas: Option<U256>,
+    // None for a legacy transaction
+    pub access_list: Option<Vec<AccessListItem>>,
+}
+
+impl Transaction {
+    pub fn from_test(tx: &TestTransaction, indexes: Indexes) -> Result<Self, TxError> {
+        if tx.blob_versioned_hashes.is_some() {
+            return Err(TxError::Unsupported("blob transaction".to_string()));
+        }
+        if tx.authorization_list.is_some() {
+            return Err(TxError::Unsupported("set-code transaction".to_string()));
+        }
+        let sender = tx
+            .sender
+            .ok_or_else(|| TxError::Unsupported("no sender, only a secret key".to_string()))?;
+        let to = match tx.to.trim() {
+            "" => None,
+            to => Some(to.parse().map_err(|_| TxError::Unsupported(format!("to: {to}")))?),
+        };
+        let out_of_range = |what: &str| TxError::Unsupported(format!("no {what} variant"));
+        let data = tx.data.get(indexes.data).ok_or_else(|| out_of_range("data"))?;
+        let gas_limit = tx.gas_limit.get(indexes.gas).ok_or_else(|| out_of_range("gas"))?;
+        let value = tx.value.get(indexes.value).ok_or_else(|| out_of_range("value"))?;
+        let access_list = tx.access_lists.as_ref().map(|lists| {
+            lists.get(indexes.data).cloned().flatten().unwrap_or_default()
+        });
+        Ok(Transaction {
+            sender,
+            to,
+            nonce: tx.nonce.saturating_to(),
+            gas_limit: gas_limit.saturating_to(),
+            value: *value,
+            data: data.to_vec(),
+            gas_price: tx.gas_price,
+            max_fee_per_gas: tx.max_fee_per_gas,
+            max_priority_fee_per_gas: tx.max_priority_fee_per_gas,
+            access_list,
+        })
+    }
+
+    pub fn is_create(&self) -> bool {
+        self.to.is_none()
+    }
+
+    // what the transaction pays before running anything
+    pub fn intrinsic_gas(&self, fork: Fork) -> u64 {
+        let zeros = self.data.iter().filter(|byte| **byte == 0).count() as u64;
+        let non_zeros = self.data.len() as u64 - zeros;
+        // EIP-2028
+        let non_zero_cost = if fork >= Fork::Istanbul { 16 } else { 68 };
+        let mut gas = 21000 + 4 * zeros + non_zero_cost * non_zeros;
+        if self.is_create() {
+            // EIP-2
+            if fork >= Fork::Homestead {
+                gas += 32000;
+            }
+            // EIP-3860
+            if fork >= Fork::Shanghai {
+                gas += 2 * (self.data.len() as u64).div_ceil(32);
+            }
+        }
+        // EIP-2930
+        for item in self.access_list.iter().flatten() {
+            gas += 2400 + 1900 * item.storage_keys.len() as u64;
+        }
+        gas
+    }
+
+    // the least a transaction pays for its calldata since Prague (EIP-7623)
+    pub fn floor_gas(&self) -> u64 {
+        let zeros = self.data.iter().filter(|byte| **byte == 0).count() as u64;
+        let tokens = zeros + 4 * (self.data.len() as u64 - zeros);
+        21000 + 10 * tokens
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    // false if the code reverted or halted, the transaction is still included
+    pub success: bool,
+    // after the refund
+    pub gas_used: u64,
+    pub output: Vec<u8>,
+}
+
+// the address of a contract created by `sender` with `nonce`
+pub fn create_address(sender: Address, nonce: u64) -> Address {
+    let rlp = encode_list(&[encode_bytes(sender.as_slice()), encode_u64(nonce)]);
+    Address::from_slice(&keccak256(rlp)[12..])
+}
+
+// the gas price the sender pays and the part of it the coinbase gets
+fn gas_prices(fork: Fork, tx: &Transaction, base_fee: U256) -> Result<(U256, U256), TxError> {
+    if (tx.access_list.is_some() && fork < Fork::Berlin)
+        || (tx.max_fee_per_gas.is_some() && fork < Fork::London)
+    {
+        return Err(TxError::TypeNotSupported);
+    }
+    let (max_fee, max_priority_fee) = match (tx.gas_price, tx.max_fee_per_gas) {
+        (Some(price), _) => (price, price),
+        (None, Some(max_fee)) => (max_fee, tx.max_priority_fee_per_gas.unwrap_or_default()),
+        (None, None) => return Err(TxError::Unsupported("no gas price".to_string())),
+    };
+    if max_priority_fee > max_fee {
+        return Err(TxError::PriorityFeeAboveFeeCap);
+    }
+    if max_fee < base_fee {
+        return Err(TxError::FeeCapBelowBaseFee);
+    }
+    let price = max_fee.min(base_fee + max_priority_fee);
+    Ok((price, price - base_fee))
+}
+
+// the precompiles of the fork, warm from the start since Berlin
+fn precompiles(fork: Fork) -> impl Iterator<Item = Address> {
+    let last = if fork >= Fork::Prague {
+        0x11
+    } else if fork >= Fork::Cancun {
+        0x0a
+    } else {
+        0x09
+    };
+    (1..=last).map(Address::with_last_byte)
+}
+
+// Validates and runs `tx`, leaving the state it ends in in `host`. `host.env` holds the block,
+// the origin and gas price are set here. An invalid transaction leaves the host as it was, after
+// an `Unsupported` one it is half run and only good to be thrown away.
+pub fn execute(fork: Fork, host: &mut InMemoryHost, tx: &Transaction) -> Result<Outcome, TxError> {
+    let base_fee = if fork >= Fork::London { host.env.base_fee } else { U256::ZERO };
+    let (price, priority_fee) = gas_prices(fork, tx, base_fee)?;
+
+    let intrinsic = tx.intrinsic_gas(fork);
+    let least = if fork >= Fork::Prague { intrinsic.max(tx.floor_gas()) } else { intrinsic };
+    if tx.gas_limit < least {
+        return Err(TxError::IntrinsicGasTooLow {
+            intrinsic: least,
+            limit: tx.gas_limit,
+        });
+    }
+    if tx.gas_limit > host.env.gas_limit {
+        return Err(TxError::GasLimitAboveBlock {
+            limit: tx.gas_limit,
+            block: host.env.gas_limit,
+        });
+    }
+    if tx.is_create() && fork >= Fork::Shanghai && tx.data.len() > MAX_INITCODE_SIZE {
+        return Err(TxError::InitcodeTooLarge(tx.data.len()));
+    }
+    if !host.code(tx.sender).is_empty() {
+        return Err(TxError::SenderNotEoa);
+    }
+    let nonce = host.nonce(tx.sender);
+    if nonce == u64::MAX {
+        return Err(TxError::NonceOverflow);
+    }
+    if nonce != tx.nonce {
+        return Err(TxError::NonceMismatch {
+            account: nonce,
+            tx: tx.nonce,
+        });
+    }
+    let max_fee = tx.max_fee_per_gas.or(tx.gas_price).unwrap_or_default();
+    let balance = host.balance(tx.sender);
+    let required = U256::from(tx.gas_limit)
+        .checked_mul(max_fee)
+        .and_then(|cost| cost.checked_add(tx.value));
+    if required.is_none_or(|required| required > balance) {
+        return Err(TxError::InsufficientFunds {
+            balance,
+            required: required.unwrap_or(U256::MAX),
+        });
+    }
+
+    // buy the gas, from here on the transaction is part of the block
+    host.env.origin = tx.sender;
+    host.env.gas_price = price;
+    let sender = host.db.account_mut(tx.sender);
+    sender.info.balance -= U256::from(tx.gas_limit) * price;
+    sender.info.nonce += 1;
+    sender.exists = true;
+
+    let address = tx.to.unwrap_or_else(|| create_address(tx.sender, nonce));
+    host.warm_account(tx.sender);
+    host.warm_account(address);
+    // EIP-3651
+    if fork >= Fork::Shanghai {
+        host.warm_account(host.env.coinbase);
+    }
+    if fork >= Fork::Berlin {
+        precompiles(fork).for_each(|precompile| host.warm_account(precompile));
+    }
+    for item in tx.access_list.iter().flatten() {
+        host.warm_account(item.address);
+        for key in &item.storage_keys {
+            host.warm_slot(item.address, *key);
+        }
+    }
+
+    // what a failed execution goes back to
+    let snapshot = host.clone();
+    let mut remaining = tx.gas_limit - intrinsic;
+    let mut refund = 0;
+    let mut output = Vec::new();
+    let success = run(fork, host, tx, address, &mut remaining, &mut refund, &mut output)?;
+    if !success {
+        *host = snapshot;
+    }
+
+    // accounts that destructed go away with the transaction, since Cancun (EIP-6780) only
+    // the ones it created
+    let destructed = std::mem::take(&mut host.destructed);
+    for destructed_address in &destructed {
+        if fork < Fork::Cancun || (tx.is_create() && *destructed_address == address) {
+            *host.db.account_mut(*destructed_address) = Default::default();
+        }
+    }
+
+    let mut gas_used = tx.gas_limit - remaining;
+    if success {
+        // EIP-3529
+        let quotient = if fork >= Fork::London { 5 } else { 2 };
+        gas_used -= refund.min(gas_used / quotient);
+    }
+    if fork >= Fork::Prague {
+        gas_used = gas_used.max(tx.floor_gas());
+    }
+    let sender = host.db.account_mut(tx.sender);
+    sender.info.balance += U256::from(tx.gas_limit - gas_used) * price;
+    let coinbase = host.db.account_mut(host.env.coinbase);
+    coinbase.info.balance += U256::from(gas_used) * priority_fee;
+    coinbase.exists = true;
+
+    // EIP-161: an account the transaction touched and left empty is deleted
+    if fork >= Fork::Byzantium {
+        for touched in [tx.sender, address, host.env.coinbase] {
+            let account = host.db.account_mut(touched);
+            if account.exists && account.info.is_empty() {
+                *account = Default::default();
+            }
+        }
+    }
+    Ok(Outcome {
+        success,
+        gas_used,
+        output,
+    })
+}
+
+// the value transfer and the code, false if the state has to be rolled back, `Unsupported` if the
+// code stopped on an instruction the interpreter can't run yet
+fn run(
+    fork: Fork,
+    host: &mut InMemoryHost,
+    tx: &Transaction,
+    address: Address,
+    remaining: &mut u64,
+    refund: &mut u64,
+    output: &mut Vec<u8>,
+) -> Result<bool, TxError> {
+    let code = match tx.to {
+        Some(to) => host.code(to),
+        None => {
+            // a contract is already there
+            if host.nonce(address) != 0 || !host.code(address).is_empty() {
+                *remaining = 0;
+                return Ok(false);
+            }
+            tx.data.clone()
+        }
+    };
+    host.db.account_mut(tx.sender).info.balance -= tx.value;
+    let recipient = host.db.account_mut(address);
+    recipient.info.balance += tx.value;
+    recipient.exists = true;
+    if tx.is_create() && fork >= Fork::Byzantium {
+        recipient.info.nonce = 1;
+    }
+    if code.is_empty() {
+        return Ok(true);
+    }
+
+    let calldata = if tx.is_create() { Vec::new() } else { tx.data.clone() };
+    let mut evm = EVM::with_host(
+        std::mem::take(host),
+        tx.sender,
+        code,
+        *remaining,
+        tx.value,
+        calldata,
+    );
+    evm.address = address;
+    evm.gas_schedule = GasSchedule::for_fork(fork);
+    let result = evm.run();
+    // a failed instruction leaves pc on itself
+    let failed_at = evm.program.get(evm.pc).copied();
+    *host = std::mem::take(&mut evm.host);
+    *output = std::mem::take(&mut evm.output);
+    if let Err(err) = result {
+        if let Some(why) = unsupported(&err, failed_at) {
+            return Err(TxError::Unsupported(why));
+        }
+        *remaining = 0;
+        return Ok(false);
+    }
+    *remaining = evm.gas;
+    if evm.revert_flag {
+        return Ok(false);
+    }
+    *refund = evm.refund;
+    // SELFDESTRUCT refunded 24000 once per account before London (EIP-3529)
+    if fork < Fork::London {
+        let mut destructed = host.destructed.clone();
+        destructed.sort();
+        destructed.dedup();
+        *refund += 24000 * destructed.len() as u64;
+    }
+
+    if tx.is_create() {
+        let deposit = 200 * output.len() as u64;
+        let too_large = fork >= Fork::Byzantium && output.len() > MAX_CODE_SIZE;
+        // EIP-3541
+        let reserved = fork >= Fork::London && output.first() == Some(&0xef);
+        if too_large || reserved || (deposit > *remaining && fork >= Fork::Homestead) {
+            *remaining = 0;
+            return Ok(false);
+        }
+        // Frontier leaves a contract without code when the deposit can't be paid
+        if deposit <= *remaining {
+            *remaining -= deposit;
+            let account = host.db.account_mut(address);
+            let (balance, nonce) = (account.info.balance, account.info.nonce);
+            host.db.insert_account(address, AccountInfo::new(balance, nonce, output.clone()));
+        }
+    }
+    Ok(true)
+}
+
+// an error that isn't the code failing but the interpreter missing something, like a CALL or a
+// CREATE, which it has no frames for. `opcode` is the instruction that failed
+fn unsupported(err: &EvmError, opcode: Option<u8>) -> Option<String> {
+    match err {
+        EvmError::UnsupportedCall { target } => Some(format!("call into {target}")),
+        EvmError::UnknownOpcode { .. } => {
+            let info = opcode_info(opcode?).filter(|info| !info.implemented)?;
+            Some(format!("{} is not implemented", info.mnemonic))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{address, hex};
+
+    use super::*;
+    use crate::host::Env;
+
+    const SENDER: Address = address!("a94f5374fce5edbc8e2a8697c15331677e6ebf0b");
+    const CONTRACT: Address = address!("1000000000000000000000000000000000000000");
+    const COINBASE: Address = address!("2adc25665018aa1fe0e6bc666dac8fc2697ff9ba");
+
+    fn host(code: Vec<u8>) -> InMemoryHost {
+        let mut host = InMemoryHost::new(Env {
+            coinbase: COINBASE,
+            gas_limit: 10_000_000,
+            base_fee: U256::from(10),
+            ..Env::default()
+        });
+        host.db.insert_account(SENDER, AccountInfo::new(U256::from(10_000_000), 0, vec![]));
+        host.db.insert_account(CONTRACT, AccountInfo::new(U256::ZERO, 0, code));
+        host
+    }
+
+    fn call(data: Vec<u8>, gas_limit: u64, value: u64) -> Transaction {
+        Transaction {
+            sender: SENDER,
+            to: Some(CONTRACT),
+            nonce: 0,
+            gas_limit,
+            value: U256::from(value),
+            data,
+            gas_price: Some(U256::from(12)),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            access_list: None,
+        }
+    }
+
+    #[test]
+    fn test_call_pays_gas_and_transfers_value() {
+        // PUSH1 1 PUSH1 0 SSTORE
+        let mut host = host(hex!("6001600055").to_vec());
+        let tx = call(vec![0x00, 0x01], 100_000, 5);
+        let outcome = execute(Fork::Cancun, &mut host, &tx).unwrap();
+        assert!(outcome.success);
+        // 21000 + 4 + 16 for the data, 2 * 3 for the pushes, 22100 for a cold new slot
+        assert_eq!(outcome.gas_used, 21020 + 6 + 22100);
+        assert_eq!(host.nonce(SENDER), 1);
+        let cost = U256::from(outcome.gas_used) * U256::from(12);
+        assert_eq!(host.balance(SENDER), U256::from(10_000_000 - 5) - cost);
+        assert_eq!(host.balance(CONTRACT), U256::from(5));
+        assert_eq!(host.sload(CONTRACT, U256::ZERO), U256::from(1));
+        // the coinbase only gets the tip above the base fee
+        assert_eq!(host.balance(COINBASE), U256::from(outcome.gas_used * 2));
+    }
+
+    #[test]
+    fn test_failed_execution_keeps_only_the_gas_payment() {
+        // SSTORE then REVERT
+        let mut host = host(hex!("600160005560006000fd").to_vec());
+        let outcome = execute(Fork::Cancun, &mut host, &call(vec![], 100_000, 5)).unwrap();
+        assert!(!outcome.success);
+        assert_eq!(host.nonce(SENDER), 1);
+        assert_eq!(host.balance(CONTRACT), U256::ZERO);
+        assert_eq!(host.sload(CONTRACT, U256::ZERO), U256::ZERO);
+
+        // an invalid opcode burns the whole gas limit
+        let mut host = self::host(vec![0xfe]);
+        let outcome = execute(Fork::Cancun, &mut host, &call(vec![], 100_000, 0)).unwrap();
+        assert_eq!(outcome.gas_used, 100_000);
+    }
+
+    #[test]
+    fn test_call_or_create_is_unsupported_not_failed() {
+        // PUSH1 0 (x8) CALL
+        let mut host = host(hex!("60006000600060006000600060006000f1").to_vec());
+        let outcome = execute(Fork::Cancun, &mut host, &call(vec![], 100_000, 0));
+        assert_eq!(outcome, Err(TxError::Unsupported("CALL is not implemented".to_string())));
+
+        // an opcode that doesn't exist is still the code failing
+        let mut host = self::host(vec![0x0c]);
+        let outcome = execute(Fork::Cancun, &mut host, &call(vec![], 100_000, 0)).unwrap();
+        assert!(!outcome.success);
+    }
+
+    #[test]
+    fn test_invalid_transactions_change_nothing() {
+        let mut host = host(vec![]);
+        let before = host.clone();
+        let low_gas = execute(Fork::Cancun, &mut host, &call(vec![1], 21000, 0));
+        assert_eq!(
+            low_gas,
+            Err(TxError::IntrinsicGasTooLow {
+                intrinsic: 21016,
+                limit: 21000
+            })
+        );
+        let rich = call(vec![], 21000, 10_000_000);
+        assert!(matches!(
+            execute(Fork::Cancun, &mut host, &rich),
+            Err(TxError::InsufficientFunds { .. })
+        ));
+        let replay = Transaction { nonce: 1, ..call(vec![], 21000, 0) };
+        assert_eq!(
+            execute(Fork::Cancun, &mut host, &replay),
+            Err(TxError::NonceMismatch { account: 0, tx: 1 })
+        );
+        assert_eq!(host, before);
+    }
+
+    #[test]
+    fn test_create_deploys_the_returned_code() {
+        // returns the single byte 0x00: PUSH1 0 PUSH1 0 MSTORE8 PUSH1 1 PUSH1 0 RETURN
+        let initcode = hex!("600060005360016000f3").to_vec();
+        let mut host = host(vec![]);
+        let tx = Transaction { to: None, ..call(initcode, 100_000, 0) };
+        let outcome = execute(Fork::Cancun, &mut host, &tx).unwrap();
+        assert!(outcome.success);
+        let created = create_address(SENDER, 0);
+        assert_eq!(created, address!("6295ee1b4f6dd65047762f924ecd367c17eabf8f"));
+        assert_eq!(host.code(created), vec![0x00]);
+        assert_eq!(host.nonce(created), 1);
+    }
+}
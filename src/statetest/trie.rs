@@ -0,0 +1,184 @@
+// The roots a state test is checked against: the Merkle Patricia trie of the accounts and
+// keccak256 of the RLP of the logs.
+// Only roots are computed, the trie is built from scratch every time and never kept:
+//   state trie    keccak256(address) -> rlp([nonce, balance, storage root, code hash])
+//   storage trie  keccak256(slot) -> rlp(value), zero slots are left out
+
+use std::collections::HashMap;
+
+use alloy_primitives::{B256, U256, keccak256};
+
+use crate::{
+    db::CacheDB,
+    evm::Log,
+    statetest::rlp::{encode_bytes, encode_list, encode_u64, encode_u256},
+};
+
+fn nibbles(key: &[u8]) -> Vec<u8> {
+    key.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]).collect()
+}
+
+// the hex-prefix encoding of a path, its first nibble says whether it ends in a leaf and whether
+// the path has an odd length
+fn compact(path: &[u8], leaf: bool) -> Vec<u8> {
+    let mut nibbles = vec![2 * leaf as u8 + (path.len() % 2) as u8];
+    if path.len().is_multiple_of(2) {
+        nibbles.push(0);
+    }
+    nibbles.extend_from_slice(path);
+    nibbles.chunks(2).map(|pair| (pair[0] << 4) | pair[1]).collect()
+}
+
+// a node shorter than a hash is embedded in its parent, a longer one is referenced by its hash
+fn reference(node: Vec<u8>) -> Vec<u8> {
+    if node.len() < 32 {
+        return node;
+    }
+    encode_bytes(keccak256(&node).as_slice())
+}
+
+// the RLP of the node holding `entries`, sorted by key, their keys agree on the first `depth`
+// nibbles
+fn node(entries: &[(Vec<u8>, Vec<u8>)], depth: usize) -> Vec<u8> {
+    if let [(key, value)] = entries {
+        return encode_list(&[encode_bytes(&compact(&key[depth..], true)), encode_bytes(value)]);
+    }
+    // the first and the last key share whatever all of them share
+    let (first, last) = (&entries[0].0, &entries[entries.len() - 1].0);
+    let shared = first[depth..]
+        .iter()
+        .zip(&last[depth..])
+        .take_while(|(a, b)| a == b)
+        .count();
+    if shared > 0 {
+        let path = encode_bytes(&compact(&first[depth..depth + shared], false));
+        return encode_list(&[path, reference(node(entries, depth + shared))]);
+    }
+
+    // a key that ends here sorts first and goes into the branch itself
+    let mut rest = entries;
+    let mut value = encode_bytes(&[]);
+    if first.len() == depth {
+        value = encode_bytes(&entries[0].1);
+        rest = &entries[1..];
+    }
+    let mut items = Vec::with_capacity(17);
+    for nibble in 0..16 {
+        let end = rest.iter().position(|(key, _)| key[depth] != nibble).unwrap_or(rest.len());
+        let (children, tail) = rest.split_at(end);
+        items.push(match children {
+            [] => encode_bytes(&[]),
+            _ => reference(node(children, depth + 1)),
+        });
+        rest = tail;
+    }
+    items.push(value);
+    encode_list(&items)
+}
+
+// the root of a trie holding `entries`, every key at most once
+pub fn trie_root(entries: impl IntoIterator<Item = (Vec<u8>, Vec<u8>)>) -> B256 {
+    let mut entries: Vec<_> = entries
+        .into_iter()
+        .map(|(key, value)| (nibbles(&key), value))
+        .collect();
+    if entries.is_empty() {
+        return keccak256(encode_bytes(&[]));
+    }
+    entries.sort();
+    keccak256(node(&entries, 0))
+}
+
+pub fn storage_root(storage: &HashMap<U256, U256>) -> B256 {
+    trie_root(
+        storage
+            .iter()
+            .filter(|(_, value)| !value.is_zero())
+            .map(|(slot, value)| {
+                (keccak256(slot.to_be_bytes::<32>()).to_vec(), encode_u256(*value))
+            }),
+    )
+}
+
+// the root of every account in the cache, which has to hold the whole state, e.g. an `InMemoryDB`
+pub fn state_root<D>(db: &CacheDB<D>) -> B256 {
+    trie_root(db.accounts.iter().filter(|(_, account)| account.exists).map(|(address, account)| {
+        let value = encode_list(&[
+            encode_u64(account.info.nonce),
+            encode_u256(account.info.balance),
+            encode_bytes(storage_root(&account.storage).as_slice()),
+            encode_bytes(account.info.code_hash.as_slice()),
+        ]);
+        (keccak256(address).to_vec(), value)
+    }))
+}
+
+// keccak256(rlp([[address, [topics], data], ...]))
+pub fn logs_hash(logs: &[Log]) -> B256 {
+    let logs: Vec<_> = logs
+        .iter()
+        .map(|log| {
+            let topics: Vec<_> = log
+                .topics
+                .iter()
+                .map(|topic| encode_bytes(&topic.to_be_bytes::<32>()))
+                .collect();
+            encode_list(&[
+                encode_bytes(log.address.as_slice()),
+                encode_list(&topics),
+                encode_bytes(&log.data),
+            ])
+        })
+        .collect();
+    keccak256(encode_list(&logs))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Address, b256};
+
+    use super::*;
+    use crate::db::{AccountInfo, InMemoryDB};
+
+    fn root(entries: &[(&str, &str)]) -> B256 {
+        trie_root(
+            entries
+                .iter()
+                .map(|(key, value)| (key.as_bytes().to_vec(), value.as_bytes().to_vec())),
+        )
+    }
+
+    #[test]
+    fn test_known_roots() {
+        let empty = b256!("56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421");
+        assert_eq!(trie_root([]), empty);
+        // "dog" ends in a branch that "dogglesworth" continues from
+        assert_eq!(
+            root(&[("doe", "reindeer"), ("dog", "puppy"), ("dogglesworth", "cat")]),
+            b256!("8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3")
+        );
+        assert_eq!(
+            root(&[("do", "verb"), ("horse", "stallion"), ("doge", "coin"), ("dog", "puppy")]),
+            b256!("5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84")
+        );
+        assert_eq!(
+            logs_hash(&[]),
+            b256!("1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347")
+        );
+    }
+
+    #[test]
+    fn test_state_root_ignores_missing_accounts_and_zero_slots() {
+        let address = Address::repeat_byte(0x11);
+        let mut db = InMemoryDB::default();
+        db.insert_account(address, AccountInfo::new(U256::from(1), 0, vec![]));
+        let root = state_root(&db);
+        assert_ne!(root, trie_root([]));
+
+        db.insert_storage(address, U256::from(1), U256::ZERO);
+        db.account_mut(Address::repeat_byte(0x22));
+        assert_eq!(state_root(&db), root);
+        db.insert_storage(address, U256::from(1), U256::from(1));
+        assert_ne!(state_root(&db), root);
+    }
+}
@@ -0,0 +1,361 @@
+// The call tree of a run, in the shape of geth's `callTracer`:
+//   {"type":"CALL","from":"0x..","to":"0x..","value":"0x0","gas":"0x2710","gasUsed":"0x5208",
+//    "input":"0x..","output":"0x..","calls":[{"type":"EXTCALL", ...}],"logs":[...]}
+// The root frame is the running program itself, every EXTCALL (and later CREATE) nests under it.
+// As in geth, a frame that fails takes its logs and those of its sub-calls with it, and a revert
+// carrying `Error(string)` or `Panic(uint256)` gets its reason decoded into `revertReason`.
+// Built `with_abi`, logs of the events in the ABI also carry the decoded event, e.g.
+//   "event":"Transfer(from: 0x.., to: 0x.., value: 100)"
+//   evm.set_inspector(CallTracer::default());
+//   let result = evm.run();
+//   let tree = evm.take_inspector::<CallTracer>().unwrap().into_frame(&evm, &result);
+
+use alloy_primitives::{Address, B256, Bytes, U256};
+use serde::{Serialize, Serializer};
+
+pub use crate::abi::ERROR_SELECTOR;
+use crate::{
+    abi::{Abi, Revert},
+    evm::{EVM, EvmError, Log},
+    host::Host,
+    inspector::{CallInputs, CallOutcome, CallStatus, CreateInputs, CreateOutcome, Inspector},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum CallKind {
+    Call,
+    ExtCall,
+    Create,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallLog {
+    pub address: Address,
+    pub topics: Vec<B256>,
+    pub data: Bytes,
+    // how many sub-calls of the frame came before the log
+    #[serde(serialize_with = "hex_u64")]
+    pub position: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallFrame {
+    #[serde(rename = "type")]
+    pub kind: CallKind,
+    pub from: Address,
+    // None for a creation that failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<Address>,
+    pub value: U256,
+    #[serde(serialize_with = "hex_u64")]
+    pub gas: u64,
+    #[serde(serialize_with = "hex_u64")]
+    pub gas_used: u64,
+    pub input: Bytes,
+    #[serde(skip_serializing_if = "<[u8]>::is_empty")]
+    pub output: Bytes,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revert_reason: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<CallFrame>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub logs: Vec<CallLog>,
+}
+
+fn hex_u64<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{:#x}", value))
+}
+
+// the message of an `Error(string)` revert or what a panic code means, None for anything else
+// (custom errors, no data)
+pub fn decode_revert_reason(output: &[u8]) -> Option<String> {
+    match Revert::decode(output, &[]) {
+        revert @ (Revert::Error(_) | Revert::Panic(_)) => Some(revert.to_string()),
+        _ => None,
+    }
+}
+
+impl CallFrame {
+    fn new(
+        kind: CallKind,
+        from: Address,
+        to: Option<Address>,
+        value: U256,
+        gas: u64,
+        input: &[u8],
+    ) -> Self {
+        Self {
+            kind,
+            from,
+            to,
+            value,
+            gas,
+            gas_used: 0,
+            input: Bytes::copy_from_slice(input),
+            output: Bytes::new(),
+            error: None,
+            revert_reason: None,
+            calls: Vec::new(),
+            logs: Vec::new(),
+        }
+    }
+
+    fn finish(&mut self, status: CallStatus, output: &[u8], gas_used: u64, error: Option<String>) {
+        self.gas_used = gas_used;
+        self.output = Bytes::copy_from_slice(output);
+        match status {
+            CallStatus::Success => {}
+            CallStatus::Revert => {
+                self.error = Some("execution reverted".to_string());
+                self.revert_reason = decode_revert_reason(output);
+            }
+            CallStatus::Halt => {
+                self.error = Some(error.unwrap_or_else(|| "execution halted".to_string()));
+            }
+        }
+        if status != CallStatus::Success {
+            self.clear_logs();
+        }
+    }
+
+    fn clear_logs(&mut self) {
+        self.logs.clear();
+        for call in &mut self.calls {
+            call.clear_logs();
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CallTracer {
+    // the frames still running, the root first
+    frames: Vec<CallFrame>,
+    // the events logs are decoded with, none by default
+    abi: Abi,
+}
+
+impl CallTracer {
+    pub fn with_abi(abi: Abi) -> Self {
+        Self {
+            frames: Vec::new(),
+            abi,
+        }
+    }
+
+    // the tree once the run is over, `result` is what `EVM::run` returned
+    pub fn into_frame<H: Host>(mut self, vm: &EVM<H>, result: &Result<(), EvmError>) -> CallFrame {
+        self.enter_root(vm);
+        // a run that failed inside a sub-call leaves it open, close it as a halt
+        while self.frames.len() > 1 {
+            self.exit(|frame| {
+                let gas = frame.gas;
+                frame.finish(CallStatus::Halt, &[], gas, None);
+            });
+        }
+        let mut root = self.frames.pop().unwrap();
+        let (status, error) = match result {
+            Err(err) => (CallStatus::Halt, Some(format!("{:?}", err))),
+            Ok(()) if vm.revert_flag => (CallStatus::Revert, None),
+            Ok(()) => (CallStatus::Success, None),
+        };
+        // a halt hands back nothing
+        let output = if status == CallStatus::Halt { &[][..] } else { &vm.output };
+        root.finish(status, output, vm.gas_used, error);
+        root
+    }
+
+    fn enter_root<H: Host>(&mut self, vm: &EVM<H>) {
+        if self.frames.is_empty() {
+            let root = CallFrame::new(
+                CallKind::Call,
+                vm.sender,
+                Some(vm.address),
+                vm.value,
+                vm.gas,
+                &vm.calldata,
+            );
+            self.frames.push(root);
+        }
+    }
+
+    // closes the running sub-call and hangs it under its parent
+    fn exit(&mut self, finish: impl FnOnce(&mut CallFrame)) {
+        // the root is only closed by `into_frame`
+        if self.frames.len() < 2 {
+            return;
+        }
+        let mut frame = self.frames.pop().unwrap();
+        finish(&mut frame);
+        self.frames.last_mut().unwrap().calls.push(frame);
+    }
+}
+
+impl<H: Host> Inspector<H> for CallTracer {
+    fn step(&mut self, vm: &EVM<H>) {
+        self.enter_root(vm);
+    }
+
+    fn call(&mut self, vm: &EVM<H>, inputs: &CallInputs) {
+        self.enter_root(vm);
+        self.frames.push(CallFrame::new(
+            CallKind::ExtCall,
+            inputs.caller,
+            Some(inputs.target),
+            inputs.value,
+            inputs.gas_limit,
+            &inputs.input,
+        ));
+    }
+
+    fn call_end(&mut self, _vm: &EVM<H>, _inputs: &CallInputs, outcome: &CallOutcome) {
+        self.exit(|frame| frame.finish(outcome.status, &outcome.output, outcome.gas_used, None));
+    }
+
+    fn create(&mut self, vm: &EVM<H>, inputs: &CreateInputs) {
+        self.enter_root(vm);
+        self.frames.push(CallFrame::new(
+            CallKind::Create,
+            inputs.caller,
+            None,
+            inputs.value,
+            inputs.gas_limit,
+            &inputs.init_code,
+        ));
+    }
+
+    fn create_end(&mut self, _vm: &EVM<H>, _inputs: &CreateInputs, outcome: &CreateOutcome) {
+        self.exit(|frame| {
+            frame.to = outcome.address;
+            frame.finish(outcome.status, &outcome.output, outcome.gas_used, None);
+        });
+    }
+
+    fn log(&mut self, vm: &EVM<H>, log: &Log) {
+        self.enter_root(vm);
+        let frame = self.frames.last_mut().unwrap();
+        frame.logs.push(CallLog {
+            address: log.address,
+            topics: log.topics.iter().map(|topic| B256::from(*topic)).collect(),
+            data: Bytes::copy_from_slice(&log.data),
+            position: frame.calls.len() as u64,
+            event: self.abi.decode_log(log).map(|event| event.to_string()),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Error(string) with "no", the way solc lays it out
+    fn error_no() -> Vec<u8> {
+        let mut output = ERROR_SELECTOR.to_vec();
+        output.extend(U256::from(32).to_be_bytes::<32>());
+        output.extend(U256::from(2).to_be_bytes::<32>());
+        let mut text = [0u8; 32];
+        text[..2].copy_from_slice(b"no");
+        output.extend(text);
+        output
+    }
+
+    fn trace(program: Vec<u8>) -> CallFrame {
+        trace_with(CallTracer::default(), program)
+    }
+
+    fn trace_with(tracer: CallTracer, program: Vec<u8>) -> CallFrame {
+        let mut vm = EVM::new(Address::with_last_byte(1), program, 10_000, U256::ZERO, vec![0xAA]);
+        vm.set_inspector(tracer);
+        let result = vm.run();
+        vm.take_inspector::<CallTracer>().unwrap().into_frame(&vm, &result)
+    }
+
+    #[test]
+    fn test_decode_revert_reason() {
+        assert_eq!(decode_revert_reason(&error_no()), Some("no".to_string()));
+        assert_eq!(decode_revert_reason(&[]), None);
+        // a custom error, or a length running past the data
+        assert_eq!(decode_revert_reason(&[0xde, 0xad, 0xbe, 0xef]), None);
+        assert_eq!(decode_revert_reason(&error_no()[..4 + 64]), None);
+        // Panic(0x12)
+        let mut panic = crate::abi::PANIC_SELECTOR.to_vec();
+        panic.extend(U256::from(0x12).to_be_bytes::<32>());
+        assert_eq!(
+            decode_revert_reason(&panic),
+            Some("panic: division or modulo by zero (0x12)".to_string())
+        );
+    }
+
+    #[test]
+    fn test_logs_of_abi_events_are_decoded() {
+        let abi = Abi::parse(["event Deposit(uint256 amount)"]).unwrap();
+        // MSTORE 100 at 0, LOG1 of that word with the Deposit topic, STOP
+        let mut program = vec![0x60, 0x64, 0x60, 0x00, 0x52, 0x7F];
+        program.extend(abi.events[0].topic0());
+        program.extend([0x60, 0x20, 0x60, 0x00, 0xA1, 0x00]);
+        let frame = trace_with(CallTracer::with_abi(abi), program);
+        assert_eq!(frame.logs[0].event.as_deref(), Some("Deposit(amount: 100)"));
+        let json = serde_json::to_value(&frame).unwrap();
+        assert_eq!(json["logs"][0]["event"], "Deposit(amount: 100)");
+        // without the ABI the log stays raw
+        assert!(trace(vec![0x60, 0x00, 0x60, 0x00, 0xA0, 0x00]).logs[0].event.is_none());
+    }
+
+    #[test]
+    fn test_root_frame_keeps_its_logs() {
+        // LOG0 with no data, STOP
+        let frame = trace(vec![0x60, 0x00, 0x60, 0x00, 0xA0, 0x00]);
+        assert_eq!(frame.kind, CallKind::Call);
+        assert_eq!(frame.from, Address::with_last_byte(1));
+        assert_eq!(frame.gas, 10_000);
+        assert_eq!(frame.gas_used, 3 + 3 + 375);
+        assert_eq!(frame.logs.len(), 1);
+        assert_eq!(
+            serde_json::to_value(&frame).unwrap(),
+            serde_json::json!({
+                "type": "CALL",
+                "from": Address::with_last_byte(1),
+                "to": Address::with_last_byte(1),
+                "value": "0x0",
+                "gas": "0x2710",
+                "gasUsed": "0x17d",
+                "input": "0xaa",
+                "logs": [{
+                    "address": Address::with_last_byte(1),
+                    "topics": [],
+                    "data": "0x",
+                    "position": "0x0",
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn test_revert_drops_logs_and_decodes_the_reason() {
+        let reason = error_no();
+        // LOG0, then copy the reason into memory with one MSTORE8 per byte and revert with it
+        let mut program = vec![0x60, 0x00, 0x60, 0x00, 0xA0];
+        for (i, byte) in reason.iter().enumerate() {
+            program.extend([0x60, *byte, 0x60, i as u8, 0x53]);
+        }
+        program.extend([0x60, reason.len() as u8, 0x60, 0x00, 0xFD]);
+        let frame = trace(program);
+        assert_eq!(frame.error.as_deref(), Some("execution reverted"));
+        assert_eq!(frame.revert_reason.as_deref(), Some("no"));
+        assert_eq!(frame.output.to_vec(), reason);
+        assert!(frame.logs.is_empty());
+    }
+
+    #[test]
+    fn test_halt_has_the_error_and_no_output() {
+        let frame = trace(vec![0x01]);
+        assert_eq!(frame.error.as_deref(), Some("StackUnderflow"));
+        assert!(frame.output.is_empty());
+    }
+}
@@ -0,0 +1,367 @@
+// Which instructions ran, and which way every conditional jump (JUMPI, RJUMPI) went.
+// Results are kept per bytecode, keyed by its hash, so runs of many programs can share one
+// collector and collectors can be merged:
+//   let mut total = Coverage::default();
+//   for program in programs {
+//       evm.set_inspector(Coverage::default());
+//       evm.run();
+//       total.merge(evm.take_inspector::<Coverage>().unwrap());
+//   }
+//   print!("{}", total.report());
+// With the bytecode's solc source map and its source files, `BytecodeCoverage::lcov` writes an
+// LCOV tracefile (`genhtml`, IDE coverage gutters) with line and branch hits.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, btree_map::Entry},
+    fmt,
+};
+
+use alloy_primitives::{B256, keccak256};
+
+use crate::{
+    disassembler::{Instruction, disassemble_program},
+    evm::{EVM, EvmError},
+    host::Host,
+    inspector::Inspector,
+    opcodes::opcodes::{JUMPI, RJUMPI},
+    sourcemap::{SourceFile, SourceMap},
+};
+
+fn is_branch(opcode: u8) -> bool {
+    opcode == JUMPI || opcode == RJUMPI
+}
+
+// how often a conditional jump went each way
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BranchHits {
+    pub taken: u64,
+    pub not_taken: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BranchDirections {
+    Neither,
+    Taken,
+    NotTaken,
+    Both,
+}
+
+impl BranchHits {
+    pub fn directions(&self) -> BranchDirections {
+        match (self.taken > 0, self.not_taken > 0) {
+            (false, false) => BranchDirections::Neither,
+            (true, false) => BranchDirections::Taken,
+            (false, true) => BranchDirections::NotTaken,
+            (true, true) => BranchDirections::Both,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CoverageStats {
+    pub instructions: usize,
+    pub executed: usize,
+    pub branches: usize,
+    // out of two per branch
+    pub directions_covered: usize,
+}
+
+impl fmt::Display for CoverageStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = |part: usize, whole: usize| {
+            if whole == 0 { 100.0 } else { part as f64 * 100.0 / whole as f64 }
+        };
+        write!(
+            f,
+            "instructions {}/{} ({:.1}%), branch directions {}/{} ({:.1}%)",
+            self.executed,
+            self.instructions,
+            percent(self.executed, self.instructions),
+            self.directions_covered,
+            self.branches * 2,
+            percent(self.directions_covered, self.branches * 2)
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BytecodeCoverage {
+    pub code: Vec<u8>,
+    // pc -> how often the instruction there ran
+    pub hits: BTreeMap<usize, u64>,
+    // pc of a JUMPI or RJUMPI -> the way it went
+    pub branches: BTreeMap<usize, BranchHits>,
+}
+
+impl BytecodeCoverage {
+    pub fn new(code: Vec<u8>) -> Self {
+        Self {
+            code,
+            hits: BTreeMap::new(),
+            branches: BTreeMap::new(),
+        }
+    }
+
+    pub fn merge(&mut self, other: &BytecodeCoverage) {
+        for (pc, hits) in &other.hits {
+            *self.hits.entry(*pc).or_default() += hits;
+        }
+        for (pc, branch) in &other.branches {
+            let hits = self.branches.entry(*pc).or_default();
+            hits.taken += branch.taken;
+            hits.not_taken += branch.not_taken;
+        }
+    }
+
+    // every instruction of the code, EOF containers by their code sections
+    pub fn instructions(&self) -> Vec<Instruction> {
+        disassemble_program(&self.code)
+    }
+
+    pub fn stats(&self) -> CoverageStats {
+        let instructions = self.instructions();
+        let branches: Vec<_> = instructions.iter().filter(|i| is_branch(i.opcode)).collect();
+        let directions_covered = branches
+            .iter()
+            .map(|branch| match self.branch(branch.pc).directions() {
+                BranchDirections::Neither => 0,
+                BranchDirections::Taken | BranchDirections::NotTaken => 1,
+                BranchDirections::Both => 2,
+            })
+            .sum();
+        CoverageStats {
+            instructions: instructions.len(),
+            executed: instructions.iter().filter(|i| self.hits.contains_key(&i.pc)).count(),
+            branches: branches.len(),
+            directions_covered,
+        }
+    }
+
+    pub fn branch(&self, pc: usize) -> BranchHits {
+        self.branches.get(&pc).copied().unwrap_or_default()
+    }
+
+    // An LCOV tracefile, one record per source file the map points into.
+    // A line counts as often as its most executed instruction, every branch instruction is a
+    // block with branch 0 = taken and branch 1 = not taken.
+    // Instructions without a file are left out.
+    pub fn lcov(&self, source_map: &SourceMap, sources: &[SourceFile]) -> String {
+        // file id -> line -> hits, and the branches on each line
+        let mut lines: BTreeMap<usize, BTreeMap<usize, u64>> = BTreeMap::new();
+        let mut branches: BTreeMap<usize, BTreeSet<(usize, usize)>> = BTreeMap::new();
+        for instruction in self.instructions() {
+            let Some(element) = source_map.element_at_pc(instruction.pc) else {
+                continue;
+            };
+            let Some(file) = element.file.and_then(|id| sources.iter().find(|s| s.id == id))
+            else {
+                continue;
+            };
+            let line = file.line_of(element.offset);
+            let hits = self.hits.get(&instruction.pc).copied().unwrap_or(0);
+            let line_hits = lines.entry(file.id).or_default().entry(line).or_default();
+            *line_hits = (*line_hits).max(hits);
+            if is_branch(instruction.opcode) {
+                branches.entry(file.id).or_default().insert((line, instruction.pc));
+            }
+        }
+
+        let mut out = String::new();
+        for (id, file_lines) in &lines {
+            let path = &sources.iter().find(|s| s.id == *id).unwrap().path;
+            out.push_str(&format!("TN:\nSF:{}\n", path));
+            let file_branches = branches.get(id).into_iter().flatten();
+            let (mut found, mut hit) = (0, 0);
+            for (line, pc) in file_branches {
+                let ran = self.hits.contains_key(pc);
+                let branch = self.branch(*pc);
+                for (index, count) in [branch.taken, branch.not_taken].into_iter().enumerate() {
+                    let count = if ran { count.to_string() } else { "-".to_string() };
+                    out.push_str(&format!("BRDA:{},{},{},{}\n", line, pc, index, count));
+                }
+                found += 2;
+                hit += (branch.taken > 0) as usize + (branch.not_taken > 0) as usize;
+            }
+            out.push_str(&format!("BRF:{}\nBRH:{}\n", found, hit));
+            for (line, hits) in file_lines {
+                out.push_str(&format!("DA:{},{}\n", line, hits));
+            }
+            let lines_hit = file_lines.values().filter(|hits| **hits > 0).count();
+            out.push_str(&format!("LF:{}\nLH:{}\nend_of_record\n", file_lines.len(), lines_hit));
+        }
+        out
+    }
+}
+
+// the branch instruction being run, and the pc it falls through to
+#[derive(Debug)]
+struct PendingBranch {
+    pc: usize,
+    fallthrough: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct Coverage {
+    bytecodes: BTreeMap<B256, BytecodeCoverage>,
+    // the hash of the program being run, so it is only hashed once per run, dropped when a run
+    // starts: a reused collector may see another program of the same length at the same address
+    current: Option<(usize, usize, B256)>,
+    branch: Option<PendingBranch>,
+}
+
+impl Coverage {
+    pub fn bytecodes(&self) -> &BTreeMap<B256, BytecodeCoverage> {
+        &self.bytecodes
+    }
+
+    pub fn bytecode(&self, code: &[u8]) -> Option<&BytecodeCoverage> {
+        self.bytecodes.get(&keccak256(code))
+    }
+
+    pub fn merge(&mut self, other: Coverage) {
+        for (hash, coverage) in other.bytecodes {
+            match self.bytecodes.entry(hash) {
+                Entry::Occupied(mut entry) => entry.get_mut().merge(&coverage),
+                Entry::Vacant(entry) => {
+                    entry.insert(coverage);
+                }
+            }
+        }
+    }
+
+    // one line per bytecode
+    pub fn report(&self) -> String {
+        self.bytecodes
+            .iter()
+            .map(|(hash, coverage)| format!("{}: {}\n", hash, coverage.stats()))
+            .collect()
+    }
+
+    fn current<H: Host>(&mut self, vm: &EVM<H>) -> &mut BytecodeCoverage {
+        let key = (vm.program.as_ptr() as usize, vm.program.len());
+        let hash = match self.current {
+            Some((ptr, len, hash)) if (ptr, len) == key => hash,
+            _ => {
+                let hash = keccak256(&vm.program);
+                self.current = Some((key.0, key.1, hash));
+                hash
+            }
+        };
+        self.bytecodes
+            .entry(hash)
+            .or_insert_with(|| BytecodeCoverage::new(vm.program.clone()))
+    }
+}
+
+impl<H: Host> Inspector<H> for Coverage {
+    fn step(&mut self, vm: &EVM<H>) {
+        // a run starts with nothing charged and an empty stack, a step mid-run that looks the same
+        // only costs a hash
+        if vm.gas_used == 0 && vm.stack.is_empty() {
+            self.current = None;
+        }
+        let pc = vm.pc;
+        *self.current(vm).hits.entry(pc).or_default() += 1;
+        self.branch = is_branch(vm.peek()).then(|| PendingBranch {
+            pc,
+            fallthrough: pc + 1 + vm.opcode_table().immediate_size(&vm.program, pc).unwrap_or(0),
+        });
+    }
+
+    fn step_end(&mut self, vm: &EVM<H>, result: &Result<(), EvmError>) {
+        let Some(branch) = self.branch.take() else {
+            return;
+        };
+        if result.is_err() {
+            return;
+        }
+        let hits = self.current(vm).branches.entry(branch.pc).or_default();
+        if vm.pc == branch.fallthrough {
+            hits.not_taken += 1;
+        } else {
+            hits.taken += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Address, U256};
+
+    use super::*;
+
+    // CALLDATASIZE, PUSH1 7, JUMPI, PUSH1 1, STOP, JUMPDEST, PUSH1 2, STOP
+    // (the pc after JUMPI is 4, the JUMPDEST at 7)
+    const PROGRAM: [u8; 11] = [0x36, 0x60, 0x07, 0x57, 0x60, 0x01, 0x00, 0x5B, 0x60, 0x02, 0x00];
+
+    fn run(calldata: Vec<u8>) -> Coverage {
+        let mut vm = EVM::new(Address::ZERO, PROGRAM.to_vec(), 10_000, U256::ZERO, calldata);
+        vm.set_inspector(Coverage::default());
+        vm.run().unwrap();
+        vm.take_inspector::<Coverage>().unwrap()
+    }
+
+    #[test]
+    fn test_branch_directions_merge_across_runs() {
+        let not_taken = run(vec![]);
+        let coverage = not_taken.bytecode(&PROGRAM).unwrap();
+        assert_eq!(coverage.branch(3).directions(), BranchDirections::NotTaken);
+        assert_eq!(
+            coverage.stats(),
+            CoverageStats {
+                instructions: 8,
+                executed: 5,
+                branches: 1,
+                directions_covered: 1,
+            }
+        );
+
+        let mut total = not_taken;
+        total.merge(run(vec![1]));
+        let coverage = total.bytecode(&PROGRAM).unwrap();
+        assert_eq!(coverage.branch(3), BranchHits { taken: 1, not_taken: 1 });
+        assert_eq!(coverage.hits[&0], 2);
+        assert_eq!(coverage.stats().executed, 8);
+        let report = total.report();
+        assert!(report.contains("instructions 8/8 (100.0%), branch directions 2/2 (100.0%)"));
+    }
+
+    #[test]
+    fn test_reused_collector_sees_a_replaced_program() {
+        let mut vm = EVM::new(Address::ZERO, PROGRAM.to_vec(), 10_000, U256::ZERO, vec![]);
+        vm.set_inspector(Coverage::default());
+        vm.run().unwrap();
+        // the same buffer, other code: PUSH1 1, STOP and JUMPDESTs
+        let other = [0x60, 0x01, 0x00, 0x5B, 0x5B, 0x5B, 0x5B, 0x5B, 0x5B, 0x5B, 0x5B];
+        vm.program.copy_from_slice(&other);
+        vm.reset();
+        vm.run().unwrap();
+
+        let coverage = vm.take_inspector::<Coverage>().unwrap();
+        assert_eq!(coverage.bytecode(&PROGRAM).unwrap().hits.len(), 5);
+        assert_eq!(coverage.bytecode(&other).unwrap().hits.len(), 2);
+    }
+
+    #[test]
+    fn test_lcov() {
+        let source = "function f(bool b) {\n  if (b) {\n    x = 2;\n  }\n  x = 1;\n}\n";
+        let line_start = |line: usize| {
+            source.split_inclusive('\n').take(line - 1).map(str::len).sum::<usize>()
+        };
+        // the condition and jump on line 2, the fallthrough on line 5, the jump target on line 3
+        let map = format!(
+            "{a}:1:0;;;{e}:1;;{c}:1;;",
+            a = line_start(2),
+            c = line_start(3),
+            e = line_start(5)
+        );
+        let source_map = SourceMap::new(&map, &PROGRAM).unwrap();
+        let sources = [SourceFile::new(0, "F.sol", source)];
+        let lcov = run(vec![]).bytecode(&PROGRAM).unwrap().lcov(&source_map, &sources);
+        assert_eq!(
+            lcov,
+            "TN:\nSF:F.sol\nBRDA:2,3,0,0\nBRDA:2,3,1,1\nBRF:2\nBRH:1\n\
+             DA:2,1\nDA:3,0\nDA:5,1\nLF:3\nLH:2\nend_of_record\n"
+        );
+    }
+}
@@ -0,0 +1,266 @@
+// EIP-3155 traces: one JSON line per instruction, then a summary line, e.g.
+//   {"pc":0,"op":96,"gas":"0x2710","gasCost":"0x3","memSize":0,"stack":[],"depth":1,"refund":0,"opName":"PUSH1"}
+//   {"output":"","gasUsed":"0x3"}
+// The step line shows the state before the instruction ran, its `gasCost` is what it was charged.
+// Attach the tracer, run, then take it back for the summary:
+//   evm.set_inspector(Eip3155Tracer::new(io::stderr()));
+//   let result = evm.run();
+//   let mut tracer: Eip3155Tracer<Stderr> = evm.take_inspector().unwrap();
+//   tracer.write_summary(&evm, &result)?;
+// Built `with_debug_info`, every step also says where in the Solidity sources it is:
+//   "source":"A.sol:12","sourceLine":"balance[to] += amount;"
+
+use std::io::{self, Write};
+
+use alloy_primitives::hex;
+use serde::Serialize;
+
+use crate::{
+    evm::{EVM, EvmError},
+    host::Host,
+    inspector::{CallInputs, CallOutcome, CreateInputs, CreateOutcome, Inspector},
+    sourcemap::DebugInfo,
+};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Step {
+    pc: usize,
+    op: u8,
+    gas: String,
+    gas_cost: String,
+    mem_size: usize,
+    // bottom first, the top of the stack is the last item
+    stack: Vec<String>,
+    depth: usize,
+    refund: u64,
+    op_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    // path:line and the text of that line, with debug info only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source_line: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Summary {
+    // hex without the 0x prefix, like geth
+    output: String,
+    gas_used: String,
+    // left out when the run succeeded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+pub struct Eip3155Tracer<W: Write> {
+    writer: W,
+    // the top frame is 1
+    depth: usize,
+    // the line of the running instruction, written once its cost is known
+    step: Option<Step>,
+    gas_used_before: u64,
+    // hooks can't fail, the first write error is kept for `write_summary`
+    io_error: Option<io::Error>,
+    debug_info: Option<DebugInfo>,
+}
+
+impl<W: Write> Eip3155Tracer<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            depth: 1,
+            step: None,
+            gas_used_before: 0,
+            io_error: None,
+            debug_info: None,
+        }
+    }
+
+    // the source map must be the one of the program being traced
+    pub fn with_debug_info(writer: W, debug_info: DebugInfo) -> Self {
+        Self {
+            debug_info: Some(debug_info),
+            ..Self::new(writer)
+        }
+    }
+
+    // the closing line, fails if any earlier line could not be written either
+    pub fn write_summary<H: Host>(
+        &mut self,
+        vm: &EVM<H>,
+        result: &Result<(), EvmError>,
+    ) -> io::Result<()> {
+        if let Some(err) = self.io_error.take() {
+            return Err(err);
+        }
+        let error = match result {
+            Err(err) => Some(format!("{:?}", err)),
+            Ok(()) if vm.revert_flag => Some("execution reverted".to_string()),
+            Ok(()) => None,
+        };
+        let summary = Summary {
+            output: hex::encode(&vm.output),
+            gas_used: format!("{:#x}", vm.gas_used),
+            error,
+        };
+        self.write_line(&summary);
+        match self.io_error.take() {
+            Some(err) => Err(err),
+            None => self.writer.flush(),
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_line(&mut self, value: &impl Serialize) {
+        if self.io_error.is_some() {
+            return;
+        }
+        let written = serde_json::to_writer(&mut self.writer, value)
+            .map_err(io::Error::from)
+            .and_then(|_| self.writer.write_all(b"\n"));
+        if let Err(err) = written {
+            self.io_error = Some(err);
+        }
+    }
+}
+
+impl<H: Host, W: Write> Inspector<H> for Eip3155Tracer<W> {
+    fn step(&mut self, vm: &EVM<H>) {
+        let op = vm.peek();
+        self.gas_used_before = vm.gas_used;
+        let location = self.debug_info.as_ref().and_then(|info| info.location(vm.pc));
+        self.step = Some(Step {
+            pc: vm.pc,
+            op,
+            gas: format!("{:#x}", vm.gas),
+            gas_cost: String::new(),
+            mem_size: vm.memory.memory.len(),
+            stack: vm.stack.items().iter().map(|item| format!("{:#x}", item)).collect(),
+            depth: self.depth,
+            refund: vm.refund,
+            op_name: vm.opcode_table().name(op),
+            error: None,
+            source: location.map(|location| location.to_string()),
+            source_line: location.map(|location| location.text().to_string()),
+        });
+    }
+
+    fn step_end(&mut self, vm: &EVM<H>, result: &Result<(), EvmError>) {
+        let Some(mut step) = self.step.take() else {
+            return;
+        };
+        let mut gas_cost = vm.gas_used - self.gas_used_before;
+        if let Err(err) = result {
+            // a failed charge leaves nothing behind, show what the instruction costs at least
+            if gas_cost == 0 {
+                gas_cost = vm.opcode_table().get(step.op).map_or(0, |info| info.base_gas);
+            }
+            step.error = Some(format!("{:?}", err));
+        }
+        step.gas_cost = format!("{:#x}", gas_cost);
+        self.write_line(&step);
+    }
+
+    fn call(&mut self, _vm: &EVM<H>, _inputs: &CallInputs) {
+        self.depth += 1;
+    }
+
+    fn call_end(&mut self, _vm: &EVM<H>, _inputs: &CallInputs, _outcome: &CallOutcome) {
+        self.depth -= 1;
+    }
+
+    fn create(&mut self, _vm: &EVM<H>, _inputs: &CreateInputs) {
+        self.depth += 1;
+    }
+
+    fn create_end(&mut self, _vm: &EVM<H>, _inputs: &CreateInputs, _outcome: &CreateOutcome) {
+        self.depth -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Address, U256};
+
+    use super::*;
+    use crate::sourcemap::SourceFile;
+
+    fn trace(program: Vec<u8>, gas: u64) -> Vec<serde_json::Value> {
+        trace_with(Eip3155Tracer::new(Vec::new()), program, gas)
+    }
+
+    fn trace_with(
+        tracer: Eip3155Tracer<Vec<u8>>,
+        program: Vec<u8>,
+        gas: u64,
+    ) -> Vec<serde_json::Value> {
+        let mut vm = EVM::new(Address::ZERO, program, gas, U256::ZERO, vec![]);
+        vm.set_inspector(tracer);
+        let result = vm.run();
+        let mut tracer: Eip3155Tracer<Vec<u8>> = vm.take_inspector().unwrap();
+        tracer.write_summary(&vm, &result).unwrap();
+        let lines = String::from_utf8(tracer.into_inner()).unwrap();
+        lines.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_steps_and_summary() {
+        // PUSH1 0x2a, PUSH1 0, MSTORE, PUSH1 32, PUSH1 0, RETURN
+        let program = vec![0x60, 0x2a, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xF3];
+        let lines = trace(program, 100);
+        assert_eq!(lines.len(), 7);
+        assert_eq!(
+            lines[0],
+            serde_json::json!({
+                "pc": 0, "op": 0x60, "gas": "0x64", "gasCost": "0x3", "memSize": 0,
+                "stack": [], "depth": 1, "refund": 0, "opName": "PUSH1"
+            })
+        );
+        // MSTORE pays for the first word of memory, its line still shows memory empty
+        assert_eq!(lines[2]["opName"], "MSTORE");
+        assert_eq!(lines[2]["stack"], serde_json::json!(["0x2a", "0x0"]));
+        assert_eq!(lines[2]["gasCost"], "0x6");
+        assert_eq!(lines[2]["memSize"], 0);
+        assert_eq!(lines[5]["memSize"], 32);
+        assert_eq!(
+            lines[6],
+            serde_json::json!({
+                "output": format!("{:064x}", 0x2a),
+                "gasUsed": "0x12",
+            })
+        );
+    }
+
+    #[test]
+    fn test_failing_step_and_summary_carry_the_error() {
+        // PUSH1 1, PUSH1 2, ADD with 7 gas: ADD runs out
+        let lines = trace(vec![0x60, 0x01, 0x60, 0x02, 0x01], 7);
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[2]["opName"], "ADD");
+        assert_eq!(lines[2]["gasCost"], "0x3");
+        assert_eq!(lines[2]["error"], "OutOfGas");
+        assert!(lines[1].get("error").is_none());
+        assert_eq!(lines[3]["gasUsed"], "0x6");
+        assert_eq!(lines[3]["error"], "OutOfGas");
+    }
+
+    #[test]
+    fn test_steps_carry_their_source_line() {
+        let source = "contract A {\n  function f() {\n    x = 1;\n  }\n}\n";
+        let offset = source.find("x = 1").unwrap();
+        let files = vec![SourceFile::new(0, "A.sol", source)];
+        // PUSH1 1, PUSH1 0, SSTORE, the first push has no file
+        let program = vec![0x60, 0x01, 0x60, 0x00, 0x55];
+        let info = DebugInfo::new(&format!("0:0:-1;{offset}:6:0;"), &program, files).unwrap();
+        let lines = trace_with(Eip3155Tracer::with_debug_info(Vec::new(), info), program, 100_000);
+        assert!(lines[0].get("source").is_none());
+        assert_eq!(lines[1]["source"], "A.sol:3");
+        assert_eq!(lines[2]["sourceLine"], "x = 1;");
+    }
+}
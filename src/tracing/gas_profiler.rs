@@ -0,0 +1,296 @@
+// Where the gas of a run went, added up by opcode, by pc, by category and by call path.
+// Memory expansion is split off from the instruction that paid for it, and the gas a callee used
+// is kept apart from what the call instruction cost itself.
+//   evm.set_inspector(GasProfiler::default());
+//   evm.run()?;
+//   let profiler = evm.take_inspector::<GasProfiler>().unwrap();
+//   print!("{}", profiler.table());
+//   fs::write("gas.folded", profiler.folded())?;  // inferno-flamegraph gas.folded > gas.svg
+// Folded stacks are one line per path, `frame;frame;OPCODE gas`, the root frame being the running
+// account.
+
+use std::{collections::BTreeMap, fmt};
+
+use crate::{
+    evm::{EVM, EvmError},
+    host::Host,
+    inspector::{CallInputs, CallOutcome, Inspector},
+    memory::Memory,
+    opcodes::opcodes::{
+        BALANCE, EXTCALL, EXTCODECOPY, EXTCODEHASH, EXTCODESIZE, LOG0, LOG4, SELFBALANCE,
+        SELFDESTRUCT, SLOAD, SSTORE, TLOAD, TSTORE,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GasCategory {
+    Compute,
+    MemoryExpansion,
+    // storage slots and account state
+    Storage,
+    Logs,
+    // the call instructions and everything their callees used
+    Calls,
+}
+
+impl GasCategory {
+    pub fn of(opcode: u8) -> Self {
+        match opcode {
+            SLOAD | SSTORE | TLOAD | TSTORE | BALANCE | SELFBALANCE | EXTCODESIZE | EXTCODECOPY
+            | EXTCODEHASH | SELFDESTRUCT => GasCategory::Storage,
+            LOG0..=LOG4 => GasCategory::Logs,
+            EXTCALL => GasCategory::Calls,
+            _ => GasCategory::Compute,
+        }
+    }
+}
+
+impl fmt::Display for GasCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            GasCategory::Compute => "compute",
+            GasCategory::MemoryExpansion => "memory expansion",
+            GasCategory::Storage => "storage",
+            GasCategory::Logs => "logs",
+            GasCategory::Calls => "calls",
+        };
+        f.pad(name)
+    }
+}
+
+// how often something ran and what it cost in total
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GasStat {
+    pub count: u64,
+    pub gas: u64,
+}
+
+impl GasStat {
+    fn add(&mut self, gas: u64) {
+        self.count += 1;
+        self.gas += gas;
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PcGas {
+    pub opcode: String,
+    pub count: u64,
+    pub gas: u64,
+}
+
+// the instruction being profiled
+#[derive(Debug)]
+struct Step {
+    opcode: u8,
+    pc: usize,
+    gas_used_before: u64,
+    memory_words_before: usize,
+    // what the callees of this instruction used, already on their own paths
+    callee_gas: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct GasProfiler {
+    by_opcode: BTreeMap<String, GasStat>,
+    by_pc: BTreeMap<usize, PcGas>,
+    by_category: BTreeMap<GasCategory, u64>,
+    // folded call path -> gas
+    by_path: BTreeMap<String, u64>,
+    // the running frames, the root first
+    frames: Vec<String>,
+    step: Option<Step>,
+}
+
+impl GasProfiler {
+    pub fn by_opcode(&self) -> &BTreeMap<String, GasStat> {
+        &self.by_opcode
+    }
+
+    pub fn by_pc(&self) -> &BTreeMap<usize, PcGas> {
+        &self.by_pc
+    }
+
+    pub fn by_category(&self) -> &BTreeMap<GasCategory, u64> {
+        &self.by_category
+    }
+
+    pub fn by_path(&self) -> &BTreeMap<String, u64> {
+        &self.by_path
+    }
+
+    pub fn total(&self) -> u64 {
+        self.by_category.values().sum()
+    }
+
+    // categories, opcodes and pcs, the most expensive first
+    pub fn table(&self) -> String {
+        let total = self.total().max(1);
+        let share = |gas: u64| gas as f64 * 100.0 / total as f64;
+        let mut out = String::new();
+
+        out.push_str(&format!("{:<18} {:>12} {:>7}\n", "category", "gas", "%"));
+        let mut categories: Vec<_> = self.by_category.iter().collect();
+        categories.sort_by_key(|(_, gas)| std::cmp::Reverse(**gas));
+        for (category, gas) in categories {
+            out.push_str(&format!("{:<18} {:>12} {:>6.2}%\n", category, gas, share(*gas)));
+        }
+
+        out.push_str(&format!("\n{:<18} {:>8} {:>12} {:>7}\n", "opcode", "count", "gas", "%"));
+        let mut opcodes: Vec<_> = self.by_opcode.iter().collect();
+        opcodes.sort_by_key(|(_, stat)| std::cmp::Reverse(stat.gas));
+        for (opcode, stat) in opcodes {
+            out.push_str(&format!(
+                "{:<18} {:>8} {:>12} {:>6.2}%\n",
+                opcode,
+                stat.count,
+                stat.gas,
+                share(stat.gas)
+            ));
+        }
+
+        out.push_str(&format!(
+            "\n{:<8} {:<18} {:>8} {:>12} {:>7}\n",
+            "pc", "opcode", "count", "gas", "%"
+        ));
+        let mut pcs: Vec<_> = self.by_pc.iter().collect();
+        pcs.sort_by_key(|(pc, stat)| (std::cmp::Reverse(stat.gas), **pc));
+        for (pc, stat) in pcs {
+            out.push_str(&format!(
+                "{:<8} {:<18} {:>8} {:>12} {:>6.2}%\n",
+                format!("{:#06x}", pc),
+                stat.opcode,
+                stat.count,
+                stat.gas,
+                share(stat.gas)
+            ));
+        }
+        out
+    }
+
+    // folded stacks for `inferno-flamegraph` or `flamegraph.pl`
+    pub fn folded(&self) -> String {
+        self.by_path
+            .iter()
+            .filter(|(_, gas)| **gas > 0)
+            .map(|(path, gas)| format!("{} {}\n", path, gas))
+            .collect()
+    }
+
+    fn path(&self) -> String {
+        self.frames.join(";")
+    }
+}
+
+impl<H: Host> Inspector<H> for GasProfiler {
+    fn step(&mut self, vm: &EVM<H>) {
+        if self.frames.is_empty() {
+            self.frames.push(vm.address.to_string());
+        }
+        self.step = Some(Step {
+            opcode: vm.peek(),
+            pc: vm.pc,
+            gas_used_before: vm.gas_used,
+            memory_words_before: vm.memory.memory.len().div_ceil(32),
+            callee_gas: 0,
+        });
+    }
+
+    fn step_end(&mut self, vm: &EVM<H>, _result: &Result<(), EvmError>) {
+        let Some(step) = self.step.take() else {
+            return;
+        };
+        let spent = vm.gas_used - step.gas_used_before;
+        let callee_gas = step.callee_gas.min(spent);
+        let own = spent - callee_gas;
+        // memory only grows once paid for, so the growth is what the instruction paid for it
+        let words_after = vm.memory.memory.len().div_ceil(32);
+        let memory_cost =
+            |words| Memory::calculate_memory_gas(words, &vm.gas_schedule).unwrap_or(0);
+        let expansion = memory_cost(words_after)
+            .saturating_sub(memory_cost(step.memory_words_before))
+            .min(own);
+
+        let name = vm.opcode_table().name(step.opcode);
+        self.by_opcode.entry(name.clone()).or_default().add(own);
+        let pc = self.by_pc.entry(step.pc).or_insert_with(|| PcGas {
+            opcode: name.clone(),
+            ..PcGas::default()
+        });
+        pc.count += 1;
+        pc.gas += own;
+        *self.by_category.entry(GasCategory::MemoryExpansion).or_default() += expansion;
+        *self.by_category.entry(GasCategory::of(step.opcode)).or_default() += own - expansion;
+        if callee_gas > 0 {
+            *self.by_category.entry(GasCategory::Calls).or_default() += callee_gas;
+        }
+        *self.by_path.entry(format!("{};{}", self.path(), name)).or_default() += own;
+    }
+
+    fn call(&mut self, _vm: &EVM<H>, inputs: &CallInputs) {
+        self.frames.push(inputs.target.to_string());
+    }
+
+    fn call_end(&mut self, _vm: &EVM<H>, _inputs: &CallInputs, outcome: &CallOutcome) {
+        // a callee runs native code (a precompile), its gas lands on its frame as a whole
+        *self.by_path.entry(self.path()).or_default() += outcome.gas_used;
+        if self.frames.len() > 1 {
+            self.frames.pop();
+        }
+        if let Some(step) = &mut self.step {
+            step.callee_gas += outcome.gas_used;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Address, U256};
+
+    use super::*;
+
+    fn profile(program: Vec<u8>) -> GasProfiler {
+        let mut vm = EVM::new(Address::ZERO, program, 100_000, U256::ZERO, vec![]);
+        vm.set_inspector(GasProfiler::default());
+        vm.run().unwrap();
+        let profiler = vm.take_inspector::<GasProfiler>().unwrap();
+        assert_eq!(profiler.total(), vm.gas_used);
+        profiler
+    }
+
+    // PUSH1 1, PUSH1 0, MSTORE, PUSH1 1, PUSH1 0, SSTORE, PUSH1 32, PUSH1 0, LOG0, PUSH1 0
+    const PROGRAM: [u8; 17] = [
+        0x60, 0x01, 0x60, 0x00, 0x52, 0x60, 0x01, 0x60, 0x00, 0x55, 0x60, 0x20, 0x60, 0x00, 0xA0,
+        0x60, 0x00,
+    ];
+
+    #[test]
+    fn test_breakdowns() {
+        let profiler = profile(PROGRAM.to_vec());
+        assert_eq!(profiler.by_opcode()["PUSH1"], GasStat { count: 7, gas: 21 });
+        assert_eq!(profiler.by_opcode()["MSTORE"], GasStat { count: 1, gas: 6 });
+        assert_eq!(profiler.by_pc()[&4].opcode, "MSTORE");
+        assert_eq!(profiler.by_pc()[&4].gas, 6);
+
+        let categories = profiler.by_category();
+        assert_eq!(categories[&GasCategory::MemoryExpansion], 3);
+        assert_eq!(categories[&GasCategory::Compute], 21 + 3);
+        assert_eq!(categories[&GasCategory::Storage], 22_100);
+        // LOG0 with 32 bytes of data, memory is already there
+        assert_eq!(categories[&GasCategory::Logs], 375 + 8 * 32);
+    }
+
+    #[test]
+    fn test_folded_stacks_and_table() {
+        let profiler = profile(PROGRAM.to_vec());
+        let root = Address::ZERO.to_string();
+        let folded = profiler.folded();
+        assert!(folded.contains(&format!("{};PUSH1 21\n", root)));
+        assert!(folded.contains(&format!("{};SSTORE 22100\n", root)));
+        assert_eq!(folded.lines().count(), 4);
+
+        let table = profiler.table();
+        let first_opcode = table.lines().skip_while(|line| !line.starts_with("opcode")).nth(1);
+        assert!(first_opcode.unwrap().starts_with("SSTORE"));
+    }
+}
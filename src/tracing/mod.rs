@@ -0,0 +1,21 @@
+// Tracers built on the `Inspector` hooks, each one writes or collects a different view of a run.
+//   eip3155 -> one JSON object per step, the format geth and evmone print with `--json`
+//   struct_log -> geth's default `debug_traceTransaction` format
+//   call_tracer -> the tree of calls with their logs and revert reasons, like geth's `callTracer`
+//   prestate -> the touched state before the run, or a pre/post diff, like geth's `prestateTracer`
+//   gas_profiler -> gas by opcode, pc, category and call path, as a table or folded stacks
+//   coverage -> executed pcs and branch directions per bytecode, LCOV with a source map
+
+pub mod call_tracer;
+pub mod coverage;
+pub mod eip3155;
+pub mod gas_profiler;
+pub mod prestate;
+pub mod struct_log;
+
+pub use call_tracer::{CallFrame, CallTracer};
+pub use coverage::{BytecodeCoverage, Coverage};
+pub use eip3155::Eip3155Tracer;
+pub use gas_profiler::{GasCategory, GasProfiler};
+pub use prestate::{AccountState, PrestateTracer, StateDiff};
+pub use struct_log::{StructLog, StructLogTrace, StructLogger, StructLoggerConfig};
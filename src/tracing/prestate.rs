@@ -0,0 +1,251 @@
+// What a run read and changed, in the shapes of geth's `prestateTracer`:
+//   prestate -> every touched account as it was before the run, with the slots that were touched
+//     {"0x..": {"balance":"0x0","nonce":1,"code":"0x60..","storage":{"0x..01":"0x..07"}}}
+//   diff -> only what changed: `pre` has the old values, `post` only the fields that differ
+//     {"pre": {...}, "post": {...}}
+// The tracer clones the host when the first instruction runs and reads the old values from that
+// copy, so it needs `H: Clone`. The new values are read from the EVM's own host afterwards.
+//   evm.set_inspector(PrestateTracer::<InMemoryHost>::default());
+//   let result = evm.run();
+//   let diff = evm.take_inspector::<PrestateTracer<InMemoryHost>>().unwrap().into_diff(&mut evm);
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use alloy_primitives::{Address, B256, Bytes, U256};
+use serde::Serialize;
+
+use crate::{
+    evm::EVM,
+    host::Host,
+    inspector::{CallInputs, Inspector},
+    opcodes::{
+        environment::to_address,
+        opcodes::{BALANCE, EXTCODECOPY, EXTCODEHASH, EXTCODESIZE, SELFDESTRUCT, SLOAD, SSTORE},
+    },
+};
+
+// an account in a prestate or a diff, fields that don't apply are left out
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct AccountState {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<Bytes>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub storage: BTreeMap<B256, B256>,
+}
+
+impl AccountState {
+    // an account that doesn't exist, or is as good as missing
+    fn is_empty(&self) -> bool {
+        self.balance.is_none_or(|balance| balance.is_zero())
+            && self.nonce.is_none()
+            && self.code.is_none()
+            && self.storage.values().all(|value| value.is_zero())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct StateDiff {
+    pub pre: BTreeMap<Address, AccountState>,
+    pub post: BTreeMap<Address, AccountState>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PrestateTracer<H> {
+    // the host as it was before the first instruction
+    pre_host: Option<H>,
+    accounts: BTreeSet<Address>,
+    slots: BTreeMap<Address, BTreeSet<U256>>,
+}
+
+impl<H> Default for PrestateTracer<H> {
+    fn default() -> Self {
+        Self {
+            pre_host: None,
+            accounts: BTreeSet::new(),
+            slots: BTreeMap::new(),
+        }
+    }
+}
+
+// the account and the touched slots as `host` has them, zero balances and nonces left out
+fn read_account<H: Host>(
+    host: &mut H,
+    address: Address,
+    slots: Option<&BTreeSet<U256>>,
+) -> AccountState {
+    let balance = host.balance(address);
+    let nonce = host.nonce(address);
+    let code = host.code(address);
+    let storage = slots
+        .into_iter()
+        .flatten()
+        .map(|slot| (B256::from(*slot), B256::from(host.sload(address, *slot))))
+        .collect();
+    AccountState {
+        balance: Some(balance),
+        nonce: (nonce != 0).then_some(nonce),
+        code: (!code.is_empty()).then(|| Bytes::from(code)),
+        storage,
+    }
+}
+
+impl<H: Host + Clone> PrestateTracer<H> {
+    // every touched account as it was before the run
+    pub fn into_prestate(self, vm: &EVM<H>) -> BTreeMap<Address, AccountState> {
+        let mut pre_host = self.pre_host.unwrap_or_else(|| vm.host.clone());
+        let mut prestate = BTreeMap::new();
+        for address in self.accounts {
+            let account = read_account(&mut pre_host, address, self.slots.get(&address));
+            prestate.insert(address, account);
+        }
+        prestate
+    }
+
+    // the accounts that changed, before and after the run
+    pub fn into_diff(self, vm: &mut EVM<H>) -> StateDiff {
+        let mut pre_host = self.pre_host.unwrap_or_else(|| vm.host.clone());
+        let mut diff = StateDiff::default();
+        for address in self.accounts {
+            let slots = self.slots.get(&address);
+            let mut pre = read_account(&mut pre_host, address, slots);
+            let post = read_account(&mut vm.host, address, slots);
+            if pre == post {
+                continue;
+            }
+            // post only has the fields that changed, pre only the slots that did
+            let changed = AccountState {
+                balance: post.balance.filter(|_| post.balance != pre.balance),
+                nonce: post.nonce.filter(|_| post.nonce != pre.nonce),
+                code: post.code.clone().filter(|_| post.code != pre.code),
+                storage: post
+                    .storage
+                    .iter()
+                    .filter(|(slot, value)| pre.storage.get(*slot) != Some(*value))
+                    .map(|(slot, value)| (*slot, *value))
+                    .collect(),
+            };
+            pre.storage.retain(|slot, _| changed.storage.contains_key(slot));
+            // an account that only comes into being has no pre state
+            if !pre.is_empty() {
+                diff.pre.insert(address, pre);
+            }
+            diff.post.insert(address, changed);
+        }
+        diff
+    }
+}
+
+impl<H: Host + Clone> Inspector<H> for PrestateTracer<H> {
+    fn step(&mut self, vm: &EVM<H>) {
+        if self.pre_host.is_none() {
+            self.pre_host = Some(vm.host.clone());
+            self.accounts.insert(vm.sender);
+            self.accounts.insert(vm.address);
+        }
+        if vm.stack.is_empty() {
+            return;
+        }
+        let top = vm.stack.nth(0);
+        match vm.peek() {
+            SLOAD | SSTORE => {
+                self.slots.entry(vm.address).or_default().insert(top);
+            }
+            BALANCE | EXTCODESIZE | EXTCODECOPY | EXTCODEHASH | SELFDESTRUCT => {
+                self.accounts.insert(to_address(top));
+            }
+            _ => {}
+        }
+    }
+
+    fn call(&mut self, _vm: &EVM<H>, inputs: &CallInputs) {
+        self.accounts.insert(inputs.target);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::AccountInfo, host::InMemoryHost};
+
+    const ME: Address = Address::repeat_byte(0x11);
+    const HEIR: Address = Address::repeat_byte(0x22);
+
+    fn run(program: Vec<u8>) -> EVM {
+        let mut vm = EVM::new(ME, program, 100_000, U256::ZERO, vec![]);
+        vm.host.db.insert_account(ME, AccountInfo::new(U256::from(50), 1, vec![]));
+        vm.host.db.insert_storage(ME, U256::from(1), U256::from(5));
+        vm.set_inspector(PrestateTracer::<InMemoryHost>::default());
+        assert!(vm.run().is_ok());
+        vm
+    }
+
+    // SSTORE 7 to slot 1, SLOAD slot 2, SELFDESTRUCT to HEIR
+    fn program() -> Vec<u8> {
+        let mut program = vec![0x60, 0x07, 0x60, 0x01, 0x55, 0x60, 0x02, 0x54, 0x50, 0x73];
+        program.extend(HEIR.as_slice());
+        program.push(0xFF);
+        program
+    }
+
+    #[test]
+    fn test_prestate_has_the_old_values_of_touched_state() {
+        let mut vm = run(program());
+        let tracer = vm.take_inspector::<PrestateTracer<InMemoryHost>>().unwrap();
+        let prestate = tracer.into_prestate(&vm);
+        assert_eq!(prestate.len(), 2);
+        assert_eq!(
+            serde_json::to_value(&prestate[&ME]).unwrap(),
+            serde_json::json!({
+                "balance": "0x32",
+                "nonce": 1,
+                "storage": {
+                    B256::from(U256::from(1)).to_string(): B256::from(U256::from(5)),
+                    B256::from(U256::from(2)).to_string(): B256::ZERO,
+                },
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(&prestate[&HEIR]).unwrap(),
+            serde_json::json!({ "balance": "0x0" })
+        );
+    }
+
+    #[test]
+    fn test_diff_has_only_what_changed() {
+        let mut vm = run(program());
+        let tracer = vm.take_inspector::<PrestateTracer<InMemoryHost>>().unwrap();
+        let diff = tracer.into_diff(&mut vm);
+        let slot = B256::from(U256::from(1));
+        assert_eq!(
+            diff.pre[&ME],
+            AccountState {
+                balance: Some(U256::from(50)),
+                nonce: Some(1),
+                code: None,
+                storage: BTreeMap::from([(slot, B256::from(U256::from(5)))]),
+            }
+        );
+        assert_eq!(
+            diff.post[&ME],
+            AccountState {
+                balance: Some(U256::ZERO),
+                storage: BTreeMap::from([(slot, B256::from(U256::from(7)))]),
+                ..AccountState::default()
+            }
+        );
+        // HEIR didn't exist before
+        assert!(!diff.pre.contains_key(&HEIR));
+        assert_eq!(diff.post[&HEIR].balance, Some(U256::from(50)));
+    }
+
+    #[test]
+    fn test_untouched_run_has_an_empty_diff() {
+        let mut vm = run(vec![0x60, 0x01, 0x50]);
+        let tracer = vm.take_inspector::<PrestateTracer<InMemoryHost>>().unwrap();
+        assert_eq!(tracer.into_diff(&mut vm), StateDiff::default());
+    }
+}
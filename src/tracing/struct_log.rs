@@ -0,0 +1,254 @@
+// Geth's default `debug_traceTransaction` output, the struct logger:
+//   {"gas":18,"failed":false,"returnValue":"","structLogs":[
+//     {"pc":0,"op":"PUSH1","gas":100,"gasCost":3,"depth":1,"stack":[],"memory":[]}, ...]}
+// Each log is the state before the instruction ran. Memory is listed in 32-byte words.
+// Like geth, SLOAD and SSTORE steps carry the slots the running contract touched so far, one map
+// per contract, so returning from a call shows the caller's slots again.
+//   evm.set_inspector(StructLogger::new(StructLoggerConfig::default()));
+//   let result = evm.run();
+//   let trace = evm.take_inspector::<StructLogger>().unwrap().into_trace(&evm, &result);
+//   serde_json::to_string(&trace)
+
+use std::collections::{BTreeMap, HashMap};
+
+use alloy_primitives::{Address, U256, hex};
+use serde::Serialize;
+
+use crate::{
+    evm::{EVM, EvmError},
+    host::Host,
+    inspector::{CallInputs, CallOutcome, CreateInputs, CreateOutcome, Inspector},
+    opcodes::opcodes::{SLOAD, SSTORE},
+};
+
+// what to leave out of every log, everything is captured by default
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StructLoggerConfig {
+    pub disable_memory: bool,
+    pub disable_stack: bool,
+    pub disable_storage: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructLog {
+    pub pc: usize,
+    pub op: String,
+    pub gas: u64,
+    pub gas_cost: u64,
+    pub depth: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    // bottom first, as 0x-prefixed hex
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stack: Option<Vec<String>>,
+    // 32-byte words as hex without a prefix
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "is_zero")]
+    pub refund: u64,
+    // only on SLOAD and SSTORE, slot -> value as 32-byte hex without a prefix
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage: Option<BTreeMap<String, String>>,
+}
+
+// the whole result, the same object geth's `debug_traceTransaction` returns
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructLogTrace {
+    // gas used by the run
+    pub gas: u64,
+    pub failed: bool,
+    pub return_value: String,
+    pub struct_logs: Vec<StructLog>,
+}
+
+#[derive(Debug, Default)]
+pub struct StructLogger {
+    config: StructLoggerConfig,
+    logs: Vec<StructLog>,
+    // the top frame is 1
+    depth: usize,
+    gas_used_before: u64,
+    // the slot the running SLOAD reads
+    sload_key: Option<U256>,
+    // the slots each contract has read or written so far
+    storage: HashMap<Address, BTreeMap<U256, U256>>,
+}
+
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
+fn word_hex(value: U256) -> String {
+    hex::encode(value.to_be_bytes::<32>())
+}
+
+impl StructLogger {
+    pub fn new(config: StructLoggerConfig) -> Self {
+        Self {
+            config,
+            depth: 1,
+            ..Self::default()
+        }
+    }
+
+    pub fn logs(&self) -> &[StructLog] {
+        &self.logs
+    }
+
+    pub fn into_trace<H: Host>(self, vm: &EVM<H>, result: &Result<(), EvmError>) -> StructLogTrace {
+        StructLogTrace {
+            gas: vm.gas_used,
+            failed: result.is_err() || vm.revert_flag,
+            return_value: hex::encode(&vm.output),
+            struct_logs: self.logs,
+        }
+    }
+
+    fn storage_snapshot(&self, address: Address) -> BTreeMap<String, String> {
+        self.storage
+            .get(&address)
+            .into_iter()
+            .flatten()
+            .map(|(key, value)| (word_hex(*key), word_hex(*value)))
+            .collect()
+    }
+}
+
+impl<H: Host> Inspector<H> for StructLogger {
+    fn step(&mut self, vm: &EVM<H>) {
+        let op = vm.peek();
+        self.gas_used_before = vm.gas_used;
+        self.sload_key = None;
+        // the value SSTORE writes is on the stack already, SLOAD's is only known once it ran
+        if !self.config.disable_storage && !vm.stack.is_empty() {
+            if op == SSTORE && vm.stack.len() >= 2 {
+                let slots = self.storage.entry(vm.address).or_default();
+                slots.insert(vm.stack.nth(0), vm.stack.nth(1));
+            } else if op == SLOAD {
+                self.sload_key = Some(vm.stack.nth(0));
+            }
+        }
+        let stack = (!self.config.disable_stack)
+            .then(|| vm.stack.items().iter().map(|item| format!("{:#x}", item)).collect());
+        let memory = (!self.config.disable_memory)
+            .then(|| vm.memory.memory.chunks(32).map(hex::encode).collect());
+        self.logs.push(StructLog {
+            pc: vm.pc,
+            op: vm.opcode_table().name(op),
+            gas: vm.gas,
+            gas_cost: 0,
+            depth: self.depth,
+            error: None,
+            stack,
+            memory,
+            refund: vm.refund,
+            storage: None,
+        });
+    }
+
+    fn step_end(&mut self, vm: &EVM<H>, result: &Result<(), EvmError>) {
+        let Some(pc) = self.logs.last().map(|log| log.pc) else {
+            return;
+        };
+        let op = vm.program[pc];
+        if let Some(key) = self.sload_key.take()
+            && result.is_ok()
+        {
+            self.storage.entry(vm.address).or_default().insert(key, vm.stack.nth(0));
+        }
+        let storage = ((op == SLOAD || op == SSTORE) && !self.config.disable_storage)
+            .then(|| self.storage_snapshot(vm.address));
+        let gas_cost = vm.gas_used - self.gas_used_before;
+        let log = self.logs.last_mut().unwrap();
+        log.gas_cost = gas_cost;
+        log.storage = storage;
+        if let Err(err) = result {
+            log.error = Some(format!("{:?}", err));
+        }
+    }
+
+    fn call(&mut self, _vm: &EVM<H>, _inputs: &CallInputs) {
+        self.depth += 1;
+    }
+
+    fn call_end(&mut self, _vm: &EVM<H>, _inputs: &CallInputs, _outcome: &CallOutcome) {
+        self.depth -= 1;
+    }
+
+    fn create(&mut self, _vm: &EVM<H>, _inputs: &CreateInputs) {
+        self.depth += 1;
+    }
+
+    fn create_end(&mut self, _vm: &EVM<H>, _inputs: &CreateInputs, _outcome: &CreateOutcome) {
+        self.depth -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trace(program: Vec<u8>, config: StructLoggerConfig) -> serde_json::Value {
+        let mut vm = EVM::new(Address::ZERO, program, 100_000, U256::ZERO, vec![]);
+        vm.set_inspector(StructLogger::new(config));
+        let result = vm.run();
+        let logger = vm.take_inspector::<StructLogger>().unwrap();
+        serde_json::to_value(logger.into_trace(&vm, &result)).unwrap()
+    }
+
+    // PUSH1 7, PUSH1 1, SSTORE, PUSH1 2, SLOAD, PUSH1 0, MSTORE, STOP
+    const PROGRAM: [u8; 12] = [
+        0x60, 0x07, 0x60, 0x01, 0x55, 0x60, 0x02, 0x54, 0x60, 0x00, 0x52, 0x00,
+    ];
+
+    #[test]
+    fn test_geth_format_with_storage_snapshots() {
+        let trace = trace(PROGRAM.to_vec(), StructLoggerConfig::default());
+        assert_eq!(trace["failed"], false);
+        assert_eq!(trace["returnValue"], "");
+        let logs = trace["structLogs"].as_array().unwrap();
+        assert_eq!(logs.len(), 8);
+        assert_eq!(
+            logs[0],
+            serde_json::json!({
+                "pc": 0, "op": "PUSH1", "gas": 100_000, "gasCost": 3, "depth": 1,
+                "stack": [], "memory": []
+            })
+        );
+        let slot = |n: u8| format!("{:064x}", n);
+        assert_eq!(logs[2]["op"], "SSTORE");
+        assert_eq!(logs[2]["stack"], serde_json::json!(["0x7", "0x1"]));
+        assert_eq!(logs[2]["storage"], serde_json::json!({ slot(1): slot(7) }));
+        // SLOAD adds the slot it read to what SSTORE wrote
+        assert_eq!(logs[4]["op"], "SLOAD");
+        assert_eq!(logs[4]["storage"], serde_json::json!({ slot(1): slot(7), slot(2): slot(0) }));
+        assert!(logs[5].get("storage").is_none());
+        assert_eq!(logs[7]["memory"], serde_json::json!([slot(0)]));
+        let gas_used: u64 = logs.iter().map(|log| log["gasCost"].as_u64().unwrap()).sum();
+        assert_eq!(trace["gas"], gas_used);
+    }
+
+    #[test]
+    fn test_disabled_captures_are_left_out() {
+        let config = StructLoggerConfig {
+            disable_memory: true,
+            disable_stack: true,
+            disable_storage: true,
+        };
+        let trace = trace(PROGRAM.to_vec(), config);
+        for log in trace["structLogs"].as_array().unwrap() {
+            assert!(log.get("stack").is_none());
+            assert!(log.get("memory").is_none());
+            assert!(log.get("storage").is_none());
+        }
+    }
+
+    #[test]
+    fn test_failed_run() {
+        let trace = trace(vec![0x01], StructLoggerConfig::default());
+        assert_eq!(trace["failed"], true);
+        assert_eq!(trace["structLogs"][0]["error"], "StackUnderflow");
+    }
+}
@@ -0,0 +1,389 @@
+// The opcode-level test vectors of the evm-from-scratch course, its `evm.json`: a list of cases
+//   { "name": "ADD (overflow)", "hint": "...",
+//     "code": { "asm": "PUSH32 0xff..ff\nPUSH1 0x02\nADD", "bin": "7fff..ff600201" },
+//     "tx": { "to": "0x...", "from": "0x...", "origin": "0x...", "value": "0x0", "data": "0x" },
+//     "block": { "coinbase": "0x...", "number": "0x...", "basefee": "0x1", ... },
+//     "state": { "0x...": { "balance": "0x100", "code": { "bin": "..." } } },
+//     "expect": { "stack": ["0x1"], "success": true, "return": "...", "logs": [...] } }
+// `tx`, `block` and `state` may be missing, so may `return` and `logs`, which are then not checked.
+// The stack is listed top first. The course has no gas, the code runs unmetered, and a run that
+// fails leaves an empty stack.
+//   let results = vectors::run_all(&vectors::load("evm.json")?);
+//   for result in results.iter().filter(|result| !result.passed()) { ... }
+
+use std::{collections::BTreeMap, fmt, fs, path::Path};
+
+use alloy_primitives::{Address, B256, U256, hex};
+use serde::{Deserialize, Deserializer, de};
+
+use crate::{
+    db::AccountInfo,
+    evm::{EVM, Log},
+    host::{Env, InMemoryHost},
+};
+
+#[derive(Debug)]
+pub enum VectorError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+// the values are hex strings, with or without `0x`, numbers and addresses without leading zeros
+trait FromHex: Sized {
+    fn from_hex(hex: &str) -> Option<Self>;
+}
+
+impl FromHex for U256 {
+    fn from_hex(value: &str) -> Option<Self> {
+        let digits = value.trim().trim_start_matches("0x");
+        if digits.is_empty() {
+            return Some(U256::ZERO);
+        }
+        U256::from_str_radix(digits, 16).ok()
+    }
+}
+
+impl FromHex for Address {
+    fn from_hex(value: &str) -> Option<Self> {
+        let word = U256::from_hex(value)?;
+        (word.bit_len() <= 160).then(|| Address::from_word(B256::from(word)))
+    }
+}
+
+impl FromHex for Vec<u8> {
+    fn from_hex(value: &str) -> Option<Self> {
+        hex::decode(value.trim()).ok()
+    }
+}
+
+fn from_hex<'de, D: Deserializer<'de>, T: FromHex>(deserializer: D) -> Result<T, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    T::from_hex(&value).ok_or_else(|| de::Error::custom(format!("invalid hex {value:?}")))
+}
+
+fn optional_hex<'de, D: Deserializer<'de>, T: FromHex>(
+    deserializer: D,
+) -> Result<Option<T>, D::Error> {
+    from_hex(deserializer).map(Some)
+}
+
+fn hex_list<'de, D: Deserializer<'de>, T: FromHex>(deserializer: D) -> Result<Vec<T>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|value| {
+            T::from_hex(value).ok_or_else(|| de::Error::custom(format!("invalid hex {value:?}")))
+        })
+        .collect()
+}
+
+fn accounts<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<Address, Account>, D::Error> {
+    BTreeMap::<String, Account>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(address, account)| match Address::from_hex(&address) {
+            Some(address) => Ok((address, account)),
+            None => Err(de::Error::custom(format!("invalid address {address:?}"))),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TestCase {
+    pub name: String,
+    #[serde(default)]
+    pub hint: String,
+    pub code: Code,
+    #[serde(default)]
+    pub tx: Tx,
+    #[serde(default)]
+    pub block: Block,
+    #[serde(default, deserialize_with = "accounts")]
+    pub state: BTreeMap<Address, Account>,
+    pub expect: Expect,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Code {
+    #[serde(default)]
+    pub asm: Option<String>,
+    #[serde(default, deserialize_with = "from_hex")]
+    pub bin: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Tx {
+    // the account whose code runs
+    #[serde(deserialize_with = "from_hex")]
+    pub to: Address,
+    #[serde(deserialize_with = "from_hex")]
+    pub from: Address,
+    // `from` if it isn't given
+    #[serde(deserialize_with = "optional_hex")]
+    pub origin: Option<Address>,
+    #[serde(deserialize_with = "from_hex")]
+    pub gasprice: U256,
+    #[serde(deserialize_with = "from_hex")]
+    pub value: U256,
+    #[serde(deserialize_with = "from_hex")]
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Block {
+    #[serde(deserialize_with = "from_hex")]
+    pub basefee: U256,
+    #[serde(deserialize_with = "from_hex")]
+    pub coinbase: Address,
+    #[serde(deserialize_with = "from_hex")]
+    pub timestamp: U256,
+    #[serde(deserialize_with = "from_hex")]
+    pub number: U256,
+    // PREVRANDAO
+    #[serde(deserialize_with = "from_hex")]
+    pub difficulty: U256,
+    #[serde(deserialize_with = "from_hex")]
+    pub gaslimit: U256,
+    #[serde(deserialize_with = "from_hex")]
+    pub chainid: U256,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Account {
+    #[serde(deserialize_with = "from_hex")]
+    pub balance: U256,
+    pub code: Code,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Expect {
+    // top first
+    #[serde(default, deserialize_with = "hex_list")]
+    pub stack: Vec<U256>,
+    pub success: bool,
+    #[serde(default, rename = "return", deserialize_with = "optional_hex")]
+    pub return_data: Option<Vec<u8>>,
+    pub logs: Option<Vec<ExpectedLog>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ExpectedLog {
+    #[serde(deserialize_with = "from_hex")]
+    pub address: Address,
+    #[serde(default, deserialize_with = "from_hex")]
+    pub data: Vec<u8>,
+    #[serde(default, deserialize_with = "hex_list")]
+    pub topics: Vec<U256>,
+}
+
+impl From<&ExpectedLog> for Log {
+    fn from(log: &ExpectedLog) -> Self {
+        Log::new(log.address, log.data.clone(), log.topics.clone())
+    }
+}
+
+// one way a run differs from what the case expects
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mismatch {
+    // `error` is why the run failed, None if it succeeded or reverted
+    Success { expected: bool, error: Option<String> },
+    Stack { expected: Vec<U256>, got: Vec<U256> },
+    Return { expected: Vec<u8>, got: Vec<u8> },
+    Logs { expected: Vec<Log>, got: Vec<Log> },
+}
+
+fn words(words: &[U256]) -> String {
+    let words: Vec<_> = words.iter().map(|word| format!("{:#x}", word)).collect();
+    format!("[{}]", words.join(", "))
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mismatch::Success { expected, error } => {
+                let got = match (expected, error) {
+                    (true, Some(error)) => format!("failed with {error}"),
+                    (true, None) => "reverted".to_string(),
+                    (false, _) => "succeeded".to_string(),
+                };
+                let expected = if *expected { "success" } else { "failure" };
+                write!(f, "expected {expected}, {got}")
+            }
+            Mismatch::Stack { expected, got } => {
+                write!(f, "stack {}, expected {}", words(got), words(expected))
+            }
+            Mismatch::Return { expected, got } => write!(
+                f,
+                "return data 0x{}, expected 0x{}",
+                hex::encode(got),
+                hex::encode(expected)
+            ),
+            Mismatch::Logs { expected, got } => {
+                write!(f, "logs {:?}, expected {:?}", got, expected)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaseResult {
+    pub name: String,
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl CaseResult {
+    pub fn passed(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+pub fn from_json(json: &str) -> Result<Vec<TestCase>, VectorError> {
+    serde_json::from_str(json).map_err(VectorError::Json)
+}
+
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<TestCase>, VectorError> {
+    from_json(&fs::read_to_string(path).map_err(VectorError::Io)?)
+}
+
+pub fn run_case(case: &TestCase) -> CaseResult {
+    let (tx, block) = (&case.tx, &case.block);
+    let mut host = InMemoryHost::new(Env {
+        origin: tx.origin.unwrap_or(tx.from),
+        gas_price: tx.gasprice,
+        coinbase: block.coinbase,
+        number: block.number.saturating_to(),
+        timestamp: block.timestamp.saturating_to(),
+        gas_limit: block.gaslimit.saturating_to(),
+        base_fee: block.basefee,
+        prev_randao: B256::from(block.difficulty),
+        chain_id: block.chainid.saturating_to(),
+    });
+    for (address, account) in &case.state {
+        let info = AccountInfo::new(account.balance, 0, account.code.bin.clone());
+        host.db.insert_account(*address, info);
+    }
+    let code = case.code.bin.clone();
+    let mut evm = EVM::with_host(host, tx.from, code, u64::MAX, tx.value, tx.data.clone());
+    evm.address = tx.to;
+    evm.metered = false;
+    let result = evm.run();
+
+    let expect = &case.expect;
+    let mut mismatches = Vec::new();
+    let success = result.is_ok() && !evm.revert_flag;
+    if success != expect.success {
+        mismatches.push(Mismatch::Success {
+            expected: expect.success,
+            error: result.err().map(|err| format!("{:?}", err)),
+        });
+    }
+    let stack = match success {
+        true => evm.stack.items().iter().rev().copied().collect(),
+        false => Vec::new(),
+    };
+    if stack != expect.stack {
+        mismatches.push(Mismatch::Stack {
+            expected: expect.stack.clone(),
+            got: stack,
+        });
+    }
+    if let Some(expected) = &expect.return_data
+        && *expected != evm.output
+    {
+        mismatches.push(Mismatch::Return {
+            expected: expected.clone(),
+            got: evm.output.clone(),
+        });
+    }
+    if let Some(expected) = &expect.logs {
+        let expected: Vec<Log> = expected.iter().map(Log::from).collect();
+        if expected != evm.host.logs {
+            mismatches.push(Mismatch::Logs {
+                expected,
+                got: evm.host.logs.clone(),
+            });
+        }
+    }
+    CaseResult {
+        name: case.name.clone(),
+        mismatches,
+    }
+}
+
+pub fn run_all(cases: &[TestCase]) -> Vec<CaseResult> {
+    cases.iter().map(run_case).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CASES: &str = r#"[
+      { "name": "ADD", "code": { "asm": "PUSH1 0x01\nPUSH1 0x02\nADD", "bin": "6001600201" },
+        "expect": { "stack": ["0x3"], "success": true } },
+      { "name": "SELFBALANCE",
+        "tx": { "to": "0x1000000000000000000000000000000000000aaa" },
+        "state": { "0x1000000000000000000000000000000000000aaa": { "balance": "0x200" } },
+        "code": { "asm": "SELFBALANCE", "bin": "47" },
+        "expect": { "stack": ["0x200"], "success": true } },
+      { "name": "LOG1", "tx": { "to": "0x1000000000000000000000000000000000000001" },
+        "code": { "asm": "PUSH1 0xaa\nPUSH1 0\nMSTORE8\nPUSH1 0x42\nPUSH1 1\nPUSH1 0\nLOG1",
+                  "bin": "60aa600053604260016000a1" },
+        "expect": { "stack": [], "success": true, "logs": [
+          { "address": "0x1000000000000000000000000000000000000001", "data": "aa",
+            "topics": ["0x42"] } ] } },
+      { "name": "RETURN",
+        "code": { "asm": "PUSH1 0xa2\nPUSH1 0\nMSTORE8\nPUSH1 1\nPUSH1 0\nRETURN",
+                  "bin": "60a260005360016000f3" },
+        "expect": { "stack": [], "success": true, "return": "a2" } },
+      { "name": "INVALID", "code": { "asm": "PUSH1 0x01\nINVALID", "bin": "6001fe" },
+        "expect": { "stack": [], "success": false } },
+      { "name": "WRONG", "code": { "asm": "PUSH1 0x01\nPUSH1 0\nPUSH1 0\nREVERT",
+                                   "bin": "600160006000fd" },
+        "expect": { "stack": ["0x2"], "success": true, "return": "01" } }
+    ]"#;
+
+    #[test]
+    fn test_run_all() {
+        let cases = from_json(CASES).unwrap();
+        assert_eq!(cases[1].state.keys().next(), Some(&cases[1].tx.to));
+        let results = run_all(&cases);
+        for result in &results[..5] {
+            assert!(result.passed(), "{}: {:?}", result.name, result.mismatches);
+        }
+
+        // a revert counts as a failure, which leaves nothing on the stack
+        let wrong = &results[5];
+        assert_eq!(
+            wrong.mismatches,
+            vec![
+                Mismatch::Success {
+                    expected: true,
+                    error: None
+                },
+                Mismatch::Stack {
+                    expected: vec![U256::from(2)],
+                    got: vec![]
+                },
+                Mismatch::Return {
+                    expected: vec![1],
+                    got: vec![]
+                },
+            ]
+        );
+        assert_eq!(wrong.mismatches[0].to_string(), "expected success, reverted");
+        assert_eq!(wrong.mismatches[1].to_string(), "stack [], expected [0x2]");
+    }
+
+    #[test]
+    fn test_short_and_prefixed_hex() {
+        assert_eq!(U256::from_hex("0x"), Some(U256::ZERO));
+        assert_eq!(Address::from_hex("0xaa"), Some(Address::with_last_byte(0xaa)));
+        assert_eq!(Vec::<u8>::from_hex("0x0102"), Some(vec![1, 2]));
+        assert_eq!(Vec::<u8>::from_hex("0102"), Some(vec![1, 2]));
+        assert!(from_json(r#"[{ "name": "x", "code": { "bin": "zz" }, "expect": {}}]"#).is_err());
+    }
+}
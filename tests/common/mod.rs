@@ -0,0 +1,24 @@
+// helpers shared by the integration tests, each test file includes them with `mod common;`
+
+pub use evm::eof::test_container as container;
+
+// MSTORE8 0xAB at 0, then EXTCALL 0x42 with that byte as input
+// not every test file calls it
+#[allow(dead_code)]
+pub fn call_precompile_program() -> Vec<u8> {
+    container(
+        &[(0, 0x80, 4)],
+        &[&[
+            0x60, 0xAB, // PUSH1 0xAB
+            0x60, 0x00, // PUSH1 0
+            0x53, // MSTORE8
+            0x60, 0x00, // PUSH1 0 (value)
+            0x60, 0x01, // PUSH1 1 (input size)
+            0x60, 0x00, // PUSH1 0 (input offset)
+            0x60, 0x42, // PUSH1 0x42 (target)
+            0xF8, // EXTCALL
+            0x00, // STOP
+        ]],
+        &[],
+    )
+}
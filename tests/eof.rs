@@ -0,0 +1,369 @@
+mod common;
+
+use alloy_primitives::{Address, U256};
+
+use evm::{
+    db::AccountInfo,
+    eof::EofError,
+    evm::{EVM, EvmError},
+    host::Host,
+    opcodes::info::opcode_info,
+    precompile::{PrecompileContext, PrecompileError, PrecompileOutput},
+};
+
+use common::{call_precompile_program, container};
+
+fn init_evm(program: Vec<u8>) -> EVM {
+    EVM::new(Address::ZERO, program, 10000, U256::ZERO, vec![])
+}
+
+#[test]
+fn test_eof_callf_and_retf() {
+    let program = container(
+        &[(0, 0x80, 1), (1, 1, 1)],
+        &[
+            &[
+                0x60, 0x15, // PUSH1 21
+                0xE3, 0x00, 0x01, // CALLF 1
+                0x00, // STOP
+            ],
+            &[
+                0x80, // DUP1
+                0x01, // ADD
+                0xE4, // RETF
+            ],
+        ],
+        &[],
+    );
+    let mut my_evm = init_evm(program);
+    assert!(my_evm.run().is_ok());
+    assert_eq!(my_evm.stack.len(), 1);
+    assert_eq!(my_evm.stack.peek(0).unwrap(), U256::from(42));
+    assert!(my_evm.return_stack.is_empty());
+}
+
+#[test]
+fn test_eof_rjumpi_loop() {
+    // counts down from 3, storing the counter in memory on every iteration
+    let program = container(
+        &[(0, 0x80, 3)],
+        &[&[
+            0x60, 0x03, // PUSH1 3
+            0x80, // DUP1            <- loop
+            0x60, 0x00, // PUSH1 0
+            0x52, // MSTORE
+            0x60, 0x01, // PUSH1 1
+            0x90, // SWAP1
+            0x03, // SUB
+            0x80, // DUP1
+            0xE1, 0xFF, 0xF4, // RJUMPI -12 (back to the loop)
+            0x00, // STOP
+        ]],
+        &[],
+    );
+    let mut my_evm = init_evm(program);
+    assert!(my_evm.run().is_ok());
+    assert_eq!(my_evm.stack.peek(0).unwrap(), U256::ZERO);
+    assert_eq!(my_evm.memory.load(0)[31], 1);
+}
+
+#[test]
+fn test_eof_rjumpv() {
+    // jump table with 2 entries, case 1 pushes 0xBB
+    let program = container(
+        &[(0, 0x80, 1)],
+        &[&[
+            0x60, 0x01, // PUSH1 1
+            0xE2, 0x01, 0x00, 0x03, 0x00, 0x06, // RJUMPV [+3, +6]
+            0x60, 0xAA, 0x00, // PUSH1 0xAA STOP
+            0x60, 0xAA, 0x00, // PUSH1 0xAA STOP
+            0x60, 0xBB, 0x00, // PUSH1 0xBB STOP
+        ]],
+        &[],
+    );
+    let mut my_evm = init_evm(program);
+    assert!(my_evm.run().is_ok());
+    assert_eq!(my_evm.stack.peek(0).unwrap(), U256::from(0xBB));
+}
+
+#[test]
+fn test_eof_data_section() {
+    let mut data = vec![0u8; 31];
+    data.push(0x2A);
+    data.push(0xFF);
+    let program = container(
+        &[(0, 0x80, 3)],
+        &[&[
+            0xD1, 0x00, 0x00, // DATALOADN 0
+            0x60, 0x02, // PUSH1 2
+            0xD0, // DATALOAD (reads 0x2AFF followed by zero padding)
+            0xD2, // DATASIZE
+            0x00, // STOP
+        ]],
+        &data,
+    );
+    let mut my_evm = init_evm(program);
+    assert!(my_evm.run().is_ok());
+    assert_eq!(my_evm.stack.peek(0).unwrap(), U256::from(33));
+    assert_eq!(my_evm.stack.peek(1).unwrap(), U256::from(0x2AFF00));
+    assert_eq!(my_evm.stack.peek(2).unwrap(), U256::from(0x2A));
+}
+
+#[test]
+fn test_eof_datacopy_gas_overflow() {
+    let program = container(
+        &[(0, 0x80, 3)],
+        &[&[
+            0x65, 0x02, 0xD4, 0x13, 0xCC, 0x6F, 0xE0, // PUSH6 0x02d413cc6fe0 (size)
+            0x60, 0x00, // PUSH1 0 (offset)
+            0x60, 0x00, // PUSH1 0 (dest offset)
+            0xD3, // DATACOPY, the copy gas on top of the expansion overflows a u64
+            0x00, // STOP
+        ]],
+        &[],
+    );
+    let mut my_evm = init_evm(program);
+    my_evm.gas = u64::MAX;
+    assert_eq!(my_evm.run(), Err(EvmError::OutOfGas));
+    assert_eq!(my_evm.memory.memory.len(), 0);
+}
+
+// the EOF side of test_handlers_match_their_stack_effects in tests/evm.rs, CALLF, RETF, JUMPF,
+// DUPN, SWAPN and EXCHANGE declare no stack effect and check their own
+#[test]
+fn test_eof_handlers_match_their_stack_effects() {
+    for opcode in 0..=u8::MAX {
+        let Some(info) = opcode_info(opcode) else {
+            continue;
+        };
+        if !info.implemented || info.is_valid_in_legacy() || info.inputs + info.outputs == 0 {
+            continue;
+        }
+        let mut code = [0x60, 0x00].repeat(info.inputs as usize); // PUSH1 0 for every input
+        code.push(opcode);
+        match opcode {
+            0xE2 => code.extend_from_slice(&[0x00, 0x00, 0x00]), // RJUMPV with one entry
+            _ => code.resize(code.len() + info.immediate_size as usize, 0),
+        }
+        code.push(0x00); // STOP
+        let max_stack = info.inputs.max(info.outputs) as u16;
+        let mut my_evm = init_evm(container(&[(0, 0x80, max_stack)], &[&code], &[0; 32]));
+        my_evm.gas = 1_000_000;
+        assert!(my_evm.run().is_ok(), "{}", info.mnemonic);
+        assert_eq!(my_evm.stack.len(), info.outputs as usize, "{}", info.mnemonic);
+    }
+}
+
+#[test]
+fn test_eof_swapn_dupn_exchange() {
+    let program = container(
+        &[(0, 0x80, 4)],
+        &[&[
+            0x60, 0x01, // PUSH1 1
+            0x60, 0x02, // PUSH1 2
+            0x60, 0x03, // PUSH1 3
+            0xE6, 0x02, // DUPN 2     -> [1, 2, 3, 1]
+            0xE7, 0x01, // SWAPN 1    -> [1, 1, 3, 2]
+            0xE8, 0x00, // EXCHANGE 0 -> [1, 3, 1, 2]
+            0x00, // STOP
+        ]],
+        &[],
+    );
+    let mut my_evm = init_evm(program);
+    assert!(my_evm.run().is_ok());
+    let items: Vec<U256> = [1, 3, 1, 2].iter().map(|v| U256::from(*v)).collect();
+    assert_eq!(my_evm.stack.items(), items);
+}
+
+#[test]
+fn test_eof_step_starts_at_first_code_section() {
+    let program = container(&[(0, 0x80, 1)], &[&[0x60, 0x01, 0x00]], &[]);
+    let mut my_evm = init_evm(program);
+    assert_eq!(my_evm.step(), Ok(true));
+    assert_eq!(my_evm.stack.peek(0).unwrap(), U256::ONE);
+}
+
+#[test]
+fn test_eof_rejects_invalid_container() {
+    // JUMP is not allowed inside a container
+    let program = container(&[(0, 0x80, 1)], &[&[0x60, 0x00, 0x56]], &[]);
+    let mut my_evm = init_evm(program);
+    assert_eq!(
+        my_evm.run(),
+        Err(EvmError::InvalidEof(EofError::UndefinedInstruction {
+            section: 0,
+            pc: 2,
+            opcode: 0x56
+        }))
+    );
+}
+
+#[test]
+fn test_eof_opcodes_are_undefined_in_legacy_code() {
+    let mut my_evm = init_evm(vec![0xE0, 0x00, 0x00]);
+    assert_eq!(
+        my_evm.run(),
+        Err(EvmError::UnknownOpcode {
+            opcode: "0xe0".to_string()
+        })
+    );
+}
+
+#[test]
+fn test_eof_extcall_with_value_in_static_context() {
+    let program = container(
+        &[(0, 0x80, 4)],
+        &[&[
+            0x60, 0x01, // PUSH1 1 (value)
+            0x60, 0x00, // PUSH1 0 (input size)
+            0x60, 0x00, // PUSH1 0 (input offset)
+            0x60, 0x00, // PUSH1 0 (target)
+            0xF8, // EXTCALL
+            0x00, // STOP
+        ]],
+        &[],
+    );
+    let mut my_evm = init_evm(program.clone());
+    my_evm.gas = 20000;
+    my_evm
+        .host
+        .db
+        .insert_account(Address::ZERO, AccountInfo::new(U256::from(1), 0, vec![]));
+    assert!(my_evm.run().is_ok());
+    assert_eq!(my_evm.stack.peek(0).unwrap(), U256::ZERO);
+
+    let mut my_evm = init_evm(program);
+    my_evm.gas = 20000;
+    my_evm.is_static = true;
+    assert_eq!(my_evm.run(), Err(EvmError::StateChangeDuringStaticCall));
+}
+
+#[test]
+fn test_eof_extcall_runs_a_precompile() {
+    let address = Address::with_last_byte(0x42);
+    let echo = |input: &[u8], _: u64, ctx: &mut PrecompileContext<'_>| {
+        assert_eq!(ctx.address, Address::with_last_byte(0x42));
+        Ok(PrecompileOutput::new(50, input.repeat(2)))
+    };
+    let mut my_evm = init_evm(call_precompile_program());
+    my_evm.register_precompile(address, echo);
+    assert!(my_evm.run().is_ok());
+    assert_eq!(my_evm.stack.peek(0).unwrap(), U256::ZERO);
+    assert_eq!(my_evm.return_data, vec![0xAB, 0xAB]);
+    // precompiles are warm, the memory was already paid for by MSTORE8
+    assert_eq!(my_evm.gas_used, 3 + 3 + 6 + 3 * 4 + 100 + 50);
+}
+
+#[test]
+fn test_eof_extcall_precompile_revert() {
+    let revert = |_: &[u8], _: u64, _: &mut PrecompileContext<'_>| {
+        Ok(PrecompileOutput::revert(10, vec![0x01]))
+    };
+    let mut my_evm = init_evm(call_precompile_program());
+    my_evm.register_precompile(Address::with_last_byte(0x42), revert);
+    assert!(my_evm.run().is_ok());
+    assert_eq!(my_evm.stack.peek(0).unwrap(), U256::from(1));
+    assert_eq!(my_evm.return_data, vec![0x01]);
+    assert_eq!(my_evm.gas_used, 3 + 3 + 6 + 3 * 4 + 100 + 10);
+}
+
+#[test]
+fn test_eof_extcall_precompile_halt_burns_its_gas() {
+    let halt = |_: &[u8], _: u64, _: &mut PrecompileContext<'_>| {
+        Err(PrecompileError::Other("unsupported input".to_string()))
+    };
+    let mut my_evm = init_evm(call_precompile_program());
+    my_evm.register_precompile(Address::with_last_byte(0x42), halt);
+    assert!(my_evm.run().is_ok());
+    assert_eq!(my_evm.stack.peek(0).unwrap(), U256::from(2));
+    assert!(my_evm.return_data.is_empty());
+    // one 64th of the gas left at the call is kept
+    let before_call = 10000 - (3 + 3 + 6 + 3 * 4 + 100);
+    assert_eq!(my_evm.gas, before_call / 64);
+}
+
+// EXTCALL 0x42 sending it 5 wei, without input
+fn send_value_program() -> Vec<u8> {
+    container(
+        &[(0, 0x80, 4)],
+        &[&[
+            0x60, 0x05, // PUSH1 5 (value)
+            0x60, 0x00, // PUSH1 0 (input size)
+            0x60, 0x00, // PUSH1 0 (input offset)
+            0x60, 0x42, // PUSH1 0x42 (target)
+            0xF8, // EXTCALL
+            0x00, // STOP
+        ]],
+        &[],
+    )
+}
+
+#[test]
+fn test_eof_extcall_moves_value_to_an_account_without_code() {
+    let target = Address::with_last_byte(0x42);
+    let mut my_evm = init_evm(send_value_program());
+    my_evm.gas = 20000;
+    my_evm
+        .host
+        .db
+        .insert_account(Address::ZERO, AccountInfo::new(U256::from(8), 0, vec![]));
+    assert!(my_evm.run().is_ok());
+    assert_eq!(my_evm.stack.peek(0).unwrap(), U256::ZERO);
+    assert_eq!(my_evm.host.balance(Address::ZERO), U256::from(3));
+    assert_eq!(my_evm.host.balance(target), U256::from(5));
+
+    // without the balance the call fails and nothing moves
+    let mut my_evm = init_evm(send_value_program());
+    my_evm.gas = 20000;
+    assert!(my_evm.run().is_ok());
+    assert_eq!(my_evm.stack.peek(0).unwrap(), U256::from(1));
+    assert_eq!(my_evm.host.balance(target), U256::ZERO);
+}
+
+#[test]
+fn test_eof_extcall_precompile_revert_undoes_its_writes() {
+    let target = Address::with_last_byte(0x42);
+    let write_then_revert = |_: &[u8], _: u64, ctx: &mut PrecompileContext<'_>| {
+        ctx.host.sstore(ctx.address, U256::from(1), U256::from(2));
+        Ok(PrecompileOutput::revert(10, vec![]))
+    };
+    let mut my_evm = init_evm(send_value_program()).with_precompile(target, write_then_revert);
+    my_evm.gas = 20000;
+    my_evm
+        .host
+        .db
+        .insert_account(Address::ZERO, AccountInfo::new(U256::from(8), 0, vec![]));
+    assert!(my_evm.run().is_ok());
+    assert_eq!(my_evm.stack.peek(0).unwrap(), U256::from(1));
+    assert_eq!(my_evm.host.sload(target, U256::from(1)), U256::ZERO);
+    assert_eq!(my_evm.host.balance(Address::ZERO), U256::from(8));
+    assert_eq!(my_evm.host.balance(target), U256::ZERO);
+
+    // a successful call keeps them
+    let write = |_: &[u8], _: u64, ctx: &mut PrecompileContext<'_>| {
+        ctx.host.sstore(ctx.address, U256::from(1), U256::from(2));
+        Ok(PrecompileOutput::new(10, vec![]))
+    };
+    let mut my_evm = init_evm(send_value_program());
+    my_evm.gas = 20000;
+    my_evm
+        .host
+        .db
+        .insert_account(Address::ZERO, AccountInfo::new(U256::from(8), 0, vec![]));
+    assert!(my_evm.register_precompile(target, write).is_none());
+    assert!(my_evm.run().is_ok());
+    assert_eq!(my_evm.stack.peek(0).unwrap(), U256::ZERO);
+    assert_eq!(my_evm.host.sload(target, U256::from(1)), U256::from(2));
+    assert_eq!(my_evm.host.balance(target), U256::from(5));
+}
+
+#[test]
+fn test_eof_extcall_into_code_is_unsupported() {
+    let target = Address::with_last_byte(0x42);
+    let mut my_evm = init_evm(call_precompile_program());
+    my_evm
+        .host
+        .db
+        .insert_account(target, AccountInfo::new(U256::ZERO, 0, vec![0x00]));
+    assert_eq!(my_evm.run(), Err(EvmError::UnsupportedCall { target }));
+}
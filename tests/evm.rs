@@ -1,6 +1,16 @@
-use alloy_primitives::{Address, U256};
+use alloy_primitives::{Address, B256, U256, keccak256};
 
-use evm::evm::{EVM, EvmError};
+use evm::{
+    abi::{Abi, AbiValue, Function, Revert},
+    db::{AccountInfo, Database},
+    evm::{EVM, EvmError, Log},
+    fork::Fork,
+    gas::GasSchedule,
+    host::{Env, Host, InMemoryHost},
+    inspector::Inspector,
+    memory::{Memory, UNMETERED_LIMIT},
+    opcodes::info::{OpcodeInfo, opcode_info},
+};
 
 fn init_evm() -> EVM {
     EVM::new(Address::ZERO, vec![], 1000, U256::ZERO, vec![])
@@ -59,8 +69,8 @@ fn test_storage_persistence() {
     assert!(output.is_ok());
     let key = U256::from(0x01);
     let value = U256::from(0x69);
-    let retrieved_val = my_evm.storage.load(key);
-    assert_eq!(retrieved_val.1, value);
+    let storage = my_evm.host.db.storage_of(my_evm.address).unwrap();
+    assert_eq!(storage[&key], value);
 }
 // check for storage persistence and warmness of value
 
@@ -93,6 +103,44 @@ fn test_stack_underflow() {
     assert_eq!(output, Err(EvmError::StackUnderflow));
 }
 
+#[test]
+fn test_unimplemented_opcode_on_an_empty_stack() {
+    let mut my_evm = init_evm();
+    my_evm.program = vec![0xF1]; // CALL, which needs 7 items
+    assert_eq!(
+        my_evm.run(),
+        Err(EvmError::UnknownOpcode {
+            opcode: "0xf1".to_string()
+        })
+    );
+    assert_eq!(my_evm.gas, 1000);
+}
+
+// The handlers pop and push without checking the height, they trust the table: each one runs on
+// exactly the items its entry declares, must not read past them and leaves exactly its outputs.
+#[test]
+fn test_handlers_match_their_stack_effects() {
+    for opcode in 0..=u8::MAX {
+        let Some(info) = opcode_info(opcode) else {
+            continue;
+        };
+        if !info.implemented || !info.is_valid_in_legacy() {
+            continue;
+        }
+        let mut my_evm = init_evm();
+        my_evm.gas = 1_000_000;
+        for _ in 0..info.inputs {
+            my_evm.stack.push(U256::ZERO).unwrap();
+        }
+        my_evm.program = vec![opcode];
+        my_evm.program.resize(1 + info.immediate_size as usize, 0);
+        // a failure such as a bad jump is fine, a panic on the stack bounds is not
+        if my_evm.run().is_ok() {
+            assert_eq!(my_evm.stack.len(), info.outputs as usize, "{}", info.mnemonic);
+        }
+    }
+}
+
 #[test]
 fn test_stack_overflow() {
     let mut my_evm = init_evm();
@@ -109,3 +157,724 @@ fn test_stack_overflow() {
     assert!(output.is_err());
     assert_eq!(output, Err(EvmError::StackOverflow));
 }
+
+// Static context
+#[test]
+fn test_static_call_rejects_sstore() {
+    let mut my_evm = init_evm();
+    my_evm.program = vec![
+        0x60, 0x69, // PUSH1 0X69
+        0x60, 0x01, // PUSH1 0X01
+        0x55, // SSTORE
+    ];
+    my_evm.gas = 30000;
+    my_evm.is_static = true;
+    let output = my_evm.run();
+    assert_eq!(output, Err(EvmError::StateChangeDuringStaticCall));
+    assert!(my_evm.host.db.storage_of(my_evm.address).is_none());
+}
+
+#[test]
+fn test_static_call_rejects_tstore_and_log() {
+    let tstore = vec![
+        0x60, 0x69, // PUSH1 0X69
+        0x60, 0x01, // PUSH1 0X01
+        0x5D, // TSTORE
+    ];
+    let log = vec![
+        0x60, 0x00, // PUSH1 0
+        0x60, 0x00, // PUSH1 0
+        0xA0, // LOG0
+    ];
+    for program in [tstore, log] {
+        let mut my_evm = init_evm();
+        my_evm.program = program;
+        my_evm.is_static = true;
+        assert_eq!(my_evm.run(), Err(EvmError::StateChangeDuringStaticCall));
+        assert!(my_evm.host.transient_storage.is_empty());
+        assert!(my_evm.host.logs.is_empty());
+    }
+}
+
+#[test]
+fn test_static_call_allows_reads() {
+    let mut my_evm = init_evm();
+    my_evm.program = vec![
+        0x60, 0x01, // PUSH1 0X01
+        0x54, // SLOAD
+        0x60, 0x01, // PUSH1 0X01
+        0x5C, // TLOAD
+    ];
+    my_evm.gas = 30000;
+    my_evm.is_static = true;
+    assert!(my_evm.run().is_ok());
+    assert_eq!(my_evm.stack.len(), 2);
+}
+
+// Memory is charged before it is allocated
+#[test]
+fn test_huge_calldatacopy_runs_out_of_gas_without_allocating() {
+    let mut my_evm = init_evm();
+    my_evm.program = vec![
+        0x7F, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+        0xFF, 0xFF, 0xFF, // PUSH32 2^256 - 1 (size)
+        0x60, 0x00, // PUSH1 0 (offset)
+        0x60, 0x00, // PUSH1 0 (dest offset)
+        0x37, // CALLDATACOPY
+    ];
+    assert_eq!(my_evm.run(), Err(EvmError::OutOfGas));
+    assert_eq!(my_evm.memory.memory.len(), 0);
+}
+
+#[test]
+fn test_mstore_at_huge_offset_runs_out_of_gas() {
+    let mut my_evm = init_evm();
+    my_evm.program = vec![
+        0x60, 0x01, // PUSH1 1
+        0x67, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, // PUSH8 2^64 - 1
+        0x52, // MSTORE
+    ];
+    assert_eq!(my_evm.run(), Err(EvmError::OutOfGas));
+    assert_eq!(my_evm.memory.memory.len(), 0);
+}
+
+#[test]
+fn test_memory_limit() {
+    let mut my_evm = init_evm();
+    my_evm.memory = Memory::with_limit(64);
+    my_evm.program = vec![
+        0x60, 0x01, // PUSH1 1
+        0x60, 0x40, // PUSH1 64
+        0x52, // MSTORE
+    ];
+    assert_eq!(
+        my_evm.run(),
+        Err(EvmError::MemoryLimitExceeded {
+            required: 96,
+            limit: 64
+        })
+    );
+    assert_eq!(my_evm.gas, 1000 - 6);
+}
+
+#[test]
+fn test_sha3_zero_extends_memory() {
+    let mut my_evm = init_evm();
+    my_evm.program = vec![
+        0x60, 0x40, // PUSH1 64 (size)
+        0x60, 0x10, // PUSH1 16 (offset)
+        0x20, // SHA3
+    ];
+    assert!(my_evm.run().is_ok());
+    assert_eq!(
+        my_evm.stack.peek(0).unwrap(),
+        U256::from_be_bytes(keccak256([0u8; 64]).0)
+    );
+    // [16, 80) rounds up to 3 words
+    assert_eq!(my_evm.memory.memory.len(), 96);
+    // 3 + 3 + 30 + 6 * 2 words + 9 expansion
+    assert_eq!(my_evm.gas, 1000 - 57);
+}
+
+#[test]
+fn test_sha3_of_nothing_ignores_offset() {
+    let mut my_evm = init_evm();
+    my_evm.program = vec![
+        0x60, 0x00, // PUSH1 0 (size)
+        0x7F, // PUSH32 0xFF..FF (offset)
+    ];
+    my_evm.program.extend_from_slice(&[0xFF; 32]);
+    my_evm.program.push(0x20); // SHA3
+    assert!(my_evm.run().is_ok());
+    assert_eq!(
+        my_evm.stack.peek(0).unwrap(),
+        U256::from_be_bytes(keccak256([]).0)
+    );
+    assert_eq!(my_evm.memory.memory.len(), 0);
+}
+
+#[test]
+fn test_sha3_at_huge_offset_runs_out_of_gas() {
+    let mut my_evm = init_evm();
+    my_evm.program = vec![
+        0x60, 0x01, // PUSH1 1 (size)
+        0x7F, // PUSH32 0xFF..FF (offset)
+    ];
+    my_evm.program.extend_from_slice(&[0xFF; 32]);
+    my_evm.program.push(0x20); // SHA3
+    assert_eq!(my_evm.run(), Err(EvmError::OutOfGas));
+    assert_eq!(my_evm.memory.memory.len(), 0);
+}
+
+// A size whose expansion alone still fits in a u64, the copy, hash or log data gas on top of it
+// doesn't. All the gas there is can't pay for it and nothing is allocated.
+const HUGE_SIZE: [u8; 7] = [0x65, 0x02, 0xD4, 0x13, 0xCC, 0x6F, 0xE0]; // PUSH6 0x02d413cc6fe0
+
+fn run_with_huge_size(arguments_after_size: &[u8], opcode: u8) -> (EVM, Result<(), EvmError>) {
+    let mut my_evm = init_evm();
+    my_evm.gas = u64::MAX;
+    my_evm.program = HUGE_SIZE.to_vec();
+    my_evm.program.extend_from_slice(arguments_after_size);
+    my_evm.program.push(opcode);
+    let result = my_evm.run();
+    (my_evm, result)
+}
+
+#[test]
+fn test_calldatacopy_gas_overflow() {
+    // PUSH1 0 (offset), PUSH1 0 (dest offset), CALLDATACOPY
+    let (my_evm, result) = run_with_huge_size(&[0x60, 0x00, 0x60, 0x00], 0x37);
+    assert_eq!(result, Err(EvmError::OutOfGas));
+    assert_eq!(my_evm.memory.memory.len(), 0);
+}
+
+#[test]
+fn test_codecopy_gas_overflow() {
+    // PUSH1 0 (offset), PUSH1 0 (dest offset), CODECOPY
+    let (my_evm, result) = run_with_huge_size(&[0x60, 0x00, 0x60, 0x00], 0x39);
+    assert_eq!(result, Err(EvmError::OutOfGas));
+    assert_eq!(my_evm.memory.memory.len(), 0);
+}
+
+#[test]
+fn test_extcodecopy_gas_overflow() {
+    // PUSH1 0 (offset), PUSH1 0 (dest offset), PUSH1 0 (address), EXTCODECOPY
+    let (my_evm, result) = run_with_huge_size(&[0x60, 0x00, 0x60, 0x00, 0x60, 0x00], 0x3C);
+    assert_eq!(result, Err(EvmError::OutOfGas));
+    assert_eq!(my_evm.memory.memory.len(), 0);
+}
+
+#[test]
+fn test_returndatacopy_huge_size() {
+    // the size is checked against the return data before any gas is worked out
+    // PUSH1 0 (offset), PUSH1 0 (dest offset), RETURNDATACOPY
+    let (my_evm, result) = run_with_huge_size(&[0x60, 0x00, 0x60, 0x00], 0x3E);
+    assert_eq!(
+        result,
+        Err(EvmError::ReturnDataOutOfBounds {
+            offset: 0,
+            size: 0x2d413cc6fe0,
+            max: 0
+        })
+    );
+    assert_eq!(my_evm.memory.memory.len(), 0);
+}
+
+#[test]
+fn test_sha3_gas_overflow() {
+    // PUSH1 0 (offset), SHA3
+    let (my_evm, result) = run_with_huge_size(&[0x60, 0x00], 0x20);
+    assert_eq!(result, Err(EvmError::OutOfGas));
+    assert_eq!(my_evm.memory.memory.len(), 0);
+}
+
+#[test]
+fn test_log_gas_overflow() {
+    // PUSH1 0 (offset), LOG0
+    let (my_evm, result) = run_with_huge_size(&[0x60, 0x00], 0xA0);
+    assert_eq!(result, Err(EvmError::OutOfGas));
+    assert_eq!(my_evm.memory.memory.len(), 0);
+    assert!(my_evm.host.logs.is_empty());
+}
+
+#[test]
+fn test_return_sets_output() {
+    let mut my_evm = init_evm();
+    my_evm.program = vec![
+        0x60, 0x2A, // PUSH1 42
+        0x60, 0x00, // PUSH1 0
+        0x53, // MSTORE8
+        0x60, 0x02, // PUSH1 2 (size)
+        0x60, 0x00, // PUSH1 0 (offset)
+        0xF3, // RETURN
+        0x60, 0x01, // PUSH1 1, never reached
+    ];
+    assert!(my_evm.run().is_ok());
+    assert_eq!(my_evm.output, vec![0x2A, 0x00]);
+    assert!(my_evm.stop_flag);
+    assert!(!my_evm.revert_flag);
+    assert!(my_evm.stack.is_empty());
+}
+
+#[test]
+fn test_revert_keeps_return_data_of_last_call() {
+    let mut my_evm = init_evm();
+    my_evm.return_data = vec![0xAA; 4];
+    my_evm.program = vec![
+        0x3D, // RETURNDATASIZE
+        0x60, 0x00, // PUSH1 0 (offset)
+        0xFD, // REVERT
+    ];
+    assert!(my_evm.run().is_ok());
+    assert!(my_evm.revert_flag);
+    assert_eq!(my_evm.output, vec![0x00; 4]);
+    assert_eq!(my_evm.return_data, vec![0xAA; 4]);
+}
+
+#[test]
+fn test_return_data_copy_expands_at_destination() {
+    let mut my_evm = init_evm();
+    my_evm.return_data = vec![0xAA; 32];
+    my_evm.program = vec![
+        0x3D, // RETURNDATASIZE (size)
+        0x60, 0x00, // PUSH1 0 (src offset)
+        0x60, 0x40, // PUSH1 64 (dest offset)
+        0x3E, // RETURNDATACOPY
+    ];
+    assert!(my_evm.run().is_ok());
+    assert_eq!(my_evm.memory.memory.len(), 96);
+    assert_eq!(&my_evm.memory.memory[64..], &[0xAA; 32]);
+    // 2 + 3 + 3 + 3 + 3 * 1 word + 9 for growing to 3 words
+    assert_eq!(my_evm.gas, 1000 - 23);
+}
+
+#[test]
+fn test_stack_height_is_checked_before_gas() {
+    let mut my_evm = init_evm();
+    my_evm.program = vec![
+        0x60, 0x01, // PUSH1 1
+        0x01, // ADD, one item short
+    ];
+    assert_eq!(my_evm.run(), Err(EvmError::StackUnderflow));
+    assert_eq!(my_evm.gas, 1000 - 3);
+    assert_eq!(my_evm.pc, 2);
+}
+
+#[test]
+fn test_smod_by_zero_moves_on() {
+    let mut my_evm = init_evm();
+    my_evm.program = vec![
+        0x60, 0x00, // PUSH1 0
+        0x60, 0x07, // PUSH1 7
+        0x07, // SMOD
+        0x60, 0x01, // PUSH1 1
+    ];
+    assert!(my_evm.run().is_ok());
+    assert_eq!(my_evm.stack.items(), [U256::ZERO, U256::ONE]);
+}
+
+#[test]
+fn test_exp() {
+    let mut my_evm = init_evm();
+    my_evm.program = vec![
+        0x60, 0x0A, // PUSH1 10 (exponent)
+        0x60, 0x02, // PUSH1 2 (base)
+        0x0A, // EXP
+    ];
+    assert!(my_evm.run().is_ok());
+    assert_eq!(my_evm.stack.peek(0).unwrap(), U256::from(1024));
+    // 3 + 3 + 10 + 50 for a one byte exponent
+    assert_eq!(my_evm.gas, 1000 - 66);
+}
+
+// Host
+#[test]
+fn test_balance_warms_the_account() {
+    let mut my_evm = init_evm();
+    let account = Address::repeat_byte(0x11);
+    my_evm
+        .host
+        .db
+        .insert_account(account, AccountInfo::new(U256::from(500), 0, vec![]));
+    my_evm.program = vec![
+        0x73, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+        0x11, 0x11, 0x11, 0x11, 0x11, 0x11, // PUSH20 account
+        0x80, // DUP1
+        0x31, // BALANCE, cold
+        0x50, // POP
+        0x31, // BALANCE, warm
+    ];
+    my_evm.gas = 10000;
+    assert!(my_evm.run().is_ok());
+    assert_eq!(my_evm.stack.items(), [U256::from(500)]);
+    assert_eq!(my_evm.gas, 10000 - (3 + 3 + 2600 + 2 + 100));
+}
+
+#[test]
+fn test_block_environment_comes_from_the_host() {
+    let mut host = InMemoryHost::new(Env {
+        number: 300,
+        chain_id: 1,
+        ..Env::default()
+    });
+    host.db.block_hashes.insert(299, B256::repeat_byte(0xAB));
+    host.db.block_hashes.insert(43, B256::repeat_byte(0xCD));
+    let program = vec![
+        0x61, 0x01, 0x2B, // PUSH2 299
+        0x40, // BLOCKHASH
+        0x60, 0x2B, // PUSH1 43, more than 256 blocks back
+        0x40, // BLOCKHASH
+        0x43, // NUMBER
+        0x46, // CHAINID
+    ];
+    let mut my_evm = EVM::with_host(host, Address::ZERO, program, 1000, U256::ZERO, vec![]);
+    assert!(my_evm.run().is_ok());
+    assert_eq!(
+        my_evm.stack.items(),
+        [
+            B256::repeat_byte(0xAB).into(),
+            U256::ZERO,
+            U256::from(300),
+            U256::from(1)
+        ]
+    );
+}
+
+#[test]
+fn test_selfdestruct_sends_the_balance_and_halts() {
+    let mut my_evm = init_evm();
+    let address = my_evm.address;
+    my_evm
+        .host
+        .db
+        .insert_account(address, AccountInfo::new(U256::from(500), 0, vec![]));
+    my_evm.program = vec![
+        0x60, 0x22, // PUSH1 0x22
+        0xFF, // SELFDESTRUCT
+        0x60, 0x01, // PUSH1 1, never runs
+    ];
+    my_evm.gas = 50000;
+    assert!(my_evm.run().is_ok());
+    assert!(my_evm.stop_flag);
+    assert!(my_evm.stack.is_empty());
+    let beneficiary = Address::with_last_byte(0x22);
+    assert_eq!(my_evm.host.db.basic(beneficiary).unwrap().balance, U256::from(500));
+    assert_eq!(my_evm.host.db.basic(address).unwrap().balance, U256::ZERO);
+    // cold beneficiary that only comes into existence now
+    assert_eq!(my_evm.gas, 50000 - (3 + 5000 + 2600 + 25000));
+}
+
+// Gas schedule
+#[test]
+fn test_gas_schedule_can_be_replaced() {
+    let program = vec![
+        0x60, 0x01, // PUSH1 1
+        0x54, // SLOAD
+    ];
+    let mut istanbul = init_evm();
+    istanbul.program = program.clone();
+    istanbul.gas_schedule = GasSchedule::for_fork(Fork::Istanbul);
+    assert!(istanbul.run().is_ok());
+    assert_eq!(istanbul.gas, 1000 - (3 + 800));
+
+    let mut custom = init_evm();
+    custom.program = program;
+    custom.gas_schedule.very_low = 1;
+    custom.gas_schedule.cold_sload = 40;
+    assert!(custom.run().is_ok());
+    assert_eq!(custom.gas_used, 1 + 40);
+}
+
+// what SSTORE of `new` into a slot holding `old` costs on `fork`
+fn sstore_gas(fork: Fork, old: u64, new: u64, warm: bool) -> u64 {
+    let mut my_evm = init_evm();
+    my_evm.gas = 100_000;
+    my_evm.gas_schedule = GasSchedule::for_fork(fork);
+    my_evm.program = vec![
+        0x60, new as u8, // PUSH1 new
+        0x60, 0x01, // PUSH1 1
+        0x55, // SSTORE
+    ];
+    let address = my_evm.address;
+    my_evm.host.sstore(address, U256::from(1), U256::from(old));
+    if warm {
+        my_evm.host.warm_slot(address, U256::from(1));
+    }
+    assert!(my_evm.run().is_ok());
+    my_evm.gas_used - 3 - 3
+}
+
+#[test]
+fn test_sstore_gas_by_fork() {
+    // (set, reset, no-op) for a warm and for a cold slot
+    let cases = [
+        (Fork::Frontier, (20000, 5000, 5000), (20000, 5000, 5000)),
+        (Fork::Constantinople, (20000, 5000, 5000), (20000, 5000, 5000)),
+        (Fork::Istanbul, (20000, 5000, 800), (20000, 5000, 800)),
+        (Fork::Berlin, (20000, 2900, 100), (22100, 5000, 2200)),
+        (Fork::Cancun, (20000, 2900, 100), (22100, 5000, 2200)),
+    ];
+    for (fork, warm, cold) in cases {
+        for (is_warm, (set, reset, noop)) in [(true, warm), (false, cold)] {
+            assert_eq!(sstore_gas(fork, 0, 7, is_warm), set, "{fork:?} set, warm: {is_warm}");
+            assert_eq!(sstore_gas(fork, 7, 8, is_warm), reset, "{fork:?} reset, warm: {is_warm}");
+            assert_eq!(sstore_gas(fork, 7, 0, is_warm), reset, "{fork:?} clear, warm: {is_warm}");
+            assert_eq!(sstore_gas(fork, 7, 7, is_warm), noop, "{fork:?} no-op, warm: {is_warm}");
+        }
+    }
+}
+
+#[test]
+fn test_unmetered_run_records_gas_used() {
+    let mut my_evm = init_evm();
+    my_evm.program = vec![
+        0x60, 0x69, // PUSH1 0X69
+        0x60, 0x01, // PUSH1 0X01
+        0x55, // SSTORE, far more than the 1000 gas there is
+    ];
+    my_evm.metered = false;
+    assert!(my_evm.run().is_ok());
+    assert_eq!(my_evm.gas, 1000);
+    assert_eq!(my_evm.gas_used, 3 + 3 + 2100 + 20000);
+    assert_eq!(my_evm.host.db.storage_of(my_evm.address).unwrap()[&U256::from(1)], U256::from(0x69));
+}
+
+#[test]
+fn test_reset_starts_a_new_transaction() {
+    let mut my_evm = init_evm();
+    my_evm.gas = 100_000;
+    my_evm.program = vec![
+        0x60, 0x01, // PUSH1 1
+        0x60, 0x00, // PUSH1 0
+        0x55, // SSTORE
+        0x60, 0x02, // PUSH1 2
+        0x60, 0x00, // PUSH1 0
+        0x5D, // TSTORE
+        0x60, 0x00, // PUSH1 0
+        0x60, 0x00, // PUSH1 0
+        0xA0, // LOG0
+        0x60, 0x00, // PUSH1 0
+        0x60, 0x00, // PUSH1 0
+        0xFD, // REVERT
+    ];
+    assert!(my_evm.run().is_ok());
+    assert!(my_evm.stop_flag && my_evm.revert_flag);
+    let address = my_evm.address;
+    assert_eq!(my_evm.host.tload(address, U256::ZERO), U256::from(2));
+
+    my_evm.reset();
+    assert!(!my_evm.stop_flag && !my_evm.revert_flag);
+    assert_eq!(my_evm.gas_used, 0);
+    // transient storage and logs end with the transaction, storage stays
+    assert_eq!(my_evm.host.tload(address, U256::ZERO), U256::ZERO);
+    assert!(my_evm.host.logs.is_empty());
+    assert_eq!(my_evm.host.sload(address, U256::ZERO), U256::from(1));
+
+    // and the program runs again from the start
+    assert!(my_evm.run().is_ok());
+    assert!(my_evm.revert_flag);
+    assert_eq!(my_evm.host.logs.len(), 1);
+}
+
+#[test]
+fn test_unmetered_run_caps_memory() {
+    let mut my_evm = init_evm();
+    my_evm.program = vec![
+        0x60, 0x01, // PUSH1 1
+        0x64, 0x10, 0x00, 0x00, 0x00, 0x00, // PUSH5 2^36
+        0x52, // MSTORE
+    ];
+    my_evm.metered = false;
+    assert_eq!(
+        my_evm.run(),
+        Err(EvmError::MemoryLimitExceeded {
+            required: (1 << 36) + 32,
+            limit: UNMETERED_LIMIT
+        })
+    );
+    assert_eq!(my_evm.memory.memory.len(), 0);
+
+    // a limit of its own is kept
+    my_evm.reset();
+    my_evm.memory = Memory::with_limit(64);
+    assert_eq!(
+        my_evm.run(),
+        Err(EvmError::MemoryLimitExceeded {
+            required: (1 << 36) + 32,
+            limit: 64
+        })
+    );
+}
+
+#[test]
+fn test_metered_run_after_an_unmetered_one_has_no_cap() {
+    let mut my_evm = init_evm();
+    my_evm.program = vec![
+        0x60, 0x01, // PUSH1 1
+        0x64, 0x10, 0x00, 0x00, 0x00, 0x00, // PUSH5 2^36
+        0x52, // MSTORE
+    ];
+    my_evm.metered = false;
+    assert!(matches!(my_evm.run(), Err(EvmError::MemoryLimitExceeded { .. })));
+    assert_eq!(my_evm.memory.limit, None);
+
+    // metered, the same store is only out of gas, the cap was never the memory's own
+    my_evm.reset();
+    my_evm.metered = true;
+    assert_eq!(my_evm.memory_limit(), None);
+    assert_eq!(my_evm.run(), Err(EvmError::OutOfGas));
+}
+
+// a custom instruction: DOUBLE pops a value and pushes it twice over, 5 gas on top of its base gas
+fn double(vm: &mut EVM) -> Result<(), EvmError> {
+    vm.gas_dec(5)?;
+    let value = vm.stack.pop1();
+    vm.stack.push_unchecked(value * U256::from(2));
+    vm.pc += 1;
+    Ok(())
+}
+
+fn register_double(vm: &mut EVM) {
+    let info = OpcodeInfo::new("DOUBLE", 1, 1, 3, Fork::LATEST);
+    vm.register_opcode(0x0C, info, double).unwrap();
+}
+
+#[test]
+fn test_custom_opcode_runs() {
+    let mut my_evm = init_evm();
+    register_double(&mut my_evm);
+    my_evm.program = vec![
+        0x60, 0x15, // PUSH1 0x15
+        0x0C, // DOUBLE
+        0x0C, // DOUBLE
+    ];
+    assert!(my_evm.run().is_ok());
+    assert_eq!(my_evm.stack.items(), [U256::from(0x54)]);
+    assert_eq!(my_evm.gas_used, 3 + 2 * (3 + 5));
+
+    // and one instruction at a time
+    my_evm.reset();
+    assert_eq!(my_evm.step(), Ok(true));
+    assert_eq!(my_evm.step(), Ok(true));
+    assert_eq!(my_evm.stack.items(), [U256::from(0x2A)]);
+    assert_eq!(my_evm.pc, 3);
+}
+
+#[test]
+fn test_custom_opcode_checks_the_stack() {
+    let mut my_evm = init_evm();
+    register_double(&mut my_evm);
+    my_evm.program = vec![0x0C]; // DOUBLE on an empty stack
+    assert_eq!(my_evm.run(), Err(EvmError::StackUnderflow));
+    assert_eq!(my_evm.gas_used, 0);
+}
+
+#[test]
+fn test_custom_opcode_needs_a_free_byte() {
+    let mut my_evm = init_evm();
+    let info = OpcodeInfo::new("DOUBLE", 1, 1, 3, Fork::LATEST);
+    assert_eq!(
+        my_evm.register_opcode(0x01, info, double),
+        Err(EvmError::OpcodeAlreadyDefined {
+            opcode: "0x01".to_string()
+        })
+    );
+    register_double(&mut my_evm);
+    assert!(my_evm.register_opcode(0x0C, info, double).is_err());
+    assert_eq!(my_evm.opcode_table().name(0x0C), "DOUBLE");
+}
+
+// writes down every hook it sees
+#[derive(Default)]
+struct Recorder {
+    events: Vec<String>,
+}
+
+impl<H: Host> Inspector<H> for Recorder {
+    fn step(&mut self, vm: &EVM<H>) {
+        self.events.push(format!("step {} {}", vm.pc, vm.stack.len()));
+    }
+
+    fn step_end(&mut self, vm: &EVM<H>, result: &Result<(), EvmError>) {
+        self.events.push(format!("end {} {:?}", vm.pc, result));
+    }
+
+    fn sstore(&mut self, _: &EVM<H>, _: Address, key: U256, old: U256, new: U256) {
+        self.events.push(format!("sstore {} {} -> {}", key, old, new));
+    }
+
+    fn log(&mut self, _: &EVM<H>, log: &Log) {
+        self.events.push(format!("log {:?}", log.data));
+    }
+
+    fn selfdestruct(&mut self, _: &EVM<H>, _: Address, target: Address, value: U256) {
+        self.events.push(format!("selfdestruct {} {}", target, value));
+    }
+}
+
+#[test]
+fn test_inspector_sees_every_step_and_state_change() {
+    let mut my_evm = EVM::new(Address::ZERO, vec![], 100_000, U256::ZERO, vec![]);
+    my_evm.program = vec![
+        0x60, 0x07, // PUSH1 7
+        0x60, 0x01, // PUSH1 1
+        0x55, // SSTORE
+        0x60, 0x00, // PUSH1 0
+        0x60, 0x00, // PUSH1 0
+        0xA0, // LOG0
+        0x60, 0x22, // PUSH1 0x22
+        0xFF, // SELFDESTRUCT
+    ];
+    my_evm.set_inspector(Recorder::default());
+    assert!(my_evm.run().is_ok());
+    let recorder: Recorder = my_evm.take_inspector().unwrap();
+    assert_eq!(
+        recorder.events,
+        [
+            "step 0 0",
+            "end 2 Ok(())",
+            "step 2 1",
+            "end 4 Ok(())",
+            "step 4 2",
+            "sstore 1 0 -> 7",
+            "end 5 Ok(())",
+            "step 5 0",
+            "end 7 Ok(())",
+            "step 7 1",
+            "end 9 Ok(())",
+            "step 9 2",
+            "log []",
+            "end 10 Ok(())",
+            "step 10 0",
+            "end 12 Ok(())",
+            "step 12 1",
+            "selfdestruct 0x0000000000000000000000000000000000000022 0",
+            "end 12 Ok(())",
+        ]
+    );
+    assert!(my_evm.inspector::<Recorder>().is_none());
+}
+
+#[test]
+fn test_inspector_sees_the_failing_step() {
+    let mut my_evm = init_evm();
+    my_evm.program = vec![0x01]; // ADD on an empty stack
+    my_evm.set_inspector(Recorder::default());
+    assert_eq!(my_evm.run(), Err(EvmError::StackUnderflow));
+    let recorder = my_evm.inspector::<Recorder>().unwrap();
+    assert_eq!(recorder.events, ["step 0 0", "end 0 Err(StackUnderflow)"]);
+}
+
+#[test]
+fn test_abi_calldata_and_return_data() {
+    let add = Function::parse("add(uint256 a, uint256 b) returns (uint256)").unwrap();
+    let calldata = add.encode_input(&[AbiValue::uint(40u64), AbiValue::uint(2u64)]).unwrap();
+    let program = vec![
+        0x60, 0x24, 0x35, // PUSH1 36, CALLDATALOAD (b)
+        0x60, 0x04, 0x35, // PUSH1 4, CALLDATALOAD (a)
+        0x01, // ADD
+        0x60, 0x00, 0x52, // PUSH1 0, MSTORE
+        0x60, 0x20, 0x60, 0x00, 0xF3, // RETURN the word
+    ];
+    let mut my_evm = EVM::new(Address::ZERO, program, 100_000, U256::ZERO, calldata);
+    my_evm.run().unwrap();
+    assert_eq!(add.decode_output(&my_evm.output).unwrap(), vec![AbiValue::uint(42u64)]);
+}
+
+#[test]
+fn test_abi_decodes_a_custom_error_revert() {
+    let abi =
+        Abi::parse(["error InsufficientBalance(uint256 available, uint256 required)"]).unwrap();
+    let data = abi.errors[0].encode(&[AbiValue::uint(1u64), AbiValue::uint(5u64)]).unwrap();
+    let program = vec![
+        0x36, 0x60, 0x00, 0x60, 0x00, 0x37, // CALLDATACOPY the whole calldata to 0
+        0x36, 0x60, 0x00, 0xFD, // REVERT with it
+    ];
+    let mut my_evm = EVM::new(Address::ZERO, program, 100_000, U256::ZERO, data);
+    my_evm.run().unwrap();
+    assert!(my_evm.revert_flag);
+    let revert = abi.decode_revert(&my_evm.output);
+    assert!(matches!(revert, Revert::Custom { .. }));
+    assert_eq!(revert.to_string(), "InsufficientBalance(1, 5)");
+}
@@ -0,0 +1,293 @@
+mod common;
+
+use alloy_primitives::{Address, U256};
+
+use evm::{
+    evm::{EVM, EvmError, ReturnFrame},
+    host::InMemoryHost,
+    memory::Memory,
+    opcodes::info::opcode_info,
+    stack::Stack,
+};
+
+use common::container;
+
+// everything an instruction could change, apart from the gas itself
+#[derive(Debug, PartialEq)]
+struct Snapshot {
+    pc: usize,
+    stack: Stack,
+    memory: Memory,
+    host: InMemoryHost,
+    return_data: Vec<u8>,
+    refund: u64,
+    code_section: usize,
+    return_stack: Vec<ReturnFrame>,
+    stop_flag: bool,
+    revert_flag: bool,
+}
+
+fn snapshot(vm: &EVM) -> Snapshot {
+    // pricing SSTORE reads the slot first, what the cache picked up on the way isn't a change
+    let mut host = vm.host.clone();
+    host.db.accounts.retain(|_, account| account.exists);
+    Snapshot {
+        pc: vm.pc,
+        stack: vm.stack.clone(),
+        memory: vm.memory.clone(),
+        host,
+        return_data: vm.return_data.clone(),
+        refund: vm.refund,
+        code_section: vm.code_section,
+        return_stack: vm.return_stack.clone(),
+        stop_flag: vm.stop_flag,
+        revert_flag: vm.revert_flag,
+    }
+}
+
+// one instruction under test: `setup` steps prepare its arguments, the next step runs it
+struct Case {
+    name: &'static str,
+    program: Vec<u8>,
+    setup: usize,
+    prepare: fn(&mut EVM),
+}
+
+// PUSH32 for every argument, the first one ends up on top of the stack
+fn push_args(args: &[u64]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for arg in args.iter().rev() {
+        bytes.push(0x7F);
+        bytes.extend_from_slice(&U256::from(*arg).to_be_bytes::<32>());
+    }
+    bytes
+}
+
+fn legacy(name: &'static str, args: &[u64], code: &[u8]) -> Case {
+    let mut program = push_args(args);
+    program.extend_from_slice(code);
+    Case {
+        name,
+        program,
+        setup: args.len(),
+        prepare: |_| {},
+    }
+}
+
+// a single non-returning code section followed by STOP, `max_stack` must match what validation computes
+fn eof(name: &'static str, args: &[u64], code: &[u8], max_stack: u16) -> Case {
+    let mut section = push_args(args);
+    section.extend_from_slice(code);
+    section.push(0x00);
+    Case {
+        name,
+        program: container(&[(0, 0x80, max_stack)], &[&section], &[0xAA; 40]),
+        setup: args.len(),
+        prepare: |_| {},
+    }
+}
+
+fn cases() -> Vec<Case> {
+    let mut cases = vec![
+        legacy("ADD", &[3, 7], &[0x01]),
+        legacy("MUL", &[3, 7], &[0x02]),
+        legacy("SUB", &[3, 7], &[0x03]),
+        legacy("DIV", &[3, 7], &[0x04]),
+        legacy("SDIV", &[3, 7], &[0x05]),
+        legacy("MOD", &[3, 7], &[0x06]),
+        legacy("SMOD", &[3, 7], &[0x07]),
+        legacy("ADDMOD", &[3, 7, 5], &[0x08]),
+        legacy("MULMOD", &[3, 7, 5], &[0x09]),
+        legacy("EXP", &[2, 300], &[0x0A]),
+        legacy("SIGNEXTEND", &[0, 0xFF], &[0x0B]),
+        legacy("LT", &[3, 7], &[0x10]),
+        legacy("GT", &[3, 7], &[0x11]),
+        legacy("SLT", &[3, 7], &[0x12]),
+        legacy("SGT", &[3, 7], &[0x13]),
+        legacy("EQ", &[3, 7], &[0x14]),
+        legacy("ISZERO", &[3], &[0x15]),
+        legacy("AND", &[3, 7], &[0x16]),
+        legacy("OR", &[3, 7], &[0x17]),
+        legacy("XOR", &[3, 7], &[0x18]),
+        legacy("NOT", &[3], &[0x19]),
+        legacy("BYTE", &[31, 7], &[0x1A]),
+        legacy("SHL", &[1, 7], &[0x1B]),
+        legacy("SHR", &[1, 7], &[0x1C]),
+        legacy("SAR", &[1, 7], &[0x1D]),
+        legacy("SHA3", &[0, 64], &[0x20]),
+        legacy("ADDRESS", &[], &[0x30]),
+        legacy("BALANCE", &[0], &[0x31]),
+        legacy("ORIGIN", &[], &[0x32]),
+        legacy("CALLER", &[], &[0x33]),
+        legacy("CALLVALUE", &[], &[0x34]),
+        legacy("CALLDATALOAD", &[0], &[0x35]),
+        legacy("CALLDATASIZE", &[], &[0x36]),
+        legacy("CALLDATACOPY", &[0, 0, 64], &[0x37]),
+        legacy("CODESIZE", &[], &[0x38]),
+        legacy("CODECOPY", &[0, 0, 64], &[0x39]),
+        legacy("GASPRICE", &[], &[0x3A]),
+        legacy("EXTCODESIZE", &[0], &[0x3B]),
+        legacy("EXTCODECOPY", &[0, 0, 0, 64], &[0x3C]),
+        legacy("RETURNDATASIZE", &[], &[0x3D]),
+        Case {
+            prepare: |vm| vm.return_data = vec![0x22; 64],
+            ..legacy("RETURNDATACOPY", &[0, 0, 64], &[0x3E])
+        },
+        legacy("EXTCODEHASH", &[0], &[0x3F]),
+        Case {
+            prepare: |vm| vm.host.env.number = 10,
+            ..legacy("BLOCKHASH", &[9], &[0x40])
+        },
+        legacy("COINBASE", &[], &[0x41]),
+        legacy("TIMESTAMP", &[], &[0x42]),
+        legacy("NUMBER", &[], &[0x43]),
+        legacy("DIFFICULTY/PREVRANDAO", &[], &[0x44]),
+        legacy("GASLIMIT", &[], &[0x45]),
+        legacy("CHAINID", &[], &[0x46]),
+        legacy("SELFBALANCE", &[], &[0x47]),
+        legacy("BASEFEE", &[], &[0x48]),
+        legacy("POP", &[3], &[0x50]),
+        legacy("MLOAD", &[64], &[0x51]),
+        legacy("MSTORE", &[64, 7], &[0x52]),
+        legacy("MSTORE8", &[64, 7], &[0x53]),
+        legacy("SLOAD", &[1], &[0x54]),
+        legacy("SSTORE", &[1, 7], &[0x55]),
+        // the JUMPDEST sits right behind the one PUSH32 and the jump itself
+        legacy("JUMP", &[34], &[0x56, 0x5B]),
+        legacy("JUMPI", &[67, 1], &[0x57, 0x5B]),
+        legacy("PC", &[], &[0x58]),
+        legacy("JUMPDEST", &[], &[0x5B]),
+        legacy("TLOAD", &[1], &[0x5C]),
+        legacy("TSTORE", &[1, 7], &[0x5D]),
+        legacy("LOG0", &[0, 64], &[0xA0]),
+        legacy("LOG1", &[0, 64, 1], &[0xA1]),
+        legacy("LOG2", &[0, 64, 1, 2], &[0xA2]),
+        legacy("LOG3", &[0, 64, 1, 2, 3], &[0xA3]),
+        legacy("LOG4", &[0, 64, 1, 2, 3, 4], &[0xA4]),
+        legacy("RETURN", &[0, 64], &[0xF3]),
+        legacy("REVERT", &[0, 64], &[0xFD]),
+        legacy("SELFDESTRUCT", &[7], &[0xFF]),
+        eof("RJUMP", &[], &[0xE0, 0x00, 0x00], 0),
+        eof("RJUMPI", &[1], &[0xE1, 0x00, 0x00], 1),
+        eof("RJUMPV", &[0], &[0xE2, 0x00, 0x00, 0x00], 1),
+        eof("DUPN", &[1], &[0xE6, 0x00], 2),
+        eof("SWAPN", &[1, 2], &[0xE7, 0x00], 2),
+        eof("EXCHANGE", &[1, 2, 3], &[0xE8, 0x00], 3),
+        eof("DATALOAD", &[8], &[0xD0], 1),
+        eof("DATALOADN", &[], &[0xD1, 0x00, 0x08], 1),
+        eof("DATASIZE", &[], &[0xD2], 1),
+        eof("DATACOPY", &[0, 0, 64], &[0xD3], 3),
+        eof("EXTCALL", &[0, 0, 64, 1], &[0xF8], 4),
+        Case {
+            name: "CALLF",
+            program: container(
+                &[(0, 0x80, 0), (0, 0, 0)],
+                &[&[0xE3, 0x00, 0x01, 0x00], &[0xE4]],
+                &[],
+            ),
+            setup: 0,
+            prepare: |_| {},
+        },
+        Case {
+            name: "RETF",
+            program: container(
+                &[(0, 0x80, 0), (0, 0, 0)],
+                &[&[0xE3, 0x00, 0x01, 0x00], &[0xE4]],
+                &[],
+            ),
+            setup: 1,
+            prepare: |_| {},
+        },
+        Case {
+            name: "JUMPF",
+            program: container(
+                &[(0, 0x80, 0), (0, 0x80, 0)],
+                &[&[0xE5, 0x00, 0x01], &[0x00]],
+                &[],
+            ),
+            setup: 0,
+            prepare: |_| {},
+        },
+    ];
+    for n in 1..=32u8 {
+        cases.push(Case {
+            name: "PUSH",
+            program: [vec![0x5F + n], vec![0x01; n as usize]].concat(),
+            setup: 0,
+            prepare: |_| {},
+        });
+    }
+    for n in 1..=16u8 {
+        cases.push(legacy("DUP", &vec![1; n as usize], &[0x7F + n]));
+        cases.push(legacy("SWAP", &vec![1; n as usize + 1], &[0x8F + n]));
+    }
+    cases
+}
+
+// runs the setup with plenty of gas, leaving the EVM right before the instruction under test
+fn prepared_evm(case: &Case) -> EVM {
+    let mut vm = EVM::new(Address::ZERO, case.program.clone(), 1_000_000, U256::ZERO, vec![0x33; 16]);
+    // an EOF container is loaded on the first step, which moves pc, so load it upfront
+    vm.load_container().unwrap();
+    (case.prepare)(&mut vm);
+    for _ in 0..case.setup {
+        assert_eq!(vm.step(), Ok(true), "{}: setup failed", case.name);
+    }
+    vm
+}
+
+#[test]
+fn test_out_of_gas_leaves_state_untouched() {
+    for case in cases() {
+        let mut vm = prepared_evm(&case);
+        let gas_before = vm.gas;
+        assert!(vm.step().is_ok(), "{}: failed with enough gas", case.name);
+        let cost = gas_before - vm.gas;
+        assert!(cost > 0, "{}: costs nothing", case.name);
+
+        let mut vm = prepared_evm(&case);
+        vm.gas = cost - 1;
+        let before = snapshot(&vm);
+        assert_eq!(vm.step(), Err(EvmError::OutOfGas), "{}", case.name);
+        assert_eq!(snapshot(&vm), before, "{}: state changed", case.name);
+    }
+}
+
+#[test]
+fn test_exact_gas_is_enough() {
+    for case in cases() {
+        let mut vm = prepared_evm(&case);
+        let gas_before = vm.gas;
+        vm.step().unwrap();
+        let cost = gas_before - vm.gas;
+
+        let mut vm = prepared_evm(&case);
+        vm.gas = cost;
+        assert!(vm.step().is_ok(), "{}", case.name);
+        assert_eq!(vm.gas, 0, "{}", case.name);
+    }
+}
+
+// instructions whose cost depends on their arguments or on the state
+const DYNAMIC_COST: [&str; 24] = [
+    "SHA3", "BALANCE", "CALLDATACOPY", "CODECOPY", "EXTCODESIZE", "EXTCODECOPY", "RETURNDATACOPY",
+    "EXTCODEHASH", "MLOAD", "MSTORE", "MSTORE8", "SLOAD", "SSTORE", "LOG0", "LOG1", "LOG2", "LOG3",
+    "LOG4", "DATACOPY", "EXTCALL", "RETURN", "REVERT", "EXP", "SELFDESTRUCT",
+];
+
+#[test]
+fn test_static_costs_match_the_opcode_table() {
+    for case in cases() {
+        let mut vm = prepared_evm(&case);
+        let opcode = vm.program[vm.pc];
+        let info = opcode_info(opcode).unwrap();
+        let gas_before = vm.gas;
+        vm.step().unwrap();
+        let cost = gas_before - vm.gas;
+        // memory expansion, cold access and per-byte or per-topic costs come on top of the base
+        assert!(cost >= info.base_gas, "{}", info.mnemonic);
+        if !DYNAMIC_COST.contains(&info.mnemonic) {
+            assert_eq!(cost, info.base_gas, "{}", info.mnemonic);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use alloy_primitives::{Address, U256};
+
+use evm::{
+    evm::EVM,
+    host::Host,
+    inspector::{CallInputs, CallOutcome, CallStatus, Inspector},
+    precompile::{PrecompileContext, PrecompileOutput},
+    tracing::{
+        call_tracer::{CallKind, CallTracer},
+        gas_profiler::{GasCategory, GasProfiler},
+    },
+};
+
+use common::call_precompile_program;
+
+fn init_evm(program: Vec<u8>) -> EVM {
+    EVM::new(Address::ZERO, program, 10000, U256::ZERO, vec![])
+}
+
+#[derive(Default)]
+struct CallRecorder {
+    calls: Vec<(CallInputs, Option<CallOutcome>)>,
+}
+
+impl<H: Host> Inspector<H> for CallRecorder {
+    fn call(&mut self, _: &EVM<H>, inputs: &CallInputs) {
+        self.calls.push((inputs.clone(), None));
+    }
+
+    fn call_end(&mut self, _: &EVM<H>, _: &CallInputs, outcome: &CallOutcome) {
+        self.calls.last_mut().unwrap().1 = Some(outcome.clone());
+    }
+}
+
+#[test]
+fn test_eof_extcall_is_inspected() {
+    let revert = |_: &[u8], _: u64, _: &mut PrecompileContext<'_>| {
+        Ok(PrecompileOutput::revert(10, vec![0x01]))
+    };
+    let mut my_evm = init_evm(call_precompile_program());
+    my_evm.register_precompile(Address::with_last_byte(0x42), revert);
+    my_evm.set_inspector(CallRecorder::default());
+    assert!(my_evm.run().is_ok());
+
+    let recorder = my_evm.inspector::<CallRecorder>().unwrap();
+    let (inputs, outcome) = &recorder.calls[0];
+    assert_eq!(inputs.target, Address::with_last_byte(0x42));
+    assert_eq!(inputs.input, vec![0xAB]);
+    let before_call = 10000 - (3 + 3 + 6 + 3 * 4 + 100);
+    assert_eq!(inputs.gas_limit, before_call - before_call / 64);
+    assert_eq!(
+        outcome.as_ref().unwrap(),
+        &CallOutcome {
+            status: CallStatus::Revert,
+            output: vec![0x01],
+            gas_used: 10,
+        }
+    );
+}
+
+#[test]
+fn test_eof_extcall_in_the_call_tree() {
+    let echo = |input: &[u8], _: u64, _: &mut PrecompileContext<'_>| {
+        Ok(PrecompileOutput::new(50, input.to_vec()))
+    };
+    let mut my_evm = init_evm(call_precompile_program());
+    my_evm.register_precompile(Address::with_last_byte(0x42), echo);
+    my_evm.set_inspector(CallTracer::default());
+    let result = my_evm.run();
+    assert!(result.is_ok());
+
+    let tree = my_evm.take_inspector::<CallTracer>().unwrap().into_frame(&my_evm, &result);
+    assert_eq!(tree.kind, CallKind::Call);
+    assert_eq!(tree.gas_used, my_evm.gas_used);
+    assert_eq!(tree.calls.len(), 1);
+    let call = &tree.calls[0];
+    assert_eq!(call.kind, CallKind::ExtCall);
+    assert_eq!(call.to, Some(Address::with_last_byte(0x42)));
+    assert_eq!(call.input.to_vec(), vec![0xAB]);
+    assert_eq!(call.output.to_vec(), vec![0xAB]);
+    assert_eq!(call.gas_used, 50);
+    assert_eq!(call.error, None);
+}
+
+#[test]
+fn test_eof_extcall_callee_gas_in_the_profile() {
+    let burn = |_: &[u8], _: u64, _: &mut PrecompileContext<'_>| {
+        Ok(PrecompileOutput::new(500, vec![]))
+    };
+    let mut my_evm = init_evm(call_precompile_program());
+    my_evm.register_precompile(Address::with_last_byte(0x42), burn);
+    my_evm.set_inspector(GasProfiler::default());
+    assert!(my_evm.run().is_ok());
+
+    let profiler = my_evm.take_inspector::<GasProfiler>().unwrap();
+    assert_eq!(profiler.total(), my_evm.gas_used);
+    // EXTCALL only pays the warm access, precompiles are warm, the callee's 500 sit on its own frame
+    assert_eq!(profiler.by_opcode()["EXTCALL"].gas, 100);
+    assert_eq!(profiler.by_category()[&GasCategory::Calls], 100 + 500);
+    let root = Address::ZERO.to_string();
+    let callee = Address::with_last_byte(0x42).to_string();
+    assert_eq!(profiler.by_path()[&format!("{root};{callee}")], 500);
+    assert_eq!(profiler.by_path()[&format!("{root};EXTCALL")], 100);
+}
+